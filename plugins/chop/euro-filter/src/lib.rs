@@ -8,7 +8,7 @@ mod filter;
 #[derive(Params, Default, Clone, Debug)]
 struct EuroFilterChopParams {
     #[param(label = "Cutoff Frequency (Hz)", page = "EuroFilter")]
-    min_cutoff: f64,
+    min_cutoff: ModulatedParam<f64>,
     #[param(label = "Speed Coefficient", page = "EuroFilter")]
     beta: f64,
     #[param(label = "Slope Cutoff Frequency (Hz)", page = "EuroFilter")]
@@ -42,16 +42,22 @@ impl Op for EuroFilterChop {
 }
 
 impl Chop for EuroFilterChop {
-    fn channel_name(&self, index: usize, _inputs: &OperatorInputs<ChopInput>) -> String {
-        format!("chan{}", index)
+    fn channel_name(&self, index: usize, inputs: &OperatorInputs<ChopInput>) -> String {
+        match inputs.input(0) {
+            Some(input) if index < input.num_channels() => input.channel_name(index).to_string(),
+            _ => format!("chan{}", index),
+        }
     }
 
     fn execute(&mut self, output: &mut ChopOutput, inputs: &OperatorInputs<ChopInput>) {
         if let Some(input) = &inputs.input(0) {
+            let rate = input.sample_rate();
+
+            let min_cutoff = &self.params.min_cutoff;
             for filter in &mut self.filters {
                 filter.change_input(
-                    input.num_samples() as f64,
-                    self.params.min_cutoff,
+                    rate,
+                    min_cutoff.at(0),
                     self.params.beta,
                     self.params.d_cutoff,
                 );
@@ -59,16 +65,25 @@ impl Chop for EuroFilterChop {
 
             for _ in self.filters.len()..input.num_channels() {
                 self.filters.push(OneEuroImpl::new(
-                    input.num_samples() as f64,
-                    self.params.min_cutoff,
+                    rate,
+                    min_cutoff.at(0),
                     self.params.beta,
                     self.params.d_cutoff,
                 ));
             }
 
+            let modulated = min_cutoff.is_modulated();
             let mut input_sample_idx = 0;
             for i in 0..output.num_channels() {
-                for j in 0..output.num_samples() {
+                for (j, min_cutoff) in min_cutoff.samples(output.num_samples()).enumerate() {
+                    if modulated {
+                        self.filters[i].change_input(
+                            rate,
+                            min_cutoff,
+                            self.params.beta,
+                            self.params.d_cutoff,
+                        );
+                    }
                     input_sample_idx = (input_sample_idx + 1) % input.num_samples();
                     output[i][j] = self.filters[i].filter(input[i][input_sample_idx] as f64) as f32;
                 }
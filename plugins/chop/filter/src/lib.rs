@@ -68,9 +68,33 @@ impl Chop for FilterChop {
         }
     }
 
-    fn channel_name(&self, index: usize, _inputs: &OperatorInputs<ChopInput>) -> String {
-        format!("chan{}", index)
+    fn channel_name(&self, index: usize, inputs: &OperatorInputs<ChopInput>) -> String {
+        match inputs.input(0) {
+            Some(input) if index < input.num_channels() => input.channel_name(index).to_string(),
+            _ => format!("chan{}", index),
+        }
     }
 }
 
 chop_plugin!(FilterChop);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use td_rs_testing::*;
+
+    #[test]
+    fn scales_and_offsets_input() {
+        let mut harness = ChopHarness::new::<FilterChop>();
+        harness.set_float("Scale", 2.0);
+        harness.set_float("Offset", 0.5);
+        harness.set_input_chop(
+            0,
+            FakeChop::new("/project1/in").channel("chan1", [1.0, 2.0, 3.0]),
+        );
+
+        let out = harness.cook();
+        assert_eq!(out.num_channels(), 1);
+        assert_eq!(out.channel("chan1"), Some(&[2.5, 4.5, 6.5][..]));
+    }
+}
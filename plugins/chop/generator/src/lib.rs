@@ -18,7 +18,7 @@ struct GeneratorChopParams {
     num_channels: u32,
     #[param(label = "Apply Scale", page = "Generator")]
     apply_scale: bool,
-    #[param(label = "Scale", page = "Generator")]
+    #[param(label = "Scale", page = "Generator", enable_if = "apply_scale")]
     scale: f32,
     #[param(label = "Operation", page = "Generator")]
     operation: Operation,
@@ -48,6 +48,7 @@ impl OpNew for GeneratorChop {
 impl OpInfo for GeneratorChop {
     const OPERATOR_LABEL: &'static str = "Basic Generator";
     const OPERATOR_TYPE: &'static str = "Basicgenerator";
+    const MAX_INPUTS: usize = 4;
 }
 
 impl Op for GeneratorChop {
@@ -58,11 +59,34 @@ impl Op for GeneratorChop {
 
 impl Chop for GeneratorChop {
     #[tracing::instrument]
-    fn execute(&mut self, output: &mut ChopOutput, inputs: &OperatorInputs<ChopInput>) {
-        let params = inputs.params();
-        params.enable_param("Scale", self.params.apply_scale);
-
+    fn try_execute(
+        &mut self,
+        output: &mut ChopOutput,
+        inputs: &OperatorInputs<ChopInput>,
+    ) -> CookResult {
         tracing::info!("Executing chop with params: {:?}", self.params);
+        let scale = if self.params.apply_scale {
+            self.params.scale
+        } else {
+            1.0
+        };
+        if inputs.input(0).is_some() {
+            // Combine the channels of every input that match those of the first
+            let matched = ChannelMatcher::new().match_inputs(inputs)?;
+            for (i, channel) in matched.iter().enumerate() {
+                for j in 0..output.num_samples() {
+                    let mut values = channel.samples(j);
+                    let first = values.next().unwrap_or_default();
+                    let cur_value = match self.params.operation {
+                        Operation::Add => values.fold(first, |acc, v| acc + v),
+                        Operation::Multiply => values.fold(first, |acc, v| acc * v),
+                        Operation::Power => values.fold(first, f32::powf),
+                    };
+                    output[i][j] = cur_value * scale;
+                }
+            }
+            return Ok(());
+        }
         for i in 0..output.num_channels() {
             for j in 0..output.num_samples() {
                 let cur_value = match self.params.operation {
@@ -70,15 +94,11 @@ impl Chop for GeneratorChop {
                     Operation::Multiply => (i as f32) * (j as f32),
                     Operation::Power => (i as f32).powf(j as f32),
                 };
-                let scale = if self.params.apply_scale {
-                    self.params.scale
-                } else {
-                    1.0
-                };
                 let cur_value = cur_value * scale;
                 output[i][j] = cur_value;
             }
         }
+        Ok(())
     }
 
     fn general_info(&self, _inputs: &OperatorInputs<ChopInput>) -> ChopGeneralInfo {
@@ -90,11 +110,23 @@ impl Chop for GeneratorChop {
         }
     }
 
-    fn channel_name(&self, index: usize, _inputs: &OperatorInputs<ChopInput>) -> String {
-        format!("chan{}", index)
+    fn channel_name(&self, index: usize, inputs: &OperatorInputs<ChopInput>) -> String {
+        match inputs.input(0) {
+            Some(input) => input.channel_name(index).to_string(),
+            None => format!("chan{}", index),
+        }
     }
 
-    fn output_info(&self, _inputs: &OperatorInputs<ChopInput>) -> Option<ChopOutputInfo> {
+    fn output_info(&self, inputs: &OperatorInputs<ChopInput>) -> Option<ChopOutputInfo> {
+        // The first input decides the channels and range of the match
+        if let Some(input) = inputs.input(0) {
+            return Some(ChopOutputInfo {
+                num_channels: input.num_channels() as u32,
+                num_samples: input.num_samples() as u32,
+                sample_rate: input.sample_rate() as f32,
+                start_index: input.start_index() as usize,
+            });
+        }
         Some(ChopOutputInfo {
             num_channels: self.params.num_channels,
             num_samples: self.params.length,
@@ -13,8 +13,12 @@ enum Operation {
 
 #[derive(Params, Default, Clone, Debug, Eq, PartialEq)]
 struct MonomeGridParams {
-    #[param(label = "Prefix", page = "Grid", default = "/touchdesigner")]
-    prefix: String,
+    #[param(
+        label = "Prefix",
+        page = "Grid",
+        options = ["/touchdesigner", "/monome"]
+    )]
+    prefix: StringMenuParam,
     #[param(label = "Hold", page = "Grid")]
     hold: bool,
 }
@@ -23,7 +27,6 @@ struct MonomeGridParams {
 #[derive(Debug)]
 pub struct MonomeGrid {
     params: MonomeGridParams,
-    prev_params: MonomeGridParams,
     device: Option<Monome>,
     grid: [bool; 128],
 }
@@ -33,10 +36,9 @@ impl OpNew for MonomeGrid {
     fn new(_info: NodeInfo) -> Self {
         Self {
             params: MonomeGridParams {
-                prefix: "/touchdesigner".to_string(),
+                prefix: StringMenuParam::default().with_value("/touchdesigner"),
                 hold: false,
             },
-            prev_params: Default::default(),
             device: None,
             grid: [false; 128],
         }
@@ -52,13 +54,19 @@ impl Op for MonomeGrid {
     fn params_mut(&mut self) -> Option<Box<&mut dyn OperatorParams>> {
         Some(Box::new(&mut self.params))
     }
+
+    fn on_params_changed(&mut self, changed: &ParamsChanged) {
+        // Reconnect on the next cook
+        if changed.contains("Prefix") {
+            self.device = None;
+        }
+    }
 }
 
 impl Chop for MonomeGrid {
     fn execute(&mut self, output: &mut ChopOutput, inputs: &OperatorInputs<ChopInput>) {
-        if self.params != self.prev_params || self.device.is_none() {
-            self.prev_params = self.params.clone();
-            let device = match Monome::new(&self.params.prefix) {
+        if self.device.is_none() {
+            let device = match Monome::new(self.params.prefix.as_str()) {
                 Ok(device) => device,
                 Err(err) => {
                     self.set_error(&format!("Error connecting to monome: {}", err));
@@ -0,0 +1,156 @@
+use std::f64::consts::TAU;
+
+use td_rs_chop::*;
+use td_rs_derive::{InfoChop, InfoDat, Param, Params};
+
+#[derive(Param, Default, Clone, Copy, Debug)]
+enum Waveform {
+    #[default]
+    Sine,
+    Square,
+    Saw,
+    Triangle,
+}
+
+impl Waveform {
+    /// The value at a phase between zero and one.
+    fn at(self, phase: f64) -> f64 {
+        match self {
+            Waveform::Sine => (phase * TAU).sin(),
+            Waveform::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Waveform::Saw => phase * 2.0 - 1.0,
+            Waveform::Triangle => 1.0 - (phase * 4.0 - 2.0).abs(),
+        }
+    }
+}
+
+#[derive(Params, Clone, Debug)]
+struct OscillatorChopParams {
+    #[param(label = "Waveform", page = "Oscillator")]
+    waveform: Waveform,
+    #[param(label = "Frequency", page = "Oscillator", min = 0.0, max = 2000.0)]
+    frequency: f64,
+    #[param(label = "Amplitude", page = "Oscillator", max = 1.0)]
+    amplitude: f64,
+    #[param(label = "Channels", page = "Oscillator", min = 1.0, max = 16.0)]
+    channels: u32,
+    #[param(label = "Spread", page = "Oscillator", max = 1.0)]
+    spread: f64,
+    #[param(label = "Sample Rate", page = "Oscillator", min = 1.0, max = 96000.0)]
+    sample_rate: f32,
+}
+
+/// Shown in the Info CHOP and Info DAT of the operator.
+#[derive(InfoChop, InfoDat, Default, Debug)]
+struct OscillatorInfo {
+    samples: u64,
+    /// Samples skipped because frames were dropped.
+    skipped_samples: u64,
+    /// Times the timeline jumped back.
+    rewinds: u32,
+}
+
+/// A bank of oscillators whose phase carries over from one cook to the next.
+pub struct OscillatorChop {
+    params: OscillatorChopParams,
+    timeslice: Timeslice<f64>,
+    info: OscillatorInfo,
+}
+
+impl OpNew for OscillatorChop {
+    fn new(_info: NodeInfo) -> Self {
+        Self {
+            params: OscillatorChopParams {
+                waveform: Waveform::Sine,
+                frequency: 440.0,
+                amplitude: 1.0,
+                channels: 1,
+                spread: 0.0,
+                sample_rate: 44100.0,
+            },
+            timeslice: Timeslice::new(),
+            info: OscillatorInfo::default(),
+        }
+    }
+}
+
+impl OpInfo for OscillatorChop {
+    const OPERATOR_TYPE: &'static str = "Oscillator";
+    const OPERATOR_LABEL: &'static str = "Oscillator";
+    const MAX_INPUTS: usize = 1;
+}
+
+impl Op for OscillatorChop {
+    fn params_mut(&mut self) -> Option<Box<&mut dyn OperatorParams>> {
+        Some(Box::new(&mut self.params))
+    }
+
+    fn info_chop(&self) -> Option<&dyn InfoChop> {
+        Some(&self.info)
+    }
+
+    fn info_dat(&self) -> Option<&dyn InfoDat> {
+        Some(&self.info)
+    }
+}
+
+impl TimesliceChop for OscillatorChop {
+    /// The phase of the oscillator of each channel.
+    type State = f64;
+
+    fn timeslice(&mut self) -> &mut Timeslice<f64> {
+        &mut self.timeslice
+    }
+
+    fn num_channels(&self, _inputs: &OperatorInputs<ChopInput>) -> usize {
+        self.params.channels.max(1) as usize
+    }
+
+    fn sample_rate(&self, _inputs: &OperatorInputs<ChopInput>) -> f32 {
+        self.params.sample_rate
+    }
+
+    fn cook_timeslice(
+        &mut self,
+        range: &SampleRange,
+        phases: &mut [f64],
+        output: &mut ChopOutput,
+        inputs: &OperatorInputs<ChopInput>,
+    ) -> CookResult {
+        self.info.samples += range.len as u64;
+        self.info.skipped_samples += range.skipped();
+        if range.continuity == Continuity::Rewind {
+            self.info.rewinds += 1;
+        }
+
+        let params = &self.params;
+        let period = range.sample_period();
+        // The first channel of the input modulates the frequency, in Hz
+        let modulation = inputs
+            .input(0)
+            .filter(|input| input.num_channels() > 0)
+            .map(|input| range.input_channel(input, 0));
+        for (i, phase) in phases.iter_mut().enumerate() {
+            // Each channel is detuned by a fraction of the base frequency
+            let detune = 1.0 + params.spread * i as f64;
+            // Catch up on samples skipped by dropped frames
+            let skipped = range.skipped() as f64 * period;
+            *phase = (*phase + params.frequency * detune * skipped).rem_euclid(1.0);
+            for (j, sample) in output.channel_mut(i).iter_mut().enumerate() {
+                let offset = modulation.as_ref().map_or(0.0, |m| m[j] as f64);
+                let frequency = (params.frequency + offset) * detune;
+                *sample = (params.waveform.at(*phase) * params.amplitude) as f32;
+                *phase = (*phase + frequency * period).rem_euclid(1.0);
+            }
+        }
+        Ok(())
+    }
+}
+
+chop_plugin!(OscillatorChop);
@@ -77,12 +77,12 @@ impl Op for PythonChop {
         Some(Box::new(&mut self.params))
     }
 
-    fn info_dat(&self) -> Option<Box<&dyn InfoDat>> {
-        Some(Box::new(self))
+    fn info_dat(&self) -> Option<&dyn InfoDat> {
+        Some(self)
     }
 
-    fn info_chop(&self) -> Option<Box<&dyn InfoChop>> {
-        Some(Box::new(self))
+    fn info_chop(&self) -> Option<&dyn InfoChop> {
+        Some(self)
     }
 
     fn pulse_pressed(&mut self, name: &str) {
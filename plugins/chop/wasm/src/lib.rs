@@ -8,7 +8,13 @@ use td_rs_derive::Params;
 struct WasmChopParams {
     #[param(label = "Apply Scale", page = "Filter")]
     apply_scale: bool,
-    #[param(label = "Scale", page = "Filter", min = - 10.0, max = 10.0)]
+    #[param(
+        label = "Scale",
+        page = "Filter",
+        min = - 10.0,
+        max = 10.0,
+        enable_if = "apply_scale"
+    )]
     scale: f32,
     #[param(label = "Wasm", page = "Wasm")]
     wasm: FileParam,
@@ -44,8 +50,8 @@ impl Op for WasmChop {
         Some(Box::new(&mut self.params))
     }
 
-    fn info_dat(&self) -> Option<Box<&dyn InfoDat>> {
-        Some(Box::new(self))
+    fn info_dat(&self) -> Option<&dyn InfoDat> {
+        Some(self)
     }
 }
 
@@ -68,7 +74,6 @@ impl Chop for WasmChop {
     fn execute(&mut self, output: &mut ChopOutput, inputs: &OperatorInputs<ChopInput>) {
         let params = inputs.params();
         params.enable_param("Wasm", false);
-        params.enable_param("Scale", self.params.apply_scale);
 
         if let Some(input) = &inputs.input(0) {
             params.enable_param("Wasm", true);
@@ -2,10 +2,24 @@ use td_rs_dat::chop::ChopInput;
 use td_rs_dat::*;
 use td_rs_derive::{Param, Params};
 
+#[derive(Param, Default, Clone, Copy, Debug, PartialEq)]
+enum Mode {
+    #[default]
+    Replace,
+    Append,
+    KeepLast,
+}
+
 #[derive(Params, Default, Clone, Debug)]
 struct DynamicMenuDatParams {
     #[param(label = "Menu")]
     menu: DynamicMenuParam,
+    #[param(label = "Table")]
+    table: DatParam,
+    #[param(label = "Row", menu_provider = "DatColumnMenu::param(\"Table\", 0)")]
+    row: DynamicMenuParam,
+    #[param(label = "Mode", menu_provider = "EnumMenu::<Mode>::new()")]
+    mode: DynamicMenuParam,
 }
 
 /// Struct representing our DAT's state
@@ -48,9 +62,10 @@ impl Dat for DynamicMenuDat {
             match input.dat_type() {
                 DatType::Text => {
                     if let Some(output_text) = &self.params.menu.0 {
+                        let row = self.params.row.0.as_deref().unwrap_or_default();
                         output
                             .text()
-                            .set_text(&format!("Selected: {}", output_text));
+                            .set_text(&format!("Selected: {} {}", output_text, row));
                     } else {
                         output.text().set_text("");
                     }
@@ -89,3 +104,54 @@ impl Dat for DynamicMenuDat {
 impl DynamicMenuDat {}
 
 dat_plugin!(DynamicMenuDat);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use td_rs_testing::*;
+
+    fn entries(entries: &[(&str, &str)]) -> Vec<(String, String)> {
+        entries
+            .iter()
+            .map(|(name, label)| (name.to_string(), label.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn row_menu_lists_first_column_of_table() {
+        let mut harness = DatHarness::new::<DynamicMenuDat>();
+        assert!(harness.build_dynamic_menu("Row").is_empty());
+
+        harness.set_dat_param(
+            "Table",
+            FakeDat::table("/project1/table", [["kick", "1"], ["snare", "2"]]),
+        );
+        assert_eq!(
+            harness.build_dynamic_menu("Row"),
+            entries(&[("kick", "kick"), ("snare", "snare")])
+        );
+    }
+
+    #[test]
+    fn mode_menu_lists_enum_variants() {
+        let mut harness = DatHarness::new::<DynamicMenuDat>();
+        assert_eq!(
+            harness.build_dynamic_menu("Mode"),
+            entries(&[
+                ("Replace", "Replace"),
+                ("Append", "Append"),
+                ("KeepLast", "Keep Last"),
+            ])
+        );
+    }
+
+    #[test]
+    fn menu_lists_lines_of_input_text() {
+        let mut harness = DatHarness::new::<DynamicMenuDat>();
+        harness.set_input_dat(0, FakeDat::text("/project1/text", "First Line\nSecond"));
+        assert_eq!(
+            harness.build_dynamic_menu("Menu"),
+            entries(&[("FirstLine", "First Line"), ("Second", "Second")])
+        );
+    }
+}
@@ -163,10 +163,12 @@ fn pixel_format_to_wgpu_format(format: &PixelFormat) -> TextureFormat {
 }
 
 #[derive(Params, Default, Clone, Debug)]
+#[params(pages = ["Chromatic Aberration "])]
 struct BevyTopParams {
     #[param(
         label = "Intensity",
         page = "Chromatic Aberration ",
+        header = "Aberration",
         default = 0.01,
         min = 0.0,
         max = 0.4
@@ -177,6 +179,8 @@ struct BevyTopParams {
     #[param(
         label = "Intensity",
         page = "Chromatic Aberration ",
+        separator,
+        header = "Sampling",
         default = 8.0,
         min = 1.0,
         max = 16.0
@@ -146,17 +146,14 @@ impl Op for CpuMemoryTop {
     fn params_mut(&mut self) -> Option<Box<&mut dyn OperatorParams>> {
         Some(Box::new(&mut self.params))
     }
-
-    fn pulse_pressed(&mut self, name: &str) {
-        if name == "Reset" {
-            self.step = 0.0;
-        }
-    }
 }
 
 impl Top for CpuMemoryTop {
     fn execute(&mut self, mut output: TopOutput, input: &OperatorInputs<TopInput>) {
         self.execute_count += 1;
+        if self.params.reset.take() {
+            self.step = 0.0;
+        }
 
         if let Some(buf_info) = self.frame_queue.get_buffer_to_upload() {
             if let Some(mut buf) = buf_info.buf {
@@ -4,12 +4,13 @@ use td_rs_derive::Params;
 use td_rs_top::*;
 
 #[derive(Params, Default, Clone, Debug)]
+#[params(op = "CudaExample")]
 struct CudaExampleParams {
     #[param(label = "Brightness", min = 0.0, max = 1.0, default = 1.0)]
     brightness: f64,
     #[param(label = "Speed", min = -10.0, max = 10.0, default = 1.0)]
     speed: f64,
-    #[param(label = "Reset")]
+    #[param(label = "Reset", on_pulse = "reset")]
     reset: Pulse,
 }
 
@@ -52,12 +53,7 @@ impl Op for CudaExample {
     }
 
     fn pulse_pressed(&mut self, name: &str) {
-        match name {
-            "Reset" => {
-                self.execute_count = 0;
-            }
-            _ => {}
-        }
+        CudaExampleParams::handle_pulse(self, name);
     }
 }
 
@@ -79,6 +75,10 @@ impl Top for CudaExample {
 }
 
 impl CudaExample {
+    fn reset(&mut self) {
+        self.execute_count = 0;
+    }
+
     fn execute_cuda(
         &mut self,
         output: &mut TopOutput,
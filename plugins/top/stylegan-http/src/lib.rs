@@ -16,7 +16,7 @@ const MAX_TASKS: usize = 10;
 
 #[derive(Params, Default, Clone, Debug)]
 struct StyleganHttpTopParams {
-    #[param(label = "Ip Address", page = "Config", default = "localhost")]
+    #[param(label = "Ip Address", page = "Config")]
     ip: String,
     #[param(label = "Port", page = "Config", default = 5000)]
     port: u16,
@@ -24,54 +24,33 @@ struct StyleganHttpTopParams {
     seed: u16,
     #[param(label = "Blocing", page = "Config")]
     blocking: bool,
+    #[param(flatten, prefix = "X")]
+    x: FeatureParams,
+    #[param(flatten, prefix = "Y")]
+    y: FeatureParams,
+    #[param(flatten, prefix = "Z")]
+    z: FeatureParams,
+}
+
+/// A latent feature and how far to move along it.
+#[derive(Params, Default, Clone, Debug)]
+struct FeatureParams {
     #[param(
-        label = "X Feature",
-        page = "Stylegan",
-        min = 0.0,
-        max = 512.0,
-        clamp = true
-    )]
-    x_feature: u32,
-    #[param(
-        label = "X Range",
-        page = "Stylegan",
-        min = 0.0,
-        max = 512.0,
-        clamp = true
-    )]
-    x_range: f32,
-    #[param(
-        label = "Y Feature",
-        page = "Stylegan",
-        min = 0.0,
-        max = 512.0,
-        clamp = true
-    )]
-    y_feature: u32,
-    #[param(
-        label = "Y Range",
-        page = "Stylegan",
-        min = 0.0,
-        max = 512.0,
-        clamp = true
-    )]
-    y_range: f32,
-    #[param(
-        label = "Z Feature",
+        label = "Feature",
         page = "Stylegan",
         min = 0.0,
         max = 512.0,
         clamp = true
     )]
-    z_feature: u32,
+    feature: u32,
     #[param(
-        label = "Z Range",
+        label = "Range",
         page = "Stylegan",
         min = 0.0,
         max = 512.0,
         clamp = true
     )]
-    z_range: f32,
+    range: f32,
 }
 
 pub type Task = JoinHandle<anyhow::Result<Vec<u8>>>;
@@ -149,12 +128,12 @@ impl StyleganHttpTop {
                 port = self.params.port
             ),
             seed: self.params.seed,
-            x: self.params.x_feature,
-            x_range: self.params.x_range,
-            y: self.params.y_feature,
-            y_range: self.params.y_range,
-            z: self.params.z_feature,
-            z_range: self.params.z_range,
+            x: self.params.x.feature,
+            x_range: self.params.x.range,
+            y: self.params.y.feature,
+            y_range: self.params.y.range,
+            z: self.params.z.feature,
+            z_range: self.params.z.range,
         }
     }
 
@@ -184,7 +163,11 @@ impl StyleganHttpTop {
 impl TopNew for StyleganHttpTop {
     fn new(_info: NodeInfo, context: TopContext) -> Self {
         Self {
-            params: Default::default(),
+            params: StyleganHttpTopParams {
+                ip: "localhost".to_string(),
+                port: 5000,
+                ..Default::default()
+            },
             execute_count: 0,
             context,
             tasks: VecDeque::with_capacity(MAX_TASKS),
@@ -1,4 +1,14 @@
 pub fn build(output: &str, include_base: bool) -> miette::Result<()> {
+    build_with_includes(output, include_base, &[])
+}
+
+/// Like [`build`], but with additional header search paths, e.g. for crates that
+/// need the headers of several operator families.
+pub fn build_with_includes(
+    output: &str,
+    include_base: bool,
+    extra_includes: &[&str],
+) -> miette::Result<()> {
     let python_enabled = std::env::var("CARGO_FEATURE_PYTHON").is_ok();
 
     let path = std::path::PathBuf::from("src");
@@ -10,6 +20,8 @@ pub fn build(output: &str, include_base: bool) -> miette::Result<()> {
         incs.push(base_path);
     }
 
+    incs.extend(extra_includes.iter().map(std::path::PathBuf::from));
+
     println!("python_enabled: {}", python_enabled);
 
     if python_enabled {
@@ -20,10 +32,12 @@ pub fn build(output: &str, include_base: bool) -> miette::Result<()> {
             incs.push(std::path::PathBuf::from(
                 "C:\\Program Files\\Derivative\\TouchDesigner\\Samples\\CPlusPlus\\3rdParty\\Python\\Include\\PC"
             ));
-        } else {
+        } else if cfg!(target_os = "macos") {
             incs.push(std::path::PathBuf::from(
                 "/Applications/TouchDesigner.app/Contents/Frameworks/Python.framework/Headers",
             ));
+        } else {
+            incs.extend(python_include_dirs()?);
         };
         clang_args.push("-DPYTHON_ENABLED");
     }
@@ -33,6 +47,10 @@ pub fn build(output: &str, include_base: bool) -> miette::Result<()> {
         clang_args.push("-D_CRT_USE_BUILTIN_OFFSETOF");
     }
 
+    if cfg!(target_os = "linux") {
+        clang_args.push("-std=c++17");
+    }
+
     let b = autocxx_build::Builder::new("src/cxx.rs", &incs)
         .extra_clang_args(&clang_args)
         .auto_allowlist(true);
@@ -55,3 +73,60 @@ pub fn build(output: &str, include_base: bool) -> miette::Result<()> {
     println!("cargo:rerun-if-changed=src/cxx.rs");
     Ok(())
 }
+
+/// Python include paths, from `python_include_dir` in the `[linux]` section of
+/// `td-rs.toml` or else as reported by `python3-config`.
+fn python_include_dirs() -> miette::Result<Vec<std::path::PathBuf>> {
+    if let Some(dir) = configured_python_include_dir()? {
+        return Ok(vec![dir]);
+    }
+
+    let output = std::process::Command::new("python3-config")
+        .arg("--includes")
+        .output()
+        .map_err(|e| {
+            miette::miette!(
+                help = "install python3-dev or set python_include_dir in the [linux] section of td-rs.toml",
+                "Could not call python3-config: {e}"
+            )
+        })?;
+    if !output.status.success() {
+        return Err(miette::miette!(
+            help = "set python_include_dir in the [linux] section of td-rs.toml",
+            "python3-config --includes failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let includes = String::from_utf8(output.stdout)
+        .map_err(|_| miette::miette!("python3-config returned invalid UTF-8"))?;
+    Ok(includes
+        .split_whitespace()
+        .filter_map(|flag| flag.strip_prefix("-I"))
+        .map(std::path::PathBuf::from)
+        .collect())
+}
+
+/// `python_include_dir` in the `[linux]` section of the `td-rs.toml` of the
+/// workspace, if any.
+fn configured_python_include_dir() -> miette::Result<Option<std::path::PathBuf>> {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let Some(config_path) = std::path::Path::new(&manifest_dir)
+        .ancestors()
+        .map(|dir| dir.join("td-rs.toml"))
+        .find(|path| path.is_file())
+    else {
+        return Ok(None);
+    };
+    println!("cargo:rerun-if-changed={}", config_path.display());
+
+    let config = std::fs::read_to_string(&config_path)
+        .map_err(|e| miette::miette!("Could not read {}: {e}", config_path.display()))?;
+    let config = config
+        .parse::<toml::Table>()
+        .map_err(|e| miette::miette!("Could not parse {}: {e}", config_path.display()))?;
+    Ok(config
+        .get("linux")
+        .and_then(|linux| linux.get("python_include_dir"))
+        .and_then(|dir| dir.as_str())
+        .map(std::path::PathBuf::from))
+}
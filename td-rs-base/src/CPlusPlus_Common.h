@@ -28,9 +28,16 @@ stays the same, otherwise changes won't be backwards compatible
 #include <windows.h>
 #define DLLEXPORT __declspec(dllexport)
 #else
+#include <cstddef>
+#include <stdint.h>
 #define DLLEXPORT
 #endif
 
+#ifdef __linux__
+// Only meaningful on 32-bit Windows, and not a keyword for GCC
+#define __cdecl
+#endif
+
 #include <assert.h>
 #include <cmath>
 #include <cstring>
@@ -594,7 +601,7 @@ public:
   // When you first obtain a pointer to the TOP_CUDAArrayInfo, this will be
   // nullptr. It will get filled in with the correct memory address when you
   // call OP_Context::beginCUDAOperations()
-  cudaArray *cudaArray = nullptr;
+  ::cudaArray *cudaArray = nullptr;
 
   uint32_t reserved[25];
 };
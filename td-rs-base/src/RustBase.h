@@ -31,6 +31,11 @@ const char* getBuildDynamicMenuInfoNames(TD::OP_BuildDynamicMenuInfo &info) {
     return info.name;
 }
 
+// Takes the matrix as 16 doubles, as autocxx can't bind double[4][4] arguments
+bool getRelativeTransformMatrix(const TD::OP_Inputs &inputs, const char *fromName, const char *toName, double *matrix) {
+    return inputs.getRelativeTransform(fromName, toName, reinterpret_cast<double (*)[4]>(matrix));
+}
+
 // CUDA helper functions for base types
 TD::OP_TextureDesc getCUDAArrayInfoTextureDesc(const TD::OP_CUDAArrayInfo &info) {
     return info.textureDesc;
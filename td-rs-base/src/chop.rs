@@ -1,6 +1,7 @@
 use crate::cxx::OP_CHOPInput;
 use crate::{GetInput, OperatorInputs};
 use ref_cast::RefCast;
+use std::ffi;
 use std::ops::Index;
 
 /// A chop input.
@@ -34,6 +35,113 @@ impl ChopInput {
             )
         }
     }
+
+    /// Get the name of a channel.
+    pub fn channel_name(&self, index: usize) -> &str {
+        if index >= self.num_channels() {
+            panic!("index out of bounds");
+        }
+
+        unsafe { c_str(*self.input.nameData.add(index)) }
+    }
+
+    /// Iterate over the channels as `(name, samples)` pairs.
+    pub fn channels(&self) -> impl Iterator<Item = (&str, &[f32])> {
+        (0..self.num_channels()).map(|i| (self.channel_name(i), self.channel(i)))
+    }
+
+    /// Get the index of the channel with the given name.
+    pub fn channel_index(&self, name: &str) -> Option<usize> {
+        (0..self.num_channels()).find(|&i| self.channel_name(i) == name)
+    }
+
+    /// Get the channel with the given name.
+    pub fn channel_by_name(&self, name: &str) -> Option<&[f32]> {
+        self.channel_index(name).map(|i| self.channel(i))
+    }
+
+    /// Iterate over the channels whose names match a pattern, see
+    /// [`match_pattern`].
+    pub fn find_channels<'a>(
+        &'a self,
+        pattern: &'a str,
+    ) -> impl Iterator<Item = (&'a str, &'a [f32])> {
+        self.channels()
+            .filter(move |(name, _)| match_pattern(pattern, name))
+    }
+
+    /// Get the sample rate of the channels.
+    pub fn sample_rate(&self) -> f64 {
+        self.input.sampleRate
+    }
+
+    /// Get the index of the first sample.
+    pub fn start_index(&self) -> f64 {
+        self.input.startIndex
+    }
+
+    /// Get the path of the CHOP.
+    pub fn path(&self) -> &str {
+        unsafe { c_str(self.input.opPath) }
+    }
+
+    /// Get the unique id of the CHOP.
+    pub fn id(&self) -> u32 {
+        self.input.opId
+    }
+
+    /// Get the number of times the CHOP has cooked.
+    pub fn total_cooks(&self) -> i64 {
+        self.input.totalCooks
+    }
+
+    /// Get the samples of every channel at a sample index.
+    pub fn frame(&self, index: usize) -> ChopFrame<'_> {
+        if index >= self.num_samples() {
+            panic!("index out of bounds");
+        }
+
+        ChopFrame { input: self, index }
+    }
+
+    /// Iterate over the samples in order, one frame holding a sample of every
+    /// channel at a time.
+    pub fn frames(&self) -> impl ExactSizeIterator<Item = ChopFrame<'_>> {
+        (0..self.num_samples()).map(|index| ChopFrame { input: self, index })
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl ChopInput {
+    /// View the samples as an array of shape `(channels, samples)`. Only
+    /// possible if the channels are evenly spaced in memory, see
+    /// [`channel_stride`].
+    pub fn as_array(&self) -> Option<ndarray::ArrayView2<'_, f32>> {
+        use ndarray::ShapeBuilder;
+
+        let shape = (self.num_channels(), self.num_samples());
+        if shape.0 == 0 || shape.1 == 0 {
+            return ndarray::ArrayView2::from_shape(shape, &[]).ok();
+        }
+        let channels =
+            unsafe { std::slice::from_raw_parts(self.input.channelData, self.num_channels()) };
+        let stride = channel_stride(channels, self.num_samples())?;
+        // Safety: every channel holds `num_samples` samples and, being evenly
+        // spaced, can be reached from the first with `stride`
+        unsafe {
+            Some(ndarray::ArrayView2::from_shape_ptr(
+                shape.strides((stride, 1)),
+                channels[0],
+            ))
+        }
+    }
+
+    /// Copy the samples into an array of shape `(channels, samples)`.
+    pub fn to_array(&self) -> ndarray::Array2<f32> {
+        ndarray::Array2::from_shape_fn((self.num_channels(), self.num_samples()), |(c, s)| {
+            self.channel(c)[s]
+        })
+    }
 }
 
 impl<'cook> GetInput<'cook, ChopInput> for OperatorInputs<'cook, ChopInput> {
@@ -58,3 +166,256 @@ impl Index<usize> for ChopInput {
         self.channel(index)
     }
 }
+
+/// The samples of every channel of a CHOP at one sample index.
+#[derive(Clone, Copy)]
+pub struct ChopFrame<'a> {
+    input: &'a ChopInput,
+    index: usize,
+}
+
+impl<'a> ChopFrame<'a> {
+    /// Get the index of the sample in the channels.
+    pub fn sample_index(&self) -> usize {
+        self.index
+    }
+
+    /// Get the number of channels.
+    pub fn len(&self) -> usize {
+        self.input.num_channels()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the sample of a channel.
+    pub fn get(&self, channel: usize) -> f32 {
+        self.input.channel(channel)[self.index]
+    }
+
+    /// Iterate over the sample of every channel.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = f32> + 'a {
+        let ChopFrame { input, index } = *self;
+        (0..input.num_channels()).map(move |c| input.channel(c)[index])
+    }
+}
+
+/// The distance in samples from one channel to the next, if the channels lie
+/// evenly spaced in memory, in order and without overlapping. Channels with
+/// such a layout can be viewed as a single two-dimensional array.
+pub fn channel_stride<T>(channels: &[*const T], num_samples: usize) -> Option<usize> {
+    let size = std::mem::size_of::<T>();
+    let [first, second, ..] = channels else {
+        return Some(num_samples);
+    };
+    let distance = (*second as usize).checked_sub(*first as usize)?;
+    if distance % size != 0 || distance / size < num_samples {
+        return None;
+    }
+    channels
+        .windows(2)
+        .all(|pair| (pair[1] as usize).checked_sub(pair[0] as usize) == Some(distance))
+        .then_some(distance / size)
+}
+
+unsafe fn c_str<'a>(s: *const ffi::c_char) -> &'a str {
+    if s.is_null() {
+        ""
+    } else {
+        ffi::CStr::from_ptr(s).to_str().unwrap_or_default()
+    }
+}
+
+/// Whether a channel name matches a pattern, as in the channel parameters of
+/// TouchDesigner. `*` matches any run of characters, `?` any single character
+/// and `[...]` one character of a set such as `[xyz]` or `[a-z]`, or a whole
+/// number in a range such as `[1-10]`. Several patterns can be given separated
+/// by spaces, e.g. `"tx ty tz"`, and a pattern starting with `^` excludes the
+/// names matched by the patterns before it, e.g. `"chan* ^chan2"`.
+pub fn match_pattern(pattern: &str, name: &str) -> bool {
+    let name = name.chars().collect::<Vec<_>>();
+    pattern
+        .split_whitespace()
+        .fold(false, |matched, pattern| match pattern.strip_prefix('^') {
+            Some(excluded) if !excluded.is_empty() => matched && !glob(excluded, &name),
+            _ => matched || glob(pattern, &name),
+        })
+}
+
+/// A part of a pattern matching one or more characters of a name.
+#[derive(Debug, PartialEq)]
+enum Token {
+    Star,
+    Any,
+    Char(char),
+    /// One character in any of the ranges.
+    Set(Vec<(char, char)>),
+    /// A whole number in the range.
+    Number(u64, u64),
+}
+
+fn tokenize(pattern: &str) -> Vec<Token> {
+    let chars = pattern.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let token = match chars[i] {
+            '*' => Token::Star,
+            '?' => Token::Any,
+            '[' => match chars[i..].iter().position(|&c| c == ']') {
+                Some(end) => {
+                    let set = &chars[i + 1..i + end];
+                    i += end + 1;
+                    tokens.push(set_token(set));
+                    continue;
+                }
+                None => Token::Char('['),
+            },
+            c => Token::Char(c),
+        };
+        tokens.push(token);
+        i += 1;
+    }
+    tokens
+}
+
+fn set_token(set: &[char]) -> Token {
+    let text = set.iter().collect::<String>();
+    if let Some((from, to)) = text.split_once('-') {
+        if let (Ok(from), Ok(to)) = (from.parse(), to.parse()) {
+            return Token::Number(from, to);
+        }
+    }
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < set.len() {
+        if i + 2 < set.len() && set[i + 1] == '-' {
+            ranges.push((set[i], set[i + 2]));
+            i += 3;
+        } else {
+            ranges.push((set[i], set[i]));
+            i += 1;
+        }
+    }
+    Token::Set(ranges)
+}
+
+/// The number of characters at `at` matched by a token other than `*`.
+fn match_token(token: &Token, name: &[char], at: usize) -> Option<usize> {
+    let c = *name.get(at)?;
+    match token {
+        Token::Star => None,
+        Token::Any => Some(1),
+        Token::Char(expected) => (c == *expected).then_some(1),
+        Token::Set(ranges) => ranges
+            .iter()
+            .any(|(from, to)| (*from..=*to).contains(&c))
+            .then_some(1),
+        Token::Number(from, to) => {
+            // The number must not start in the middle of another one
+            if at > 0 && name[at - 1].is_ascii_digit() {
+                return None;
+            }
+            let len = name[at..].iter().take_while(|c| c.is_ascii_digit()).count();
+            let number = name[at..at + len].iter().collect::<String>().parse::<u64>();
+            let (from, to) = (*from.min(to), *from.max(to));
+            number
+                .is_ok_and(|number| (from..=to).contains(&number))
+                .then_some(len)
+        }
+    }
+}
+
+/// Match a single pattern, backtracking to the last `*` on a mismatch so that
+/// the time taken grows with the product of the lengths at worst.
+fn glob(pattern: &str, name: &[char]) -> bool {
+    let tokens = tokenize(pattern);
+    let (mut t, mut n) = (0, 0);
+    // The token after the last `*` and the position in the name it was tried at
+    let mut star = None;
+    while n < name.len() {
+        if let Some(token) = tokens.get(t) {
+            if *token == Token::Star {
+                t += 1;
+                star = Some((t, n));
+                continue;
+            }
+            if let Some(len) = match_token(token, name, n) {
+                t += 1;
+                n += len;
+                continue;
+            }
+        }
+        match star {
+            Some((after, at)) => {
+                t = after;
+                n = at + 1;
+                star = Some((after, n));
+            }
+            None => return false,
+        }
+    }
+    tokens[t..].iter().all(|token| *token == Token::Star)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_match_pattern() {
+        assert!(match_pattern("chan1", "chan1"));
+        assert!(!match_pattern("chan1", "chan12"));
+        assert!(match_pattern("chan*", "chan12"));
+        assert!(match_pattern("t?", "tx"));
+        assert!(match_pattern("tx ty tz", "ty"));
+        assert!(match_pattern("chan[1-3]", "chan2"));
+        assert!(!match_pattern("chan[1-3]", "chan4"));
+        assert!(match_pattern("[rs][xyz]", "sz"));
+        assert!(!match_pattern("", "chan1"));
+        assert!(match_pattern("[a-c]x", "bx"));
+        assert!(match_pattern("chan[", "chan["));
+    }
+
+    #[test]
+    fn test_match_number_range() {
+        assert!(match_pattern("chan[1-10]", "chan1"));
+        assert!(match_pattern("chan[1-10]", "chan10"));
+        assert!(!match_pattern("chan[1-10]", "chan11"));
+        assert!(!match_pattern("chan[1-10]", "chan0"));
+        assert!(!match_pattern("chan[1-10]", "chan"));
+        assert!(match_pattern("chan[1-10]_x", "chan7_x"));
+        assert!(!match_pattern("*[1-10]", "chan15"));
+        assert!(match_pattern("*[10-1]", "chan5"));
+    }
+
+    #[test]
+    fn test_match_exclusion() {
+        assert!(match_pattern("chan* ^chan2", "chan1"));
+        assert!(!match_pattern("chan* ^chan2", "chan2"));
+        assert!(!match_pattern("chan* ^chan[2-3]", "chan3"));
+        assert!(match_pattern("chan* ^chan2 chan2", "chan2"));
+        assert!(!match_pattern("^chan2", "chan1"));
+        assert!(match_pattern("t? ^", "tx"));
+    }
+
+    #[test]
+    fn test_match_many_stars() {
+        let name = "a".repeat(64);
+        assert!(!match_pattern(&format!("{}b", "*a".repeat(32)), &name));
+        assert!(match_pattern(&"*a".repeat(32), &name));
+    }
+
+    #[test]
+    fn test_channel_stride() {
+        let samples = [0.0f32; 12];
+        let at = |i: usize| &samples[i] as *const f32;
+        assert_eq!(channel_stride(&[at(0), at(4), at(8)], 4), Some(4));
+        assert_eq!(channel_stride(&[at(0), at(5)], 4), Some(5));
+        assert_eq!(channel_stride(&[at(0), at(2)], 4), None);
+        assert_eq!(channel_stride(&[at(0), at(4), at(6)], 2), None);
+        assert_eq!(channel_stride(&[at(4), at(0)], 4), None);
+        assert_eq!(channel_stride(&[at(0)], 4), Some(4));
+    }
+}
@@ -12,6 +12,7 @@ include_cpp! {
     generate_pod!("TD::OP_NumericParameter")
     generate_pod!("TD::OP_StringParameter")
     generate!("TD::OP_Inputs")
+    generate_pod!("TD::OP_TimeInfo")
     generate_pod!("TD::OP_InfoDATSize")
     generate_pod!("TD::OP_InfoCHOPChan")
     generate_pod!("TD::Vector")
@@ -25,6 +26,7 @@ include_cpp! {
     generate_pod!("TD::SOP_CustomAttribData")
     generate_pod!("TD::SOP_PrimitiveInfo")
     generate_pod!("TD::OP_DATInput")
+    generate_pod!("TD::OP_ObjectInput")
     generate_pod!("TD::OP_NodeInfo")
     generate!("TD::OP_Context")
     generate!("TD::OP_TOPInput")
@@ -48,6 +50,7 @@ include_cpp! {
     generate!("getDownloadTextureDesc")
     generate!("releaseDownloadResult")
     generate!("getBuildDynamicMenuInfoNames")
+    generate!("getRelativeTransformMatrix")
 
     // CUDA helper functions for base types
     generate!("getCUDAArrayInfoTextureDesc")
@@ -2,7 +2,11 @@
 #![feature(min_specialization)]
 
 use crate::cxx::OP_Inputs;
+pub use anyhow;
+pub use menu::*;
 pub use param::*;
+#[cfg(feature = "presets")]
+pub use preset::*;
 #[cfg(feature = "python")]
 pub use py::*;
 #[cfg(feature = "python")]
@@ -11,12 +15,19 @@ use pyo3::prelude::*;
 use pyo3::types::{IntoPyDict, PyTuple};
 #[cfg(feature = "python")]
 use pyo3::BoundObject;
+use ref_cast::RefCast;
+#[cfg(feature = "presets")]
+pub use serde;
+pub use status::{
+    catch_cook, catch_new, message_c_string, with_op_status, CookResult, FailedOp, OpStatus,
+    Severity, StatusMessage,
+};
 use std::ffi;
 use std::ffi::c_int;
 use std::fmt::Formatter;
 use std::ops::Index;
 use std::pin::Pin;
-use std::sync::Mutex;
+pub use time::TimeInfo;
 
 #[cfg(feature = "tokio")]
 pub static RUNTIME: std::sync::LazyLock<tokio_core::runtime::Runtime> =
@@ -32,16 +43,18 @@ pub mod chop;
 pub mod cuda;
 pub mod cxx;
 pub mod dat;
+pub mod menu;
+pub mod object;
 pub mod param;
+#[cfg(feature = "presets")]
+pub mod preset;
 #[cfg(feature = "python")]
 pub mod py;
 pub mod sop;
+pub mod status;
+pub mod time;
 pub mod top;
 
-static INFO_STR: Mutex<String> = Mutex::new(String::new());
-static ERROR_STR: Mutex<String> = Mutex::new(String::new());
-static WARNING_STR: Mutex<String> = Mutex::new(String::new());
-
 /// Metadata describing the operator plugin.
 pub trait OpInfo {
     /// The type of the operator.
@@ -74,12 +87,66 @@ pub trait InfoChop {
     fn channel(&self, index: usize) -> (String, f32);
 }
 
+/// Values that can be published as a channel of an Info CHOP, see
+/// `#[derive(InfoChop)]`.
+pub trait InfoChopValue {
+    fn info_value(&self) -> f32;
+}
+
+macro_rules! impl_info_chop_value {
+    ($($t:ty),*) => {
+        $(
+            impl InfoChopValue for $t {
+                fn info_value(&self) -> f32 {
+                    *self as f32
+                }
+            }
+        )*
+    };
+}
+
+impl_info_chop_value!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64);
+
+impl InfoChopValue for bool {
+    fn info_value(&self) -> f32 {
+        if *self {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
+
 pub trait InfoDat {
     fn size(&self) -> (u32, u32);
 
     fn entry(&self, index: usize, entry_index: usize) -> String;
 }
 
+/// A row of an Info DAT with a cell per field, see `#[derive(InfoDat)]`.
+///
+/// A `Vec` of rows is an Info DAT with a header row naming the columns
+/// followed by a row per element.
+pub trait InfoDatRow {
+    /// The names of the columns.
+    fn columns() -> &'static [&'static str];
+
+    fn cell(&self, column: usize) -> String;
+}
+
+impl<T: InfoDatRow> InfoDat for Vec<T> {
+    fn size(&self) -> (u32, u32) {
+        (self.len() as u32 + 1, T::columns().len() as u32)
+    }
+
+    fn entry(&self, index: usize, entry_index: usize) -> String {
+        match index.checked_sub(1) {
+            None => T::columns()[entry_index].to_string(),
+            Some(row) => self[row].cell(entry_index),
+        }
+    }
+}
+
 pub trait OpNew {
     fn new(info: NodeInfo) -> Self;
 }
@@ -91,39 +158,161 @@ pub trait Op {
         None
     }
 
-    fn info_dat(&self) -> Option<Box<&dyn InfoDat>> {
+    /// The Info DAT of this instance, e.g. `Some(&self.stats)` with `stats`
+    /// deriving `InfoDat`.
+    fn info_dat(&self) -> Option<&dyn InfoDat> {
         None
     }
 
-    fn info_chop(&self) -> Option<Box<&dyn InfoChop>> {
+    /// The Info CHOP of this instance, e.g. `Some(&self.stats)` with `stats`
+    /// deriving `InfoChop`.
+    fn info_chop(&self) -> Option<&dyn InfoChop> {
         None
     }
 
+    /// Replace the info popup text of this instance.
     fn set_info(&mut self, info: &str) {
-        INFO_STR.lock().unwrap().replace_range(.., info);
+        status::with_status(|s| s.set(Severity::Info, info));
+    }
+
+    /// Add a line to the info popup text of this instance.
+    fn add_info(&mut self, info: &str) {
+        status::with_status(|s| s.push(Severity::Info, info));
     }
 
     fn info(&self) -> String {
-        INFO_STR.lock().unwrap().clone()
+        status::with_status(|s| s.text(Severity::Info)).unwrap_or_default()
     }
 
+    /// Replace the errors of this instance.
     fn set_error(&mut self, error: &str) {
-        ERROR_STR.lock().unwrap().replace_range(.., error);
+        status::with_status(|s| s.set(Severity::Error, error));
+    }
+
+    /// Add an error, keeping any others reported during this cook.
+    fn add_error(&mut self, error: &str) {
+        status::with_status(|s| s.push(Severity::Error, error));
     }
 
     fn error(&self) -> String {
-        ERROR_STR.lock().unwrap().clone()
+        status::with_status(|s| s.text(Severity::Error)).unwrap_or_default()
     }
 
+    /// Replace the warnings of this instance.
     fn set_warning(&mut self, warning: &str) {
-        WARNING_STR.lock().unwrap().replace_range(.., warning);
+        status::with_status(|s| s.set(Severity::Warning, warning));
+    }
+
+    /// Add a warning, keeping any others reported during this cook.
+    fn add_warning(&mut self, warning: &str) {
+        status::with_status(|s| s.push(Severity::Warning, warning));
     }
 
     fn warning(&self) -> String {
-        WARNING_STR.lock().unwrap().clone()
+        status::with_status(|s| s.text(Severity::Warning)).unwrap_or_default()
+    }
+
+    /// All messages reported by this instance since the start of its last cook.
+    /// The messages are kept by the plugin holding the instance and are only
+    /// recorded while TouchDesigner calls into it.
+    fn status(&self) -> OpStatus {
+        status::with_status(|s| s.clone()).unwrap_or_default()
     }
 
+    /// Clear all messages of this instance. The plugin clears them at the start
+    /// of every cook.
+    fn clear_status(&mut self) {
+        status::with_status(OpStatus::clear);
+    }
+
+    /// Called when a pulse parameter is pressed, after the press was recorded
+    /// in the parameters of this instance.
     fn pulse_pressed(&mut self, _name: &str) {}
+
+    /// Record a press of a pulse parameter in the parameters of this instance,
+    /// then call [`Op::pulse_pressed`].
+    fn press_pulse(&mut self, name: &str) {
+        if let Some(params) = self.params_mut() {
+            params.press_pulse(name);
+        }
+        self.pulse_pressed(name);
+    }
+
+    /// Called by the plugin once this instance was created, with the node it
+    /// belongs to. Attaches the node to the embedded
+    /// [`PresetControls`](crate::preset::PresetControls), if any, so loaded
+    /// presets are set on its parameters.
+    fn attach_node(&mut self, info: &NodeInfo) {
+        #[cfg(feature = "presets")]
+        if let Some(params) = self.params_mut() {
+            if let Some(controls) = params
+                .presets()
+                .and_then(|presets| presets.preset_controls())
+            {
+                controls.attach(info.context());
+            }
+        }
+        #[cfg(not(feature = "presets"))]
+        let _ = info;
+    }
+
+    /// Called after the parameters of this instance were updated, with the
+    /// parameters whose value changed. Use this to only redo expensive setup
+    /// when the parameters it depends on change.
+    fn on_params_changed(&mut self, _changed: &ParamsChanged) {}
+
+    /// Update the parameters of this instance from the inputs of the cook,
+    /// calling [`Op::on_params_changed`] if any of them changed. Parameters
+    /// with presets also save and load them here, reporting any problem.
+    fn update_params(&mut self, inputs: &ParamInputs) {
+        #[cfg(feature = "presets")]
+        let (changed, messages) = match self.params_mut() {
+            Some(params) => match params.presets() {
+                Some(presets) => {
+                    let changed = presets.update_presets(inputs);
+                    let messages = presets
+                        .preset_controls()
+                        .map(|controls| controls.messages().to_vec())
+                        .unwrap_or_default();
+                    (changed, messages)
+                }
+                None => (params.update(inputs), Vec::new()),
+            },
+            None => return,
+        };
+        #[cfg(not(feature = "presets"))]
+        let changed = match self.params_mut() {
+            Some(params) => params.update(inputs),
+            None => return,
+        };
+        #[cfg(feature = "presets")]
+        {
+            // Parameters are updated several times a cook, report once
+            let status = self.status();
+            for message in messages {
+                if status.iter(message.severity).any(|m| m == message.message) {
+                    continue;
+                }
+                match message.severity {
+                    Severity::Error => self.add_error(&message.message),
+                    Severity::Warning => self.add_warning(&message.message),
+                    Severity::Info => self.add_info(&message.message),
+                }
+            }
+        }
+        if !changed.is_empty() {
+            self.on_params_changed(&changed);
+        }
+    }
+
+    /// Fill a dynamic menu from the `menu_provider` of its parameter, returning
+    /// whether the parameter has one.
+    fn build_params_menu(&mut self, inputs: &MenuInputs, menu: &mut DynamicMenuInfo) -> bool {
+        match self.params_mut() {
+            Some(params) => params.build_menu(inputs, menu),
+            None => false,
+        }
+    }
 }
 
 pub struct NodeInfo {
@@ -142,12 +331,40 @@ impl NodeInfo {
     }
 }
 
+#[derive(Clone, Copy)]
 pub struct Context {
     #[allow(dead_code)]
     context: *mut cxx::OP_Context,
 }
 
 impl Context {
+    /// Set the parameters of this operator to the values of a preset, returning
+    /// the names of the parameters that do not exist. Parameters can only be
+    /// set from Python, so this fails without the `python` feature.
+    #[cfg(feature = "presets")]
+    pub fn apply_preset(&self, preset: &Preset) -> anyhow::Result<Vec<String>> {
+        #[cfg(feature = "python")]
+        {
+            Python::with_gil(|py| Ok(preset.apply(py, &self.op(py)?)?))
+        }
+        #[cfg(not(feature = "python"))]
+        {
+            let _ = preset;
+            anyhow::bail!("Loading presets needs the `python` feature to set the parameters")
+        }
+    }
+
+    /// The Python object of this operator, e.g. to set its parameters.
+    #[cfg(feature = "python")]
+    pub fn op<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        unsafe {
+            let mut ctx = Pin::new_unchecked(&mut *self.context);
+            let args = ctx.createArgumentsTuple(autocxx::c_int(0), std::ptr::null_mut());
+            let args = PyObject::from_owned_ptr(py, args as *mut pyo3::ffi::PyObject);
+            args.downcast_bound::<PyTuple>(py)?.get_item(0)
+        }
+    }
+
     #[cfg(feature = "python")]
     pub fn call_python_callback<'py, F>(
         &self,
@@ -199,6 +416,12 @@ impl std::fmt::Debug for NodeInfo {
     }
 }
 
+impl std::fmt::Debug for Context {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Context")
+    }
+}
+
 /// Input to an operator, which can be used to get parameters, channels,
 /// and other information.
 pub struct OperatorInputs<'cook, Op> {
@@ -229,6 +452,15 @@ where
         ParamInputs::new(self.inputs)
     }
 
+    /// Get the timing of the current cook.
+    pub fn time_info(&self) -> TimeInfo {
+        let info = self.inputs.getTimeInfo();
+        if info.is_null() {
+            return TimeInfo::default();
+        }
+        unsafe { TimeInfo::from(&*info) }
+    }
+
     /// Get an input channel.
     pub fn input(&self, index: usize) -> Option<&<Self as GetInput<'cook, Op>>::Input>
     where
@@ -244,6 +476,66 @@ where
     {
         GetInput::num_inputs(self)
     }
+
+    /// Look up a CHOP anywhere in the network. Relative paths are resolved
+    /// from this operator.
+    pub fn get_chop(&self, path: &str) -> Option<&'cook chop::ChopInput> {
+        let path = ffi::CString::new(path).ok()?;
+        let chop = unsafe { self.inputs.getCHOP(path.as_ptr()) };
+        if chop.is_null() {
+            None
+        } else {
+            Some(chop::ChopInput::ref_cast(unsafe { &*chop }))
+        }
+    }
+
+    /// Look up a DAT anywhere in the network. Relative paths are resolved
+    /// from this operator.
+    pub fn get_dat(&self, path: &str) -> Option<&'cook dat::DatInput> {
+        let path = ffi::CString::new(path).ok()?;
+        let dat = unsafe { self.inputs.getDAT(path.as_ptr()) };
+        if dat.is_null() {
+            None
+        } else {
+            Some(dat::DatInput::ref_cast(unsafe { &*dat }))
+        }
+    }
+
+    /// Look up a SOP anywhere in the network. Relative paths are resolved
+    /// from this operator.
+    pub fn get_sop(&self, path: &str) -> Option<&'cook sop::SopInput> {
+        let path = ffi::CString::new(path).ok()?;
+        let sop = unsafe { self.inputs.getSOP(path.as_ptr()) };
+        if sop.is_null() {
+            None
+        } else {
+            Some(sop::SopInput::ref_cast(unsafe { &*sop }))
+        }
+    }
+
+    /// Look up a TOP anywhere in the network. Relative paths are resolved
+    /// from this operator.
+    pub fn get_top(&self, path: &str) -> Option<&'cook top::TopInput> {
+        let path = ffi::CString::new(path).ok()?;
+        let top = unsafe { self.inputs.getTOP(path.as_ptr()) };
+        if top.is_null() {
+            None
+        } else {
+            Some(top::TopInput::ref_cast(unsafe { &*top }))
+        }
+    }
+
+    /// Look up an object COMP anywhere in the network. Relative paths are
+    /// resolved from this operator.
+    pub fn get_object(&self, path: &str) -> Option<&'cook object::ObjectInput> {
+        let path = ffi::CString::new(path).ok()?;
+        let object = unsafe { self.inputs.getObject(path.as_ptr()) };
+        if object.is_null() {
+            None
+        } else {
+            Some(object::ObjectInput::ref_cast(unsafe { &*object }))
+        }
+    }
 }
 
 pub struct DynamicMenuInfo<'cook> {
@@ -260,12 +552,16 @@ impl<'cook> DynamicMenuInfo<'cook> {
         unsafe { ffi::CStr::from_ptr(name).to_str().unwrap() }
     }
 
+    /// Add an entry to the menu. TouchDesigner copies the strings. Returns
+    /// false if the entry could not be added.
     pub fn add_menu_entry(&mut self, name: &str, label: &str) -> bool {
+        let (Ok(name), Ok(label)) = (ffi::CString::new(name), ffi::CString::new(label)) else {
+            return false;
+        };
         unsafe {
-            self.menu_info.as_mut().addMenuEntry(
-                ffi::CString::new(name).unwrap().into_raw(),
-                ffi::CString::new(label).unwrap().into_raw(),
-            )
+            self.menu_info
+                .as_mut()
+                .addMenuEntry(name.as_ptr(), label.as_ptr())
         }
     }
 }
@@ -282,27 +578,19 @@ impl<'cook> ParamInputs<'cook> {
     }
 
     /// Get a float parameter.
-    pub fn get_float(&self, name: &str, index: usize) -> f64 {
-        unsafe {
-            self.inputs
-                .getParDouble(ffi::CString::new(name).unwrap().into_raw(), index as i32)
-        }
+    pub fn get_float(&self, name: &(impl ParamName + ?Sized), index: usize) -> f64 {
+        unsafe { name.with_c_str(|name| self.inputs.getParDouble(name.as_ptr(), index as i32)) }
     }
 
     /// Get an integer parameter.
-    pub fn get_int(&self, name: &str, index: usize) -> i32 {
-        unsafe {
-            self.inputs
-                .getParInt(ffi::CString::new(name).unwrap().into_raw(), index as i32)
-        }
+    pub fn get_int(&self, name: &(impl ParamName + ?Sized), index: usize) -> i32 {
+        unsafe { name.with_c_str(|name| self.inputs.getParInt(name.as_ptr(), index as i32)) }
     }
 
     /// Get a string parameter.
-    pub fn get_string(&self, name: &str) -> &str {
+    pub fn get_string(&self, name: &(impl ParamName + ?Sized)) -> &str {
         unsafe {
-            let res = self
-                .inputs
-                .getParString(ffi::CString::new(name).unwrap().into_raw());
+            let res = name.with_c_str(|name| self.inputs.getParString(name.as_ptr()));
 
             if res.is_null() {
                 return "";
@@ -313,28 +601,21 @@ impl<'cook> ParamInputs<'cook> {
     }
 
     /// Get a toggle parameter.
-    pub fn get_toggle(&self, name: &str) -> bool {
-        unsafe {
-            self.inputs
-                .getParInt(ffi::CString::new(name).unwrap().into_raw(), 0)
-                != 0
-        }
+    pub fn get_toggle(&self, name: &(impl ParamName + ?Sized)) -> bool {
+        unsafe { name.with_c_str(|name| self.inputs.getParInt(name.as_ptr(), 0)) != 0 }
     }
 
     /// Enable or disable a parameter.
-    pub fn enable_param(&self, name: &str, enable: bool) {
+    pub fn enable_param(&self, name: &(impl ParamName + ?Sized), enable: bool) {
         unsafe {
-            self.inputs
-                .enablePar(ffi::CString::new(name).unwrap().into_raw(), enable);
+            name.with_c_str(|name| self.inputs.enablePar(name.as_ptr(), enable));
         }
     }
 
     /// Get a chop parameter.
-    fn get_chop(&self, name: &str) -> ChopParam {
+    fn get_chop(&self, name: &ffi::CStr) -> ChopParam {
         unsafe {
-            let chop = self
-                .inputs
-                .getParCHOP(ffi::CString::new(name).unwrap().into_raw());
+            let chop = self.inputs.getParCHOP(name.as_ptr());
             if chop.is_null() {
                 ChopParam { input: None }
             } else {
@@ -343,11 +624,9 @@ impl<'cook> ParamInputs<'cook> {
         }
     }
 
-    fn get_sop(&self, name: &str) -> SopParam {
+    fn get_sop(&self, name: &ffi::CStr) -> SopParam {
         unsafe {
-            let sop = self
-                .inputs
-                .getParSOP(ffi::CString::new(name).unwrap().into_raw());
+            let sop = self.inputs.getParSOP(name.as_ptr());
             if sop.is_null() {
                 SopParam { input: None }
             } else {
@@ -356,11 +635,9 @@ impl<'cook> ParamInputs<'cook> {
         }
     }
 
-    fn get_top(&self, name: &str) -> TopParam {
+    fn get_top(&self, name: &ffi::CStr) -> TopParam {
         unsafe {
-            let top = self
-                .inputs
-                .getParTOP(ffi::CString::new(name).unwrap().into_raw());
+            let top = self.inputs.getParTOP(name.as_ptr());
             if top.is_null() {
                 TopParam { input: None }
             } else {
@@ -369,11 +646,9 @@ impl<'cook> ParamInputs<'cook> {
         }
     }
 
-    fn get_dat(&self, name: &str) -> DatParam {
+    fn get_dat(&self, name: &ffi::CStr) -> DatParam {
         unsafe {
-            let dat = self
-                .inputs
-                .getParDAT(ffi::CString::new(name).unwrap().into_raw());
+            let dat = self.inputs.getParDAT(name.as_ptr());
             if dat.is_null() {
                 DatParam { input: None }
             } else {
@@ -382,12 +657,39 @@ impl<'cook> ParamInputs<'cook> {
         }
     }
 
+    fn get_object(&self, name: &'static ffi::CStr) -> ObjectParam {
+        let object = unsafe { self.inputs.getParObject(name.as_ptr()) };
+        ObjectParam {
+            name,
+            input: (!object.is_null()).then_some(object),
+        }
+    }
+
+    /// Get the transform from the space of one object parameter into the
+    /// space of another, e.g. to place geometry relative to a camera.
+    pub fn relative_transform(
+        &self,
+        from: &(impl ParamName + ?Sized),
+        to: &(impl ParamName + ?Sized),
+    ) -> Option<object::Matrix4> {
+        let mut matrix = [[0.0; 4]; 4];
+        let found = from.with_c_str(|from| {
+            to.with_c_str(|to| unsafe {
+                cxx::getRelativeTransformMatrix(
+                    self.inputs,
+                    from.as_ptr(),
+                    to.as_ptr(),
+                    matrix.as_mut_ptr() as *mut f64,
+                )
+            })
+        });
+        found.then_some(matrix)
+    }
+
     #[cfg(feature = "python")]
-    fn get_python(&self, name: &str) -> *mut pyo3::ffi::PyObject {
+    fn get_python(&self, name: &ffi::CStr) -> *mut pyo3::ffi::PyObject {
         unsafe {
-            let python = self
-                .inputs
-                .getParPython(ffi::CString::new(name).unwrap().into_raw());
+            let python = self.inputs.getParPython(name.as_ptr());
             if python.is_null() {
                 std::ptr::null_mut()
             } else {
@@ -396,11 +698,11 @@ impl<'cook> ParamInputs<'cook> {
         }
     }
 
-    fn get_double_arr<const N: usize>(&self, name: &str) -> [f64; N] {
+    fn get_double_arr<const N: usize>(&self, name: &(impl ParamName + ?Sized)) -> [f64; N] {
         assert!(N > 1 && N <= 4);
-        unsafe {
+        name.with_c_str(|name| unsafe {
             let mut arr = [0.0; N];
-            let name = ffi::CString::new(name).unwrap().into_raw();
+            let name = name.as_ptr();
             match N {
                 2 => {
                     let mut a = 0.0;
@@ -445,14 +747,14 @@ impl<'cook> ParamInputs<'cook> {
             };
 
             arr
-        }
+        })
     }
 
-    fn get_int_arr<const N: usize>(&self, name: &str) -> [i32; N] {
+    fn get_int_arr<const N: usize>(&self, name: &(impl ParamName + ?Sized)) -> [i32; N] {
         assert!(N > 1 && N <= 4);
-        unsafe {
+        name.with_c_str(|name| unsafe {
             let mut arr = [0; N];
-            let name = ffi::CString::new(name).unwrap().into_raw();
+            let name = name.as_ptr();
             match N {
                 2 => {
                     let mut a = 0;
@@ -496,7 +798,7 @@ impl<'cook> ParamInputs<'cook> {
                 _ => {}
             };
             arr
-        }
+        })
     }
 }
 
@@ -0,0 +1,213 @@
+//! Providers of the entries of dynamic menu parameters.
+
+use crate::chop::{match_pattern, ChopInput};
+use crate::dat::DatInput;
+use crate::param::{intern, MenuParam, ParamScope};
+use crate::{cxx, DynamicMenuInfo, ParamInputs};
+use ref_cast::RefCast;
+use std::marker::PhantomData;
+
+/// The inputs of an operator while one of its dynamic menus is built.
+pub struct MenuInputs<'cook> {
+    inputs: &'cook cxx::OP_Inputs,
+}
+
+impl<'cook> MenuInputs<'cook> {
+    pub fn new(inputs: &'cook cxx::OP_Inputs) -> Self {
+        Self { inputs }
+    }
+
+    pub fn params(&self) -> ParamInputs<'cook> {
+        ParamInputs::new(self.inputs)
+    }
+
+    /// The CHOP connected to an input, if any.
+    pub fn chop_input(&self, index: usize) -> Option<&'cook ChopInput> {
+        let input = self.inputs.getInputCHOP(index as i32);
+        if input.is_null() {
+            None
+        } else {
+            Some(ChopInput::ref_cast(unsafe { &*input }))
+        }
+    }
+
+    /// The DAT connected to an input, if any.
+    pub fn dat_input(&self, index: usize) -> Option<&'cook DatInput> {
+        let input = self.inputs.getInputDAT(index as i32);
+        if input.is_null() {
+            None
+        } else {
+            Some(DatInput::ref_cast(unsafe { &*input }))
+        }
+    }
+}
+
+/// Fills a dynamic menu, attached to a field with
+/// `#[param(menu_provider = "ChannelMenu::input(0)")]`.
+pub trait MenuProvider {
+    /// Add the entries of the menu. `scope` is the scope of the menu parameter,
+    /// used to look up other parameters of the same struct.
+    fn build(&self, scope: &ParamScope, inputs: &MenuInputs, menu: &mut DynamicMenuInfo);
+}
+
+/// Where to find an operator for a menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuSource {
+    /// An input of the operator.
+    Input(usize),
+    /// An operator parameter of the same struct, by name.
+    Param(&'static str),
+}
+
+/// The files in the folder of a folder parameter whose name matches a pattern,
+/// e.g. `*.wav *.aif`. See [`match_pattern`] for the syntax.
+#[derive(Debug, Clone, Copy)]
+pub struct FileMenu {
+    folder: &'static str,
+    pattern: &'static str,
+}
+
+impl FileMenu {
+    pub fn new(folder: &'static str, pattern: &'static str) -> Self {
+        Self { folder, pattern }
+    }
+}
+
+impl MenuProvider for FileMenu {
+    fn build(&self, scope: &ParamScope, inputs: &MenuInputs, menu: &mut DynamicMenuInfo) {
+        let params = inputs.params();
+        let folder = params.get_string(scope.c_name(intern(self.folder)));
+        let Ok(entries) = std::fs::read_dir(folder) else {
+            return;
+        };
+        let mut files = entries
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()))
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| match_pattern(self.pattern, name))
+            .collect::<Vec<_>>();
+        files.sort();
+        for file in files {
+            menu.add_menu_entry(&file, &file);
+        }
+    }
+}
+
+/// The names of the channels of a CHOP.
+#[derive(Debug, Clone, Copy)]
+pub struct ChannelMenu {
+    source: MenuSource,
+}
+
+impl ChannelMenu {
+    /// The channels of the CHOP connected to an input.
+    pub fn input(index: usize) -> Self {
+        Self {
+            source: MenuSource::Input(index),
+        }
+    }
+
+    /// The channels of the CHOP of a CHOP parameter.
+    pub fn param(name: &'static str) -> Self {
+        Self {
+            source: MenuSource::Param(name),
+        }
+    }
+}
+
+impl MenuProvider for ChannelMenu {
+    fn build(&self, scope: &ParamScope, inputs: &MenuInputs, menu: &mut DynamicMenuInfo) {
+        let params = inputs.params();
+        let chop_param;
+        let chop = match self.source {
+            MenuSource::Input(index) => inputs.chop_input(index),
+            MenuSource::Param(name) => {
+                chop_param = params.get_chop(scope.c_name(intern(name)));
+                chop_param.input()
+            }
+        };
+        for (name, _) in chop.into_iter().flat_map(ChopInput::channels) {
+            menu.add_menu_entry(name, name);
+        }
+    }
+}
+
+/// The values of a column of a table DAT.
+#[derive(Debug, Clone, Copy)]
+pub struct DatColumnMenu {
+    source: MenuSource,
+    column: usize,
+    header: bool,
+}
+
+impl DatColumnMenu {
+    /// A column of the DAT connected to an input.
+    pub fn input(index: usize, column: usize) -> Self {
+        Self {
+            source: MenuSource::Input(index),
+            column,
+            header: false,
+        }
+    }
+
+    /// A column of the DAT of a DAT parameter.
+    pub fn param(name: &'static str, column: usize) -> Self {
+        Self {
+            source: MenuSource::Param(name),
+            column,
+            header: false,
+        }
+    }
+
+    /// Leave out the first row of the table.
+    pub fn with_header(mut self) -> Self {
+        self.header = true;
+        self
+    }
+}
+
+impl MenuProvider for DatColumnMenu {
+    fn build(&self, scope: &ParamScope, inputs: &MenuInputs, menu: &mut DynamicMenuInfo) {
+        let params = inputs.params();
+        let dat_param;
+        let dat = match self.source {
+            MenuSource::Input(index) => inputs.dat_input(index),
+            MenuSource::Param(name) => {
+                dat_param = params.get_dat(scope.c_name(intern(name)));
+                dat_param.input()
+            }
+        };
+        let Some(dat) = dat else {
+            return;
+        };
+        let first = if self.header { 1 } else { 0 };
+        for row in first..dat.num_rows() {
+            if let Some(value) = dat.cell(row, self.column) {
+                menu.add_menu_entry(value, value);
+            }
+        }
+    }
+}
+
+/// The variants of an enum deriving `Param`.
+pub struct EnumMenu<T>(PhantomData<T>);
+
+impl<T> EnumMenu<T> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T> Default for EnumMenu<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: MenuParam> MenuProvider for EnumMenu<T> {
+    fn build(&self, _scope: &ParamScope, _inputs: &MenuInputs, menu: &mut DynamicMenuInfo) {
+        for (name, label) in T::names().iter().zip(T::labels()) {
+            menu.add_menu_entry(name, &label);
+        }
+    }
+}
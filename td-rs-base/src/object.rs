@@ -0,0 +1,52 @@
+use crate::cxx::OP_ObjectInput;
+use ref_cast::RefCast;
+use std::ffi;
+
+/// A row-major 4x4 transform, with the translation in the last column.
+pub type Matrix4 = [[f64; 4]; 4];
+
+/// An object COMP, e.g. a Geometry or Camera COMP.
+#[repr(transparent)]
+#[derive(RefCast)]
+pub struct ObjectInput {
+    input: OP_ObjectInput,
+}
+
+impl ObjectInput {
+    /// Get the path of the object.
+    pub fn path(&self) -> &str {
+        if self.input.opPath.is_null() {
+            return "";
+        }
+        unsafe { ffi::CStr::from_ptr(self.input.opPath) }
+            .to_str()
+            .unwrap_or_default()
+    }
+
+    /// Get the unique id of the object.
+    pub fn id(&self) -> u32 {
+        self.input.opId
+    }
+
+    /// Get the number of times the object has cooked.
+    pub fn total_cooks(&self) -> i64 {
+        self.input.totalCooks
+    }
+
+    /// Get the transform from the space of the object into world space.
+    pub fn world_transform(&self) -> Matrix4 {
+        self.input.worldTransform
+    }
+
+    /// Get the transform from the space of the object into the space of its
+    /// parent.
+    pub fn local_transform(&self) -> Matrix4 {
+        self.input.localTransform
+    }
+
+    /// Get the position of the object in world space.
+    pub fn world_position(&self) -> [f64; 3] {
+        let m = &self.input.worldTransform;
+        [m[0][3], m[1][3], m[2][3]]
+    }
+}
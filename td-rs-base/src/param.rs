@@ -1,12 +1,85 @@
 use crate::chop::ChopInput;
+use crate::menu::MenuInputs;
+use crate::object::{Matrix4, ObjectInput};
 use crate::sop::{Color, SopInput};
-use crate::{cxx, ParamInputs};
+use crate::{cxx, DynamicMenuInfo, ParamInputs};
 use ref_cast::RefCast;
-use std::ffi;
-use std::ffi::{c_char, CString};
+use std::collections::BTreeMap;
+use std::ffi::{c_char, CStr, CString};
 use std::ops::{Deref, DerefMut};
 use std::path::PathBuf;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::{Mutex, RwLock};
+
+/// Every string interned so far, see [`intern`].
+static INTERNED: RwLock<BTreeMap<&'static str, &'static CStr>> = RwLock::new(BTreeMap::new());
+
+/// Get a nul terminated copy of a string that lives for the rest of the
+/// program. Each distinct string is only ever allocated once, so parameter
+/// names can be interned when they are registered and reused on every cook.
+pub fn intern(s: &str) -> &'static CStr {
+    if let Some(c_str) = INTERNED.read().unwrap().get(s) {
+        return c_str;
+    }
+    let mut interned = INTERNED.write().unwrap();
+    if let Some(c_str) = interned.get(s) {
+        return c_str;
+    }
+    let c_str: &'static CStr = Box::leak(
+        CString::new(s)
+            .expect("parameter strings must not contain nul bytes")
+            .into_boxed_c_str(),
+    );
+    let key = c_str.to_str().unwrap();
+    interned.insert(key, c_str);
+    c_str
+}
+
+/// The longest `str` name that is looked up without allocating.
+const STACK_NAME_LEN: usize = 64;
+
+/// A name that can be passed to TouchDesigner to look up a parameter.
+///
+/// A `CStr`, e.g. a `c"Scale"` literal or the name handed to [`Param::update`],
+/// is used as is. A `str` is copied into a nul terminated buffer for the
+/// lookup only, so looking up a name neither locks nor leaks; use [`intern`]
+/// to keep a name around instead.
+pub trait ParamName {
+    /// Call `f` with the nul terminated name.
+    fn with_c_str<R>(&self, f: impl FnOnce(&CStr) -> R) -> R;
+}
+
+impl ParamName for CStr {
+    fn with_c_str<R>(&self, f: impl FnOnce(&CStr) -> R) -> R {
+        f(self)
+    }
+}
+
+impl ParamName for CString {
+    fn with_c_str<R>(&self, f: impl FnOnce(&CStr) -> R) -> R {
+        f(self)
+    }
+}
+
+impl ParamName for str {
+    fn with_c_str<R>(&self, f: impl FnOnce(&CStr) -> R) -> R {
+        const NUL_ERROR: &str = "parameter strings must not contain nul bytes";
+        if self.len() < STACK_NAME_LEN {
+            let mut buf = [0; STACK_NAME_LEN];
+            buf[..self.len()].copy_from_slice(self.as_bytes());
+            f(CStr::from_bytes_with_nul(&buf[..=self.len()]).expect(NUL_ERROR))
+        } else {
+            f(&CString::new(self).expect(NUL_ERROR))
+        }
+    }
+}
+
+impl ParamName for String {
+    fn with_c_str<R>(&self, f: impl FnOnce(&CStr) -> R) -> R {
+        self.as_str().with_c_str(f)
+    }
+}
 
 /// A numeric parameter.
 // TODO: switch to enum to describe parameter types
@@ -55,17 +128,276 @@ impl Default for NumericParameter {
 /// Trait for defining operator parameters.
 pub trait OperatorParams {
     /// Register parameters with the parameter manager.
-    fn register(&mut self, parameter_manager: &mut ParameterManager);
-    /// Update parameters from operator input.
-    fn update(&mut self, inputs: &ParamInputs);
+    fn register(&mut self, parameter_manager: &mut ParameterManager) {
+        self.register_scoped(&ParamScope::default(), parameter_manager);
+    }
+    /// Update parameters from operator input, returning the parameters whose
+    /// value changed.
+    fn update(&mut self, inputs: &ParamInputs) -> ParamsChanged {
+        self.update_scoped(&ParamScope::default(), inputs)
+    }
+    /// Register parameters with the parameter manager within `scope`.
+    fn register_scoped(&mut self, scope: &ParamScope, parameter_manager: &mut ParameterManager);
+    /// Update parameters registered within `scope` from operator input.
+    fn update_scoped(&mut self, scope: &ParamScope, inputs: &ParamInputs) -> ParamsChanged;
+    /// Fill a dynamic menu from the `menu_provider` of its parameter, returning
+    /// whether the parameter has one.
+    fn build_menu(&self, inputs: &MenuInputs, menu: &mut DynamicMenuInfo) -> bool {
+        self.build_menu_scoped(&ParamScope::default(), inputs, menu)
+    }
+    /// Fill a dynamic menu of a parameter registered within `scope`.
+    fn build_menu_scoped(
+        &self,
+        _scope: &ParamScope,
+        _inputs: &MenuInputs,
+        _menu: &mut DynamicMenuInfo,
+    ) -> bool {
+        false
+    }
+    /// Record a press of a pulse parameter, returning whether it is one of
+    /// these parameters.
+    fn press_pulse(&mut self, name: &str) -> bool {
+        self.press_pulse_scoped(&ParamScope::default(), name)
+    }
+    /// Record a press of a pulse parameter registered within `scope`.
+    fn press_pulse_scoped(&mut self, _scope: &ParamScope, _name: &str) -> bool {
+        false
+    }
+    /// These parameters as presets, for structs marked `#[params(presets)]`.
+    /// Operators update such parameters with
+    /// [`PresetParams::update_presets`](crate::PresetParams::update_presets).
+    #[cfg(feature = "presets")]
+    fn presets(&mut self) -> Option<&mut dyn crate::PresetParams> {
+        None
+    }
+}
+
+/// Calls the `on_pulse` handlers of pulse parameters, methods of the operator
+/// `O`. Implemented by `#[derive(Params)]` for structs marked
+/// `#[params(op = "O")]`, to be called from [`Op::pulse_pressed`](crate::Op::pulse_pressed).
+/// Other structs implement it for every operator their flattened fields have
+/// handlers for.
+pub trait PulseHandlers<O: ?Sized> {
+    /// Call the handler of a pulse parameter, returning whether it has one.
+    fn handle_pulse(op: &mut O, name: &str) -> bool {
+        Self::handle_pulse_scoped(op, &ParamScope::default(), name)
+    }
+    /// Call the handler of a pulse parameter registered within `scope`.
+    fn handle_pulse_scoped(op: &mut O, scope: &ParamScope, name: &str) -> bool;
+}
+
+/// Where a parameter struct is registered. A struct embedded with
+/// `#[param(flatten, prefix = "Net", page = "Network")]` has the names and
+/// labels of its parameters prefixed, and is moved to another page.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParamScope {
+    prefix: String,
+    label_prefix: String,
+    page: Option<String>,
+}
+
+impl ParamScope {
+    /// The scope of a struct embedded in this one. The page of an outer scope
+    /// takes precedence, so moving a struct also moves the structs it embeds.
+    pub fn nested(&self, prefix: &str, page: Option<&str>) -> ParamScope {
+        ParamScope {
+            prefix: self.name(prefix),
+            label_prefix: self.label(prefix),
+            page: self.page.clone().or(page.map(str::to_string)),
+        }
+    }
+
+    /// The name of a parameter in this scope. Only the first letter of a name
+    /// may be uppercase, so the name is lowercased after the prefix.
+    pub fn name(&self, name: &str) -> String {
+        if self.prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}{}", self.prefix, name.to_lowercase())
+        }
+    }
+
+    /// The interned name of a parameter in this scope.
+    pub fn c_name(&self, name: &'static CStr) -> &'static CStr {
+        if self.prefix.is_empty() {
+            name
+        } else {
+            intern(&self.name(name.to_str().unwrap_or_default()))
+        }
+    }
+
+    /// The label of a parameter in this scope.
+    pub fn label(&self, label: &str) -> String {
+        if self.label_prefix.is_empty() {
+            label.to_string()
+        } else {
+            format!("{} {}", self.label_prefix, label)
+        }
+    }
+
+    /// The page of a parameter in this scope.
+    pub fn page(&self, page: &str) -> String {
+        self.page.as_deref().unwrap_or(page).to_string()
+    }
+}
+
+/// The names of the parameters of a struct and the scopes of the structs it
+/// embeds, worked out once for every scope the struct is registered in.
+/// `#[derive(Params)]` keeps one per struct, so updates during a cook neither
+/// format nor intern names.
+pub struct ScopedNames {
+    head: AtomicPtr<ScopedEntry>,
+    lock: Mutex<()>,
+}
+
+/// The names of the parameters of a struct within a scope, in the order of its
+/// fields, see [`ScopedNames`].
+#[derive(Debug)]
+pub struct ScopedEntry {
+    scope: ParamScope,
+    names: Box<[&'static CStr]>,
+    nested: Box<[ParamScope]>,
+    next: Option<&'static ScopedEntry>,
+}
+
+impl ScopedNames {
+    pub const fn new() -> Self {
+        Self {
+            head: AtomicPtr::new(std::ptr::null_mut()),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// The names within `scope`. The first time a scope is seen, `init` gives
+    /// the interned names of the parameters and the scopes of the embedded
+    /// structs. Later lookups neither allocate nor lock.
+    pub fn get(
+        &self,
+        scope: &ParamScope,
+        init: impl FnOnce() -> (Vec<&'static CStr>, Vec<ParamScope>),
+    ) -> &'static ScopedEntry {
+        if let Some(entry) = self.find(scope) {
+            return entry;
+        }
+        let _lock = self.lock.lock().unwrap();
+        if let Some(entry) = self.find(scope) {
+            return entry;
+        }
+        let (names, nested) = init();
+        let entry: &'static ScopedEntry = Box::leak(Box::new(ScopedEntry {
+            scope: scope.clone(),
+            names: names.into_boxed_slice(),
+            nested: nested.into_boxed_slice(),
+            next: self.first(),
+        }));
+        self.head.store(
+            entry as *const ScopedEntry as *mut ScopedEntry,
+            Ordering::Release,
+        );
+        entry
+    }
+
+    fn first(&self) -> Option<&'static ScopedEntry> {
+        // SAFETY: entries are leaked once fully built and never changed again
+        unsafe { self.head.load(Ordering::Acquire).as_ref() }
+    }
+
+    fn find(&self, scope: &ParamScope) -> Option<&'static ScopedEntry> {
+        std::iter::successors(self.first(), |entry| entry.next).find(|entry| entry.scope == *scope)
+    }
+}
+
+impl Default for ScopedNames {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScopedEntry {
+    /// The name of the `index`th parameter.
+    pub fn name(&self, index: usize) -> &'static CStr {
+        self.names[index]
+    }
+
+    /// The scope of the `index`th embedded struct.
+    pub fn nested(&self, index: usize) -> &ParamScope {
+        &self.nested[index]
+    }
+}
+
+/// The names of the parameters whose value changed during an update.
+///
+/// Values are compared with those of the previous update, so the first update
+/// only reports parameters that differ from the initial values of the struct.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParamsChanged {
+    names: Vec<&'static CStr>,
+}
+
+impl ParamsChanged {
+    /// Mark a parameter as changed.
+    pub fn insert(&mut self, name: &'static CStr) {
+        if !self.names.contains(&name) {
+            self.names.push(name);
+        }
+    }
+
+    /// Whether the parameter changed.
+    pub fn contains(&self, name: &(impl ParamName + ?Sized)) -> bool {
+        name.with_c_str(|name| self.names.contains(&name))
+    }
+
+    /// Whether any of the parameters changed.
+    pub fn contains_any<N: ParamName + ?Sized>(&self, names: &[&N]) -> bool {
+        names.iter().any(|name| self.contains(*name))
+    }
+
+    /// The names of the changed parameters, in registration order.
+    pub fn iter(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.names
+            .iter()
+            .map(|name| name.to_str().unwrap_or_default())
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+impl IntoIterator for ParamsChanged {
+    type Item = &'static CStr;
+    type IntoIter = std::vec::IntoIter<&'static CStr>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.names.into_iter()
+    }
+}
+
+impl Extend<&'static CStr> for ParamsChanged {
+    fn extend<I: IntoIterator<Item = &'static CStr>>(&mut self, names: I) {
+        for name in names {
+            self.insert(name);
+        }
+    }
+}
+
+/// Replace `value` with `new`, returning whether it changed.
+fn replace<T: PartialEq>(value: &mut T, new: T) -> bool {
+    let changed = *value != new;
+    *value = new;
+    changed
 }
 
 impl From<NumericParameter> for cxx::OP_NumericParameter {
     fn from(param: NumericParameter) -> Self {
         cxx::OP_NumericParameter {
-            name: ffi::CString::new(param.name).unwrap().into_raw(),
-            label: ffi::CString::new(param.label).unwrap().into_raw(),
-            page: ffi::CString::new(param.page).unwrap().into_raw(),
+            name: intern(&param.name).as_ptr(),
+            label: intern(&param.label).as_ptr(),
+            page: intern(&param.page).as_ptr(),
             defaultValues: param.default_values,
             minValues: param.min_values,
             maxValues: param.max_values,
@@ -94,10 +426,10 @@ pub struct StringParameter {
 impl From<StringParameter> for cxx::OP_StringParameter {
     fn from(param: StringParameter) -> Self {
         cxx::OP_StringParameter {
-            name: ffi::CString::new(param.name).unwrap().into_raw(),
-            label: ffi::CString::new(param.label).unwrap().into_raw(),
-            page: ffi::CString::new(param.page).unwrap().into_raw(),
-            defaultValue: ffi::CString::new(param.default_value).unwrap().into_raw(),
+            name: intern(&param.name).as_ptr(),
+            label: intern(&param.label).as_ptr(),
+            page: intern(&param.page).as_ptr(),
+            defaultValue: intern(&param.default_value).as_ptr(),
             reserved: Default::default(),
         }
     }
@@ -129,12 +461,24 @@ impl<'cook> ParameterManager<'cook> {
         self.manager.as_mut().appendPulse(&param);
     }
 
+    /// Append a float parameter with `size` components, from 1 to 4.
+    pub fn append_floats(&mut self, param: NumericParameter, size: usize) {
+        let param = param.into();
+        self.manager.as_mut().appendFloat(&param, size as i32);
+    }
+
     /// Append an integer parameter.
     pub fn append_int(&mut self, param: NumericParameter) {
         let param = param.into();
         self.manager.as_mut().appendInt(&param, 1);
     }
 
+    /// Append an integer parameter with `size` components, from 1 to 4.
+    pub fn append_ints(&mut self, param: NumericParameter, size: usize) {
+        let param = param.into();
+        self.manager.as_mut().appendInt(&param, size as i32);
+    }
+
     /// Append an xy parameter.
     pub fn append_xy(&mut self, param: NumericParameter) {
         let param = param.into();
@@ -222,34 +566,44 @@ impl<'cook> ParameterManager<'cook> {
     pub fn append_menu(&mut self, param: StringParameter, names: &[String], labels: &[String]) {
         assert_eq!(names.len(), labels.len());
         let n_items = names.len() as i32;
-        let c_strings: Vec<CString> = names
-            .iter()
-            .map(|s| CString::new(s.as_bytes()).unwrap())
-            .collect();
-
-        let name_ptrs: Vec<*const c_char> = c_strings.iter().map(|cs| cs.as_ptr()).collect();
-        let names: *mut *const c_char = name_ptrs.as_ptr() as *mut *const c_char;
+        let (_names, name_ptrs) = c_string_array(names);
+        let (_labels, label_ptrs) = c_string_array(labels);
 
-        let c_strings: Vec<CString> = labels
-            .iter()
-            .map(|s| CString::new(s.as_bytes()).unwrap())
-            .collect();
+        let param = param.into();
+        unsafe {
+            self.manager.as_mut().appendMenu(
+                &param,
+                n_items,
+                name_ptrs.as_ptr() as *mut *const c_char,
+                label_ptrs.as_ptr() as *mut *const c_char,
+            );
+        }
+    }
 
-        let label_ptrs: Vec<*const c_char> = c_strings.iter().map(|cs| cs.as_ptr()).collect();
-        let labels: *mut *const c_char = label_ptrs.as_ptr() as *mut *const c_char;
+    /// Append a string parameter with a menu of suggested values. Unlike
+    /// [`Self::append_menu`], any other string can be entered as well.
+    pub fn append_string_menu(
+        &mut self,
+        param: StringParameter,
+        names: &[String],
+        labels: &[String],
+    ) {
+        assert_eq!(names.len(), labels.len());
+        let n_items = names.len() as i32;
+        let (_names, name_ptrs) = c_string_array(names);
+        let (_labels, label_ptrs) = c_string_array(labels);
 
         let param = param.into();
         unsafe {
-            self.manager
-                .as_mut()
-                .appendMenu(&param, n_items, names, labels);
+            self.manager.as_mut().appendStringMenu(
+                &param,
+                n_items,
+                name_ptrs.as_ptr() as *mut *const c_char,
+                label_ptrs.as_ptr() as *mut *const c_char,
+            );
         }
     }
 
-    // pub fn append_string_menu(&mut self, param: StringParameter, names: &[&str], labels: &[&str]) {
-    //     self.manager.as_mut().appendStringMenu(&param, names.len(), names.as_ptr(), labels.as_ptr());
-    // }
-
     /// Append a sop reference parameter.
     pub fn append_sop(&mut self, param: StringParameter) {
         let param = param.into();
@@ -311,6 +665,31 @@ impl<'cook> ParameterManager<'cook> {
     }
 }
 
+/// The widget used for a float parameter with several components.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ParamStyle {
+    /// XY for two components, XYZ for three, plain floats otherwise.
+    #[default]
+    Default,
+    Xy,
+    Xyz,
+    Uv,
+    Uvw,
+    Wh,
+    Rgb,
+    Rgba,
+}
+
+/// C strings for a menu, and pointers to them which live as long as the strings.
+fn c_string_array(items: &[String]) -> (Vec<CString>, Vec<*const c_char>) {
+    let c_strings: Vec<CString> = items
+        .iter()
+        .map(|s| CString::new(s.as_bytes()).unwrap())
+        .collect();
+    let ptrs = c_strings.iter().map(|cs| cs.as_ptr()).collect();
+    (c_strings, ptrs)
+}
+
 /// Options for creating parameters in derive macro.
 /// Not intended for direct use.
 #[derive(Debug)]
@@ -318,12 +697,34 @@ pub struct ParamOptions {
     pub name: String,
     pub label: String,
     pub page: String,
-    pub min: f64,
-    pub max: f64,
-    pub min_slider: f64,
-    pub max_slider: f64,
+    /// Per component, scalar parameters only use the first.
+    pub min: [f64; 4],
+    pub max: [f64; 4],
+    pub min_slider: [f64; 4],
+    pub max_slider: [f64; 4],
     pub clamp: bool,
-    pub default: f64,
+    pub default: [f64; 4],
+    pub style: ParamStyle,
+    /// Menu entries, for string menus.
+    pub options: Vec<String>,
+}
+
+impl Default for ParamOptions {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            label: String::new(),
+            page: String::new(),
+            min: [0.0; 4],
+            max: [1.0; 4],
+            min_slider: [0.0; 4],
+            max_slider: [1.0; 4],
+            clamp: false,
+            default: [0.0; 4],
+            style: ParamStyle::Default,
+            options: Vec::new(),
+        }
+    }
 }
 
 impl From<ParamOptions> for NumericParameter {
@@ -332,13 +733,13 @@ impl From<ParamOptions> for NumericParameter {
             name: options.name,
             label: options.label,
             page: options.page,
-            default_values: [options.default; 4],
-            min_values: [options.min; 4],
-            max_values: [options.max; 4],
+            default_values: options.default,
+            min_values: options.min,
+            max_values: options.max,
             clamp_mins: [options.clamp; 4],
             clamp_maxes: [options.clamp; 4],
-            min_sliders: [options.min_slider; 4],
-            max_sliders: [options.max_slider; 4],
+            min_sliders: options.min_slider,
+            max_sliders: options.max_slider,
         }
     }
 }
@@ -358,8 +759,12 @@ impl From<ParamOptions> for StringParameter {
 pub trait Param {
     /// Register parameter with the parameter manager.
     fn register(&self, options: ParamOptions, parameter_manager: &mut ParameterManager);
-    /// Update parameter from operator input.
-    fn update(&mut self, name: &str, inputs: &ParamInputs);
+    /// Update parameter from operator input, returning whether its value
+    /// changed. `name` is the interned name the parameter was registered with.
+    fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool;
+    /// Called when TouchDesigner reports a press of this parameter, for pulse
+    /// parameters.
+    fn press(&mut self) {}
 }
 
 macro_rules! impl_param_int {
@@ -371,8 +776,8 @@ macro_rules! impl_param_int {
                 parameter_manager.append_int(param);
             }
 
-            fn update(&mut self, name: &str, inputs: &ParamInputs) {
-                *self = inputs.get_int(name, 0) as $t;
+            fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool {
+                replace(self, inputs.get_int(name, 0) as $t)
             }
         }
     };
@@ -400,8 +805,8 @@ macro_rules! impl_param_float {
                 parameter_manager.append_float(param);
             }
 
-            fn update(&mut self, name: &str, inputs: &ParamInputs) {
-                *self = inputs.get_float(name, 0) as $t;
+            fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool {
+                replace(self, inputs.get_float(name, 0) as $t)
             }
         }
     };
@@ -410,6 +815,113 @@ macro_rules! impl_param_float {
 impl_param_float!(f32);
 impl_param_float!(f64);
 
+/// Register a float parameter with `size` components, using the widget of
+/// `options.style` if it has that many components.
+fn register_floats(options: ParamOptions, size: usize, parameter_manager: &mut ParameterManager) {
+    let style = options.style;
+    let param: NumericParameter = options.into();
+    match (size, style) {
+        (2, ParamStyle::Default | ParamStyle::Xy) => parameter_manager.append_xy(param),
+        (2, ParamStyle::Uv) => parameter_manager.append_uv(param),
+        (2, ParamStyle::Wh) => parameter_manager.append_wh(param),
+        (3, ParamStyle::Default | ParamStyle::Xyz) => parameter_manager.append_xyz(param),
+        (3, ParamStyle::Uvw) => parameter_manager.append_uvw(param),
+        (3, ParamStyle::Rgb) => parameter_manager.append_rgb(param),
+        (4, ParamStyle::Rgba) => parameter_manager.append_rgba(param),
+        _ => parameter_manager.append_floats(param, size),
+    }
+}
+
+macro_rules! impl_param_array {
+    ( $( $n:literal ),* ) => {
+        $(
+            impl Param for [f32; $n] {
+                fn register(&self, options: ParamOptions, parameter_manager: &mut ParameterManager) {
+                    register_floats(options, $n, parameter_manager);
+                }
+
+                fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool {
+                    replace(self, inputs.get_double_arr::<$n>(name).map(|v| v as f32))
+                }
+            }
+
+            impl Param for [f64; $n] {
+                fn register(&self, options: ParamOptions, parameter_manager: &mut ParameterManager) {
+                    register_floats(options, $n, parameter_manager);
+                }
+
+                fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool {
+                    replace(self, inputs.get_double_arr::<$n>(name))
+                }
+            }
+
+            impl Param for [i32; $n] {
+                fn register(&self, options: ParamOptions, parameter_manager: &mut ParameterManager) {
+                    parameter_manager.append_ints(options.into(), $n);
+                }
+
+                fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool {
+                    replace(self, inputs.get_int_arr::<$n>(name))
+                }
+            }
+        )*
+    };
+}
+
+impl_param_array!(2, 3, 4);
+
+/// Implement `Param` for vector types by converting to and from arrays.
+#[cfg(any(feature = "glam", feature = "mint"))]
+macro_rules! impl_param_via_array {
+    ( $arr:ty => $( $t:ty ),* ) => {
+        $(
+            impl Param for $t {
+                fn register(&self, options: ParamOptions, parameter_manager: &mut ParameterManager) {
+                    let arr: $arr = (*self).into();
+                    arr.register(options, parameter_manager);
+                }
+
+                fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool {
+                    let mut arr: $arr = (*self).into();
+                    let changed = arr.update(name, inputs);
+                    *self = arr.into();
+                    changed
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "glam")]
+mod glam_params {
+    use super::*;
+
+    impl_param_via_array!([f32; 2] => glam::Vec2);
+    impl_param_via_array!([f32; 3] => glam::Vec3, glam::Vec3A);
+    impl_param_via_array!([f32; 4] => glam::Vec4);
+    impl_param_via_array!([f64; 2] => glam::DVec2);
+    impl_param_via_array!([f64; 3] => glam::DVec3);
+    impl_param_via_array!([f64; 4] => glam::DVec4);
+    impl_param_via_array!([i32; 2] => glam::IVec2);
+    impl_param_via_array!([i32; 3] => glam::IVec3);
+    impl_param_via_array!([i32; 4] => glam::IVec4);
+}
+
+#[cfg(feature = "mint")]
+mod mint_params {
+    use super::*;
+
+    impl_param_via_array!([f32; 2] => mint::Vector2<f32>, mint::Point2<f32>);
+    impl_param_via_array!([f32; 3] => mint::Vector3<f32>, mint::Point3<f32>);
+    impl_param_via_array!([f32; 4] => mint::Vector4<f32>);
+    impl_param_via_array!([f64; 2] => mint::Vector2<f64>, mint::Point2<f64>);
+    impl_param_via_array!([f64; 3] => mint::Vector3<f64>, mint::Point3<f64>);
+    impl_param_via_array!([f64; 4] => mint::Vector4<f64>);
+    impl_param_via_array!([i32; 2] => mint::Vector2<i32>, mint::Point2<i32>);
+    impl_param_via_array!([i32; 3] => mint::Vector3<i32>, mint::Point3<i32>);
+    impl_param_via_array!([i32; 4] => mint::Vector4<i32>);
+}
+
 impl Param for String {
     fn register(&self, options: ParamOptions, parameter_manager: &mut ParameterManager) {
         let mut param: StringParameter = options.into();
@@ -417,8 +929,8 @@ impl Param for String {
         parameter_manager.append_string(param);
     }
 
-    fn update(&mut self, name: &str, inputs: &ParamInputs) {
-        *self = inputs.get_string(name).to_string();
+    fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool {
+        replace(self, inputs.get_string(name).to_string())
     }
 }
 
@@ -429,12 +941,15 @@ impl Param for rgb::RGB8 {
         parameter_manager.append_rgb(param);
     }
 
-    fn update(&mut self, name: &str, inputs: &ParamInputs) {
-        *self = rgb::RGB8::new(
-            inputs.get_int(name, 0) as u8,
-            inputs.get_int(name, 1) as u8,
-            inputs.get_int(name, 2) as u8,
-        );
+    fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool {
+        replace(
+            self,
+            rgb::RGB8::new(
+                inputs.get_int(name, 0) as u8,
+                inputs.get_int(name, 1) as u8,
+                inputs.get_int(name, 2) as u8,
+            ),
+        )
     }
 }
 
@@ -445,12 +960,15 @@ impl Param for rgb::RGB16 {
         parameter_manager.append_rgb(param);
     }
 
-    fn update(&mut self, name: &str, inputs: &ParamInputs) {
-        *self = rgb::RGB16::new(
-            inputs.get_int(name, 0) as u16,
-            inputs.get_int(name, 1) as u16,
-            inputs.get_int(name, 2) as u16,
-        );
+    fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool {
+        replace(
+            self,
+            rgb::RGB16::new(
+                inputs.get_int(name, 0) as u16,
+                inputs.get_int(name, 1) as u16,
+                inputs.get_int(name, 2) as u16,
+            ),
+        )
     }
 }
 
@@ -461,13 +979,16 @@ impl Param for rgb::RGBA8 {
         parameter_manager.append_rgba(param);
     }
 
-    fn update(&mut self, name: &str, inputs: &ParamInputs) {
-        *self = rgb::RGBA8::new(
-            inputs.get_int(name, 0) as u8,
-            inputs.get_int(name, 1) as u8,
-            inputs.get_int(name, 2) as u8,
-            inputs.get_int(name, 3) as u8,
-        );
+    fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool {
+        replace(
+            self,
+            rgb::RGBA8::new(
+                inputs.get_int(name, 0) as u8,
+                inputs.get_int(name, 1) as u8,
+                inputs.get_int(name, 2) as u8,
+                inputs.get_int(name, 3) as u8,
+            ),
+        )
     }
 }
 
@@ -478,17 +999,23 @@ impl Param for rgb::RGBA16 {
         parameter_manager.append_rgba(param);
     }
 
-    fn update(&mut self, name: &str, inputs: &ParamInputs) {
-        *self = rgb::RGBA16::new(
-            inputs.get_int(name, 0) as u16,
-            inputs.get_int(name, 1) as u16,
-            inputs.get_int(name, 2) as u16,
-            inputs.get_int(name, 3) as u16,
-        );
+    fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool {
+        replace(
+            self,
+            rgb::RGBA16::new(
+                inputs.get_int(name, 0) as u16,
+                inputs.get_int(name, 1) as u16,
+                inputs.get_int(name, 2) as u16,
+                inputs.get_int(name, 3) as u16,
+            ),
+        )
     }
 }
 
 /// A parameter wrapping a `PathBuf` that will be registered as a folder parameter.
+#[derive(Default, Clone, Debug)]
+#[cfg_attr(feature = "presets", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "presets", serde(transparent))]
 pub struct FolderParam(PathBuf);
 
 impl Deref for FolderParam {
@@ -512,13 +1039,15 @@ impl Param for FolderParam {
         parameter_manager.append_folder(param);
     }
 
-    fn update(&mut self, name: &str, inputs: &ParamInputs) {
-        self.0 = PathBuf::from(inputs.get_string(name));
+    fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool {
+        replace(&mut self.0, PathBuf::from(inputs.get_string(name)))
     }
 }
 
 /// A parameter wrapping a `PathBuf` that will be registered as a file parameter.
 #[derive(Default, Clone, Debug)]
+#[cfg_attr(feature = "presets", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "presets", serde(transparent))]
 pub struct FileParam(PathBuf);
 
 impl Deref for FileParam {
@@ -542,8 +1071,8 @@ impl Param for FileParam {
         parameter_manager.append_file(param);
     }
 
-    fn update(&mut self, name: &str, inputs: &ParamInputs) {
-        self.0 = PathBuf::from(inputs.get_string(name));
+    fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool {
+        replace(&mut self.0, PathBuf::from(inputs.get_string(name)))
     }
 }
 
@@ -554,8 +1083,8 @@ impl Param for PathBuf {
         parameter_manager.append_file(param);
     }
 
-    fn update(&mut self, name: &str, inputs: &ParamInputs) {
-        *self = PathBuf::from(inputs.get_string(name));
+    fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool {
+        replace(self, PathBuf::from(inputs.get_string(name)))
     }
 }
 
@@ -566,13 +1095,29 @@ impl Param for bool {
         parameter_manager.append_toggle(param);
     }
 
-    fn update(&mut self, name: &str, inputs: &ParamInputs) {
-        *self = inputs.get_toggle(name);
+    fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool {
+        replace(self, inputs.get_toggle(name))
     }
 }
 
-#[derive(Default, Debug, Clone, Copy)]
-pub struct Pulse;
+/// A button which is pressed once. Presses are recorded until they are taken,
+/// so `execute` can check `self.params.reset.take()`.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pulse {
+    pressed: bool,
+}
+
+impl Pulse {
+    /// Whether the pulse was pressed since the last call.
+    pub fn take(&mut self) -> bool {
+        std::mem::take(&mut self.pressed)
+    }
+
+    /// Whether the pulse was pressed, without clearing the press.
+    pub fn is_pressed(&self) -> bool {
+        self.pressed
+    }
+}
 
 impl Param for Pulse {
     fn register(&self, options: ParamOptions, parameter_manager: &mut ParameterManager) {
@@ -580,7 +1125,52 @@ impl Param for Pulse {
         parameter_manager.append_pulse(param);
     }
 
-    fn update(&mut self, _name: &str, _inputs: &ParamInputs) {}
+    fn update(&mut self, _name: &'static CStr, _inputs: &ParamInputs) -> bool {
+        false
+    }
+
+    fn press(&mut self) {
+        self.pressed = true;
+    }
+}
+
+/// A button which is on while held. A press is recorded when the button is
+/// seen held after being released, until it is taken.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Momentary {
+    held: bool,
+    pressed: bool,
+}
+
+impl Momentary {
+    /// Whether the button is held.
+    pub fn is_held(&self) -> bool {
+        self.held
+    }
+
+    /// Whether the button was pressed since the last call.
+    pub fn take(&mut self) -> bool {
+        std::mem::take(&mut self.pressed)
+    }
+
+    /// Record whether the button is held, returning whether that changed.
+    fn set_held(&mut self, held: bool) -> bool {
+        if held && !self.held {
+            self.pressed = true;
+        }
+        replace(&mut self.held, held)
+    }
+}
+
+impl Param for Momentary {
+    fn register(&self, options: ParamOptions, parameter_manager: &mut ParameterManager) {
+        let param: NumericParameter = options.into();
+        parameter_manager.append_momentary(param);
+    }
+
+    fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool {
+        self.set_held(inputs.get_toggle(name))
+    }
 }
 
 /// A chop parameter.
@@ -595,8 +1185,9 @@ impl Param for ChopParam {
         parameter_manager.append_chop(param);
     }
 
-    fn update(&mut self, name: &str, inputs: &ParamInputs) {
-        *self = inputs.get_chop(name);
+    fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool {
+        let param = inputs.get_chop(name);
+        replace(&mut self.input, param.input)
     }
 }
 
@@ -608,6 +1199,131 @@ impl ChopParam {
     }
 }
 
+/// A number that can be modulated per sample by a channel of a CHOP.
+///
+/// Registers the number along with a CHOP parameter and the name of the
+/// channel to use, e.g. `Scale`, `Scalechop` and `Scalechan`. Without a channel
+/// name the first channel of the CHOP is used.
+#[derive(Default, Debug, Clone)]
+pub struct ModulatedParam<T> {
+    value: T,
+    chop: ChopParam,
+    channel: String,
+    /// The name the parameter was last updated with and the names of its CHOP
+    /// and channel parameters, interned on the first update.
+    names: Option<(&'static CStr, &'static CStr, &'static CStr)>,
+}
+
+/// Numbers that can be read from the samples of a CHOP.
+pub trait ModulatedValue: Param + Copy {
+    fn from_sample(sample: f32) -> Self;
+}
+
+impl ModulatedValue for f32 {
+    fn from_sample(sample: f32) -> Self {
+        sample
+    }
+}
+
+impl ModulatedValue for f64 {
+    fn from_sample(sample: f32) -> Self {
+        sample as f64
+    }
+}
+
+impl<T: ModulatedValue> ModulatedParam<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            chop: ChopParam::default(),
+            channel: String::new(),
+            names: None,
+        }
+    }
+
+    /// The value of the parameter itself, ignoring the CHOP.
+    pub fn value(&self) -> T {
+        self.value
+    }
+
+    /// Set the value of the parameter itself, until the next update.
+    pub(crate) fn set_value(&mut self, value: T) {
+        self.value = value;
+    }
+
+    /// The samples of the channel modulating the parameter, if any.
+    pub fn channel(&self) -> Option<&[f32]> {
+        let input = self.chop.input()?;
+        let channel = if self.channel.is_empty() {
+            (input.num_channels() > 0).then(|| input.channel(0))
+        } else {
+            input.channel_by_name(&self.channel)
+        };
+        channel.filter(|channel| !channel.is_empty())
+    }
+
+    /// Whether a CHOP channel is modulating the parameter.
+    pub fn is_modulated(&self) -> bool {
+        self.channel().is_some()
+    }
+
+    /// The value at a sample. A channel with fewer samples holds its last
+    /// sample.
+    pub fn at(&self, sample: usize) -> T {
+        self.value_at(self.channel(), sample)
+    }
+
+    /// The values of the first `num_samples` samples.
+    pub fn samples(&self, num_samples: usize) -> impl Iterator<Item = T> + '_ {
+        let channel = self.channel();
+        (0..num_samples).map(move |sample| self.value_at(channel, sample))
+    }
+
+    fn value_at(&self, channel: Option<&[f32]>, sample: usize) -> T {
+        match channel {
+            Some(channel) => T::from_sample(channel[sample.min(channel.len() - 1)]),
+            None => self.value,
+        }
+    }
+}
+
+impl<T: ModulatedValue> Param for ModulatedParam<T> {
+    fn register(&self, options: ParamOptions, parameter_manager: &mut ParameterManager) {
+        let chop = ParamOptions {
+            name: format!("{}chop", options.name),
+            label: format!("{} CHOP", options.label),
+            page: options.page.clone(),
+            ..Default::default()
+        };
+        let channel = ParamOptions {
+            name: format!("{}chan", options.name),
+            label: format!("{} Channel", options.label),
+            page: options.page.clone(),
+            ..Default::default()
+        };
+        self.value.register(options, parameter_manager);
+        self.chop.register(chop, parameter_manager);
+        self.channel.register(channel, parameter_manager);
+    }
+
+    fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool {
+        let (chop_name, channel_name) = match self.names {
+            Some((base, chop, channel)) if std::ptr::eq(base, name) => (chop, channel),
+            _ => {
+                let base = name.to_str().unwrap_or_default();
+                let chop = intern(&format!("{base}chop"));
+                let channel = intern(&format!("{base}chan"));
+                self.names = Some((name, chop, channel));
+                (chop, channel)
+            }
+        };
+        let value = self.value.update(name, inputs);
+        let chop = self.chop.update(chop_name, inputs);
+        let channel = self.channel.update(channel_name, inputs);
+        value || chop || channel
+    }
+}
+
 #[derive(Default, Debug, Clone)]
 pub struct SopParam {
     pub(crate) input: Option<*const cxx::OP_SOPInput>,
@@ -619,8 +1335,9 @@ impl Param for SopParam {
         parameter_manager.append_sop(param);
     }
 
-    fn update(&mut self, name: &str, inputs: &ParamInputs) {
-        *self = inputs.get_sop(name);
+    fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool {
+        let param = inputs.get_sop(name);
+        replace(&mut self.input, param.input)
     }
 }
 
@@ -643,8 +1360,9 @@ impl Param for TopParam {
         parameter_manager.append_top(param);
     }
 
-    fn update(&mut self, name: &str, inputs: &ParamInputs) {
-        *self = inputs.get_top(name);
+    fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool {
+        let param = inputs.get_top(name);
+        replace(&mut self.input, param.input)
     }
 }
 
@@ -667,8 +1385,9 @@ impl Param for DatParam {
         parameter_manager.append_dat(param);
     }
 
-    fn update(&mut self, name: &str, inputs: &ParamInputs) {
-        *self = inputs.get_dat(name);
+    fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool {
+        let param = inputs.get_dat(name);
+        replace(&mut self.input, param.input)
     }
 }
 
@@ -680,6 +1399,43 @@ impl DatParam {
     }
 }
 
+/// An object COMP parameter, e.g. a Camera or Geometry COMP.
+#[derive(Default, Debug, Clone)]
+pub struct ObjectParam {
+    pub(crate) name: &'static CStr,
+    pub(crate) input: Option<*const cxx::OP_ObjectInput>,
+}
+
+impl Param for ObjectParam {
+    fn register(&self, options: ParamOptions, parameter_manager: &mut ParameterManager) {
+        let param: StringParameter = options.into();
+        parameter_manager.append_object(param);
+    }
+
+    fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool {
+        let param = inputs.get_object(name);
+        self.name = param.name;
+        replace(&mut self.input, param.input)
+    }
+}
+
+impl ObjectParam {
+    /// Get the object input for this parameter, if it exists.
+    pub fn input(&self) -> Option<&ObjectInput> {
+        self.input
+            .map(|input| unsafe { ObjectInput::ref_cast(&*input) })
+    }
+
+    /// Get the transform from the space of this object into the space of the
+    /// object referenced by `to`, if both are set.
+    pub fn relative_transform(&self, to: &ObjectParam, inputs: &ParamInputs) -> Option<Matrix4> {
+        if self.input.is_none() || to.input.is_none() {
+            return None;
+        }
+        inputs.relative_transform(self.name, to.name)
+    }
+}
+
 #[cfg(feature = "python")]
 impl Param for *mut pyo3::ffi::PyObject {
     fn register(&self, options: ParamOptions, parameter_manager: &mut ParameterManager) {
@@ -687,18 +1443,58 @@ impl Param for *mut pyo3::ffi::PyObject {
         parameter_manager.append_python(param);
     }
 
-    fn update(&mut self, name: &str, inputs: &ParamInputs) {
+    fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool {
         // Ensure that the old object is decref'd
         unsafe {
             pyo3::ffi::Py_XDECREF(*self);
         }
-        *self = inputs.get_python(name);
+        let object = inputs.get_python(name);
+        replace(self, object)
     }
 }
 
-pub trait MenuParam {
+/// An enum shown as a menu, implemented by `#[derive(Param)]`. Entries are in
+/// the order of the variants, whatever their discriminants.
+pub trait MenuParam: Sized {
     fn names() -> Vec<String>;
     fn labels() -> Vec<String>;
+    /// The variant at a position in the menu.
+    fn from_menu_index(index: i32) -> Option<Self>;
+    /// The position of the variant in the menu.
+    fn menu_index(&self) -> i32;
+}
+
+/// Store a menu in presets by the name of its entry. Called by
+/// `#[derive(Param)]`, which can't tell whether presets are enabled.
+#[cfg(feature = "presets")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! menu_param_serde {
+    ($t:ty) => {
+        impl $crate::serde::Serialize for $t {
+            fn serialize<S: $crate::serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> Result<S::Ok, S::Error> {
+                $crate::preset::serialize_menu(self, serializer)
+            }
+        }
+
+        impl<'de> $crate::serde::Deserialize<'de> for $t {
+            fn deserialize<D: $crate::serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<Self, D::Error> {
+                $crate::preset::deserialize_menu(deserializer)
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "presets"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! menu_param_serde {
+    ($t:ty) => {};
 }
 
 impl Param for Color {
@@ -707,13 +1503,20 @@ impl Param for Color {
         parameter_manager.append_rgba(param);
     }
 
-    fn update(&mut self, name: &str, inputs: &ParamInputs) {
+    fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool {
         let [r, g, b, a] = inputs.get_double_arr::<4>(name);
+        let changed = [self.r, self.g, self.b, self.a] != [r as f32, g as f32, b as f32, a as f32];
         *self = (r, g, b, a).into();
+        changed
     }
 }
 
+/// A menu whose entries are filled when TouchDesigner opens it, by the
+/// `menu_provider` of the field or by `build_dynamic_menu` of the operator.
+/// `None` while nothing is selected.
 #[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "presets", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "presets", serde(transparent))]
 pub struct DynamicMenuParam(pub Option<String>);
 
 impl Param for DynamicMenuParam {
@@ -722,10 +1525,174 @@ impl Param for DynamicMenuParam {
         parameter_manager.append_dynamic_menu(param);
     }
 
-    fn update(&mut self, name: &str, inputs: &ParamInputs) {
+    fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool {
         let name = inputs.get_string(name);
-        if !name.is_empty() {
-            self.0 = Some(name.to_string());
+        replace(&mut self.0, (!name.is_empty()).then(|| name.to_string()))
+    }
+}
+
+/// A string parameter with a menu of suggestions. Any other string can be
+/// typed in as well, so `value` need not be one of them.
+///
+/// The suggestions are given by `#[param(options = ["a", "b"])]`, or else
+/// computed before the parameters are registered and passed to [`Self::new`].
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct StringMenuParam {
+    pub value: String,
+    pub options: Vec<String>,
+}
+
+impl StringMenuParam {
+    pub fn new(options: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            value: String::new(),
+            options: options.into_iter().map(Into::into).collect(),
         }
     }
+
+    /// Set the initial value.
+    pub fn with_value(mut self, value: impl Into<String>) -> Self {
+        self.value = value.into();
+        self
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.value
+    }
+}
+
+impl AsRef<str> for StringMenuParam {
+    fn as_ref(&self) -> &str {
+        &self.value
+    }
+}
+
+impl Param for StringMenuParam {
+    fn register(&self, mut options: ParamOptions, parameter_manager: &mut ParameterManager) {
+        let names = if options.options.is_empty() {
+            self.options.clone()
+        } else {
+            std::mem::take(&mut options.options)
+        };
+        let mut param: StringParameter = options.into();
+        param.default_value = self.value.clone();
+        parameter_manager.append_string_menu(param, &names, &names);
+    }
+
+    fn update(&mut self, name: &'static CStr, inputs: &ParamInputs) -> bool {
+        replace(&mut self.value, inputs.get_string(name).to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_params_changed() {
+        let mut changed = ParamsChanged::default();
+        assert!(changed.is_empty());
+
+        changed.insert(c"Scale");
+        changed.extend([intern("Offset"), intern("Scale")]);
+        assert_eq!(changed.len(), 2);
+        assert_eq!(changed.iter().collect::<Vec<_>>(), ["Scale", "Offset"]);
+        assert!(changed.contains("Offset"));
+        assert!(changed.contains(&String::from("Scale")));
+        assert!(!changed.contains("Rate"));
+        assert!(changed.contains_any(&["Rate", "Offset"]));
+    }
+
+    #[test]
+    fn test_param_name() {
+        assert_eq!("Scale".with_c_str(CStr::to_owned), c"Scale");
+        let long = "P".repeat(STACK_NAME_LEN);
+        assert_eq!(
+            long.with_c_str(|name| name.to_bytes().len()),
+            STACK_NAME_LEN
+        );
+        assert!(INTERNED.read().unwrap().get("Unregistered").is_none());
+        assert!(!ParamsChanged::default().contains("Unregistered"));
+        assert!(INTERNED.read().unwrap().get("Unregistered").is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "nul bytes")]
+    fn test_param_name_nul() {
+        "Sca\0le".with_c_str(|_| ());
+    }
+
+    #[test]
+    fn test_param_scope() {
+        let root = ParamScope::default();
+        assert_eq!(root.name("Host"), "Host");
+        assert!(std::ptr::eq(root.c_name(c"Host"), c"Host"));
+
+        let net = root.nested("Net", Some("Network"));
+        assert_eq!(net.name("Host"), "Nethost");
+        assert_eq!(net.c_name(c"Host"), c"Nethost");
+        assert_eq!(net.label("Host"), "Net Host");
+        assert_eq!(net.page("Config"), "Network");
+
+        let proxy = net.nested("Proxy", Some("Proxy"));
+        assert_eq!(proxy.name("Port"), "Netproxyport");
+        assert_eq!(proxy.label("Port"), "Net Proxy Port");
+        assert_eq!(proxy.page("Config"), "Network");
+        assert_eq!(root.nested("", None).page("Config"), "Config");
+    }
+
+    #[test]
+    fn test_scoped_names() {
+        static NAMES: ScopedNames = ScopedNames::new();
+        let root = ParamScope::default();
+        let net = root.nested("Net", None);
+        let lookup = |scope: &ParamScope| {
+            NAMES.get(scope, || {
+                (
+                    vec![scope.c_name(c"Host")],
+                    vec![scope.nested("Proxy", None)],
+                )
+            })
+        };
+
+        let first = lookup(&net);
+        assert_eq!(first.name(0), c"Nethost");
+        assert_eq!(first.nested(0).name("Port"), "Netproxyport");
+        assert_eq!(lookup(&root).name(0), c"Host");
+        assert!(std::ptr::eq(lookup(&net), first));
+        assert!(std::ptr::eq(
+            NAMES.get(&net, || unreachable!()).name(0),
+            first.name(0)
+        ));
+    }
+
+    #[test]
+    fn test_pulse_take() {
+        let mut pulse = Pulse::default();
+        assert!(!pulse.take());
+        pulse.press();
+        pulse.press();
+        assert!(pulse.is_pressed());
+        assert!(pulse.take());
+        assert!(!pulse.take());
+    }
+
+    #[test]
+    fn test_momentary_edges() {
+        let mut momentary = Momentary::default();
+        assert!(!momentary.set_held(false));
+        assert!(momentary.set_held(true));
+        assert!(!momentary.set_held(true));
+        assert!(momentary.is_held());
+        assert!(momentary.take());
+        assert!(!momentary.take());
+
+        assert!(momentary.set_held(false));
+        assert!(!momentary.take());
+        momentary.set_held(true);
+        momentary.set_held(false);
+        momentary.set_held(true);
+        assert!(momentary.take());
+        assert!(!momentary.take());
+    }
 }
@@ -0,0 +1,693 @@
+//! Named snapshots of parameter values that can be stored on disk.
+
+use crate::param::{
+    intern, ChopParam, DatParam, FolderParam, MenuParam, ModulatedParam, ModulatedValue, Momentary,
+    ObjectParam, OperatorParams, Param, ParamOptions, ParamScope, ParameterManager, ParamsChanged,
+    Pulse, SopParam, StringMenuParam, TopParam,
+};
+use crate::sop::Color;
+use crate::status::{Severity, StatusMessage};
+use crate::{Context, ParamInputs};
+use anyhow::Context as _;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::ffi::CStr;
+use std::path::{Path, PathBuf};
+
+/// Parameter values keyed by the name of the TouchDesigner parameter.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Preset {
+    values: BTreeMap<String, serde_json::Value>,
+}
+
+impl Preset {
+    /// Read a preset, as TOML if the file has a `.toml` extension and as JSON
+    /// otherwise.
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Could not read preset {}", path.display()))?;
+        let preset = if is_toml(path) {
+            toml::from_str(&text)?
+        } else {
+            serde_json::from_str(&text)?
+        };
+        Ok(preset)
+    }
+
+    /// Write a preset, as TOML if the file has a `.toml` extension and as JSON
+    /// otherwise.
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let text = if is_toml(path) {
+            toml::to_string_pretty(self)?
+        } else {
+            serde_json::to_string_pretty(self)?
+        };
+        std::fs::write(path, text)
+            .with_context(|| format!("Could not write preset {}", path.display()))
+    }
+
+    /// Store the value of a parameter. Values that cannot be serialized are
+    /// left out.
+    pub fn insert(&mut self, name: impl Into<String>, value: &(impl Serialize + ?Sized)) {
+        if let Ok(value) = serde_json::to_value(value) {
+            self.values.insert(name.into(), value);
+        }
+    }
+
+    /// Store the value of a parameter, unless it is of a kind that isn't
+    /// stored in presets.
+    pub fn insert_param(&mut self, name: impl Into<String>, param: &(impl PresetValue + ?Sized)) {
+        if let Some(value) = param.save_value() {
+            self.values.insert(name.into(), value);
+        }
+    }
+
+    /// The stored value of a parameter.
+    pub fn get(&self, name: &str) -> Option<&serde_json::Value> {
+        self.values.get(name)
+    }
+
+    /// The names of the stored parameters.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.values.keys().map(String::as_str)
+    }
+
+    /// The stored parameters and their values.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &serde_json::Value)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The names of the parameters whose value differs between the presets,
+    /// including those only stored in one of them.
+    pub fn diff(&self, other: &Preset) -> Vec<String> {
+        let mut names = self
+            .values
+            .iter()
+            .filter(|(name, value)| other.values.get(*name) != Some(value))
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        names.extend(
+            other
+                .values
+                .keys()
+                .filter(|name| !self.values.contains_key(*name))
+                .cloned(),
+        );
+        names.sort();
+        names
+    }
+}
+
+#[cfg(feature = "python")]
+impl Preset {
+    /// Set the TouchDesigner parameters of `op` to the stored values, returning
+    /// the names of the parameters that do not exist. Values with several
+    /// components are set on the parameters of the tuple of that name, in order
+    /// for arrays and by suffix, e.g. `Colorr`, for maps.
+    pub fn apply(
+        &self,
+        py: pyo3::Python,
+        op: &pyo3::Bound<pyo3::PyAny>,
+    ) -> pyo3::PyResult<Vec<String>> {
+        use pyo3::prelude::*;
+        use serde_json::Value;
+
+        let pars = op.getattr("par")?;
+        let par_tuples = op.getattr("parTuple")?;
+        let mut unknown = Vec::new();
+        for (name, value) in self.iter() {
+            match value {
+                Value::Array(values) => match find_par(&par_tuples, name) {
+                    Some(tuple) => {
+                        for (index, value) in values.iter().enumerate() {
+                            tuple
+                                .get_item(index)?
+                                .setattr("val", json_to_py(py, value)?)?;
+                        }
+                    }
+                    None => unknown.push(name.to_string()),
+                },
+                Value::Object(values) => {
+                    for (suffix, value) in values {
+                        let name = format!("{name}{suffix}");
+                        match find_par(&pars, &name) {
+                            Some(par) => par.setattr("val", json_to_py(py, value)?)?,
+                            None => unknown.push(name),
+                        }
+                    }
+                }
+                _ => match find_par(&pars, name) {
+                    Some(par) => par.setattr("val", json_to_py(py, value)?)?,
+                    None => unknown.push(name.to_string()),
+                },
+            }
+        }
+        Ok(unknown)
+    }
+}
+
+/// The parameter or parameter tuple of that name, if it exists.
+#[cfg(feature = "python")]
+fn find_par<'py>(
+    pars: &pyo3::Bound<'py, pyo3::PyAny>,
+    name: &str,
+) -> Option<pyo3::Bound<'py, pyo3::PyAny>> {
+    use pyo3::prelude::*;
+
+    pars.getattr(name).ok().filter(|par| !par.is_none())
+}
+
+#[cfg(feature = "python")]
+fn json_to_py<'py>(
+    py: pyo3::Python<'py>,
+    value: &serde_json::Value,
+) -> pyo3::PyResult<pyo3::Bound<'py, pyo3::PyAny>> {
+    use pyo3::IntoPyObjectExt;
+    use serde_json::Value;
+
+    match value {
+        Value::Null => Ok(py.None().into_bound(py)),
+        Value::Bool(b) => b.into_bound_py_any(py),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => i.into_bound_py_any(py),
+            None => n.as_f64().unwrap_or_default().into_bound_py_any(py),
+        },
+        Value::String(s) => s.into_bound_py_any(py),
+        Value::Array(values) => {
+            let values = values
+                .iter()
+                .map(|value| json_to_py(py, value))
+                .collect::<pyo3::PyResult<Vec<_>>>()?;
+            pyo3::types::PyTuple::new(py, values)?.into_bound_py_any(py)
+        }
+        Value::Object(_) => Err(pyo3::exceptions::PyValueError::new_err(
+            "Parameters can not be set to objects",
+        )),
+    }
+}
+
+fn is_toml(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "toml")
+}
+
+/// A parameter that can be stored in presets. Implemented for every type with
+/// serde support, and for parameters of which only part is stored.
+pub trait PresetValue {
+    /// Whether parameters of this kind are stored at all. Pulses and operator
+    /// references are not, since only TouchDesigner can set them.
+    const STORED: bool = true;
+
+    /// The value to store.
+    fn save_value(&self) -> Option<serde_json::Value>;
+
+    /// Set the parameter from its stored value.
+    fn load_value(&mut self, value: &serde_json::Value) -> Result<(), String>;
+}
+
+impl<T: Serialize + DeserializeOwned> PresetValue for T {
+    fn save_value(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn load_value(&mut self, value: &serde_json::Value) -> Result<(), String> {
+        *self = T::deserialize(value).map_err(|err| err.to_string())?;
+        Ok(())
+    }
+}
+
+macro_rules! impl_preset_value_unstored {
+    ($($t:ty),*) => {
+        $(
+            impl PresetValue for $t {
+                const STORED: bool = false;
+
+                fn save_value(&self) -> Option<serde_json::Value> {
+                    None
+                }
+
+                fn load_value(&mut self, _value: &serde_json::Value) -> Result<(), String> {
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+impl_preset_value_unstored!(
+    Pulse,
+    Momentary,
+    ChopParam,
+    SopParam,
+    TopParam,
+    DatParam,
+    ObjectParam
+);
+
+/// Only the value is stored, the CHOP and channel stay as set in TouchDesigner.
+impl<T: ModulatedValue + Serialize + DeserializeOwned> PresetValue for ModulatedParam<T> {
+    fn save_value(&self) -> Option<serde_json::Value> {
+        self.value().save_value()
+    }
+
+    fn load_value(&mut self, value: &serde_json::Value) -> Result<(), String> {
+        let mut stored = self.value();
+        stored.load_value(value)?;
+        self.set_value(stored);
+        Ok(())
+    }
+}
+
+/// Stored as `[r, g, b, a]`.
+impl PresetValue for Color {
+    fn save_value(&self) -> Option<serde_json::Value> {
+        [self.r, self.g, self.b, self.a].save_value()
+    }
+
+    fn load_value(&mut self, value: &serde_json::Value) -> Result<(), String> {
+        let mut rgba = [0.0f32; 4];
+        rgba.load_value(value)?;
+        let [r, g, b, a] = rgba;
+        *self = (r, g, b, a).into();
+        Ok(())
+    }
+}
+
+/// Only the value is stored, the suggestions are those of the field.
+impl PresetValue for StringMenuParam {
+    fn save_value(&self) -> Option<serde_json::Value> {
+        self.value.save_value()
+    }
+
+    fn load_value(&mut self, value: &serde_json::Value) -> Result<(), String> {
+        self.value.load_value(value)
+    }
+}
+
+/// Serialize a menu as the name of its entry, for `#[derive(Param)]`.
+#[doc(hidden)]
+pub fn serialize_menu<T: MenuParam, S: Serializer>(
+    menu: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let names = T::names();
+    names[menu.menu_index() as usize].serialize(serializer)
+}
+
+/// Deserialize a menu from the name of its entry, for `#[derive(Param)]`.
+#[doc(hidden)]
+pub fn deserialize_menu<'de, T: MenuParam, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<T, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    let names = T::names();
+    names
+        .iter()
+        .position(|entry| *entry == name)
+        .and_then(|index| T::from_menu_index(index as i32))
+        .ok_or_else(|| {
+            serde::de::Error::custom(format!(
+                "unknown menu entry `{name}`, expected one of {}",
+                names.join(", ")
+            ))
+        })
+}
+
+/// The outcome of loading a preset into a parameter struct.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PresetReport {
+    /// Parameters set from the preset.
+    pub loaded: Vec<String>,
+    /// Parameters not stored in the preset, which keep their value.
+    pub missing: Vec<String>,
+    /// Parameters stored in the preset that do not exist.
+    pub unknown: Vec<String>,
+    /// Parameters whose stored value could not be read, with the reason.
+    pub invalid: Vec<(String, String)>,
+}
+
+impl PresetReport {
+    /// Load the value of a parameter from the preset.
+    pub fn load<T: PresetValue + ?Sized>(&mut self, preset: &Preset, name: String, param: &mut T) {
+        if !T::STORED {
+            return;
+        }
+        match preset.get(&name) {
+            Some(stored) => match param.load_value(stored) {
+                Ok(()) => self.loaded.push(name),
+                Err(err) => self.invalid.push((name, err)),
+            },
+            None => self.missing.push(name),
+        }
+    }
+
+    /// Whether every parameter was loaded and every stored value was used.
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty() && self.unknown.is_empty() && self.invalid.is_empty()
+    }
+
+    /// A warning for each problem, to be shown on the node.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if !self.missing.is_empty() {
+            warnings.push(format!("Preset is missing {}", self.missing.join(", ")));
+        }
+        if !self.unknown.is_empty() {
+            warnings.push(format!(
+                "Preset has unknown parameters {}",
+                self.unknown.join(", ")
+            ));
+        }
+        for (name, err) in &self.invalid {
+            warnings.push(format!("Preset has an invalid value for {name}: {err}"));
+        }
+        warnings
+    }
+}
+
+/// Parameter structs that can be saved to and loaded from presets. Implemented
+/// by `#[derive(Params)]` for structs marked `#[params(presets)]`, where every
+/// field that isn't marked `#[param(skip_preset)]` must implement
+/// [`PresetValue`].
+pub trait PresetParams: OperatorParams {
+    /// Store the values of the parameters in a new preset.
+    fn save_preset(&self) -> Preset {
+        let mut preset = Preset::default();
+        self.save_scoped(&ParamScope::default(), &mut preset);
+        preset
+    }
+
+    /// Set the parameters stored in a preset.
+    fn load_preset(&mut self, preset: &Preset) -> PresetReport {
+        let mut report = PresetReport::default();
+        self.load_scoped(&ParamScope::default(), preset, &mut report);
+        report.unknown = preset
+            .names()
+            .filter(|name| !report.loaded.iter().any(|loaded| loaded == name))
+            .filter(|name| !report.invalid.iter().any(|(invalid, _)| invalid == name))
+            .map(str::to_string)
+            .collect();
+        report
+    }
+
+    /// Store the values of the parameters registered within `scope`.
+    fn save_scoped(&self, scope: &ParamScope, preset: &mut Preset);
+
+    /// Set the parameters registered within `scope` that are stored in a preset.
+    fn load_scoped(&mut self, scope: &ParamScope, preset: &Preset, report: &mut PresetReport);
+
+    /// The embedded [`PresetControls`], if any.
+    fn preset_controls(&mut self) -> Option<&mut PresetControls> {
+        None
+    }
+
+    /// Update the parameters from operator input, then save or load the
+    /// preset selected by the embedded [`PresetControls`] if one of its pulses
+    /// was pressed. Called by [`Op::update_params`](crate::Op::update_params).
+    ///
+    /// A loaded preset is also set on the TouchDesigner parameters of the node
+    /// the controls are attached to, see [`PresetControls::attach`], as the
+    /// next update would otherwise bring back the values it replaced.
+    fn update_presets(&mut self, inputs: &ParamInputs) -> ParamsChanged {
+        let mut changed = self.update(inputs);
+        let Some(controls) = self.preset_controls() else {
+            return changed;
+        };
+        let save = controls.save.take();
+        let load = controls.load.take();
+        let path = controls.path();
+        let context = controls.context;
+        let mut messages = None;
+        if save {
+            messages = Some(match self.save_preset().save(&path) {
+                Ok(()) => Vec::new(),
+                Err(err) => vec![status_message(Severity::Error, format!("{err:#}"))],
+            });
+        }
+        if load {
+            messages = Some(match Preset::load(&path) {
+                Ok(preset) => {
+                    let before = self.save_preset();
+                    let report = self.load_preset(&preset);
+                    changed.extend(
+                        report
+                            .loaded
+                            .iter()
+                            .filter(|name| before.get(name) != preset.get(name))
+                            .map(|name| intern(name)),
+                    );
+                    let mut messages = report
+                        .warnings()
+                        .into_iter()
+                        .map(|warning| status_message(Severity::Warning, warning))
+                        .collect::<Vec<_>>();
+                    let loaded = Preset {
+                        values: preset
+                            .values
+                            .into_iter()
+                            .filter(|(name, _)| report.loaded.contains(name))
+                            .collect(),
+                    };
+                    match context.map(|context| context.apply_preset(&loaded)) {
+                        Some(Ok(unknown)) if unknown.is_empty() => {}
+                        Some(Ok(unknown)) => messages.push(status_message(
+                            Severity::Warning,
+                            format!("Could not set parameters {}", unknown.join(", ")),
+                        )),
+                        Some(Err(err)) => {
+                            messages.push(status_message(Severity::Error, format!("{err:#}")))
+                        }
+                        None => messages.push(status_message(
+                            Severity::Error,
+                            "Could not set the preset parameters, the preset controls are not \
+                             attached to a node"
+                                .to_string(),
+                        )),
+                    }
+                    messages
+                }
+                Err(err) => vec![status_message(Severity::Error, format!("{err:#}"))],
+            });
+        }
+        if let (Some(controls), Some(messages)) = (self.preset_controls(), messages) {
+            controls.messages = messages;
+        }
+        changed
+    }
+}
+
+fn status_message(severity: Severity, message: String) -> StatusMessage {
+    StatusMessage { severity, message }
+}
+
+/// Parameters to save and load presets from a folder, to be embedded with
+/// `#[param(flatten, page = "Presets")]` in a struct marked
+/// `#[params(presets)]`. They are never stored in presets.
+///
+/// The pulses are handled by [`PresetParams::update_presets`] on the next
+/// cook, which reports problems as warnings and errors of the operator until
+/// the next save or load.
+#[derive(Debug, Clone)]
+pub struct PresetControls {
+    folder: FolderParam,
+    name: String,
+    save: Pulse,
+    load: Pulse,
+    save_name: &'static CStr,
+    load_name: &'static CStr,
+    context: Option<Context>,
+    messages: Vec<StatusMessage>,
+}
+
+impl Default for PresetControls {
+    fn default() -> Self {
+        Self {
+            folder: FolderParam::default(),
+            name: "default".to_string(),
+            save: Pulse::default(),
+            load: Pulse::default(),
+            save_name: c"Savepreset",
+            load_name: c"Loadpreset",
+            context: None,
+            messages: Vec::new(),
+        }
+    }
+}
+
+impl PresetControls {
+    /// The file of the selected preset. Names without an extension are stored
+    /// as JSON.
+    pub fn path(&self) -> PathBuf {
+        let path = self.folder.join(&self.name);
+        if path.extension().is_some() {
+            path
+        } else {
+            path.with_extension("json")
+        }
+    }
+
+    /// The problems of the last save or load.
+    pub fn messages(&self) -> &[StatusMessage] {
+        &self.messages
+    }
+
+    /// Set loaded presets on the parameters of a node. Done by the plugin
+    /// when the operator is created, see [`Op::attach_node`](crate::Op::attach_node).
+    pub fn attach(&mut self, context: Context) {
+        self.context = Some(context);
+    }
+}
+
+impl OperatorParams for PresetControls {
+    fn register_scoped(&mut self, scope: &ParamScope, parameter_manager: &mut ParameterManager) {
+        let options = |name: &str, label: &str| ParamOptions {
+            name: scope.name(name),
+            label: scope.label(label),
+            page: scope.page("Presets"),
+            ..Default::default()
+        };
+        self.folder
+            .register(options("Presetfolder", "Preset Folder"), parameter_manager);
+        self.name
+            .register(options("Preset", "Preset"), parameter_manager);
+        Pulse::default().register(options("Savepreset", "Save Preset"), parameter_manager);
+        Pulse::default().register(options("Loadpreset", "Load Preset"), parameter_manager);
+    }
+
+    fn update_scoped(&mut self, scope: &ParamScope, inputs: &ParamInputs) -> ParamsChanged {
+        let mut changed = ParamsChanged::default();
+        for (name, param) in [
+            (c"Presetfolder", &mut self.folder as &mut dyn Param),
+            (c"Preset", &mut self.name),
+        ] {
+            let name = scope.c_name(name);
+            if param.update(name, inputs) {
+                changed.insert(name);
+            }
+        }
+        self.save_name = scope.c_name(c"Savepreset");
+        self.load_name = scope.c_name(c"Loadpreset");
+        changed
+    }
+
+    fn press_pulse_scoped(&mut self, _scope: &ParamScope, name: &str) -> bool {
+        if name.as_bytes() == self.save_name.to_bytes() {
+            self.save.press();
+        } else if name.as_bytes() == self.load_name.to_bytes() {
+            self.load.press();
+        } else {
+            return false;
+        }
+        true
+    }
+}
+
+impl PresetParams for PresetControls {
+    fn save_scoped(&self, _scope: &ParamScope, _preset: &mut Preset) {}
+
+    fn load_scoped(&mut self, _scope: &ParamScope, _preset: &Preset, _report: &mut PresetReport) {}
+
+    fn preset_controls(&mut self) -> Option<&mut PresetControls> {
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let mut a = Preset::default();
+        a.insert("Scale", &1.0);
+        a.insert("Mode", "Advanced");
+        let mut b = a.clone();
+        assert!(a.diff(&b).is_empty());
+
+        b.insert("Scale", &2.0);
+        b.insert("Offset", &[0.0, 1.0]);
+        assert_eq!(a.diff(&b), ["Offset", "Scale"]);
+    }
+
+    #[test]
+    fn test_preset_values() {
+        let mut preset = Preset::default();
+        preset.insert_param("Pulse", &Pulse::default());
+        preset.insert_param("Chop", &ChopParam::default());
+        preset.insert_param("Scale", &ModulatedParam::new(2.0f32));
+        preset.insert_param("Codec", &StringMenuParam::new(["h264"]).with_value("hevc"));
+        assert_eq!(preset.names().collect::<Vec<_>>(), ["Codec", "Scale"]);
+
+        let mut report = PresetReport::default();
+        let mut chop = ChopParam::default();
+        let mut scale = ModulatedParam::new(1.0f32);
+        let mut codec = StringMenuParam::new(["h264"]);
+        report.load(&preset, "Chop".to_string(), &mut chop);
+        report.load(&preset, "Scale".to_string(), &mut scale);
+        report.load(&preset, "Codec".to_string(), &mut codec);
+        assert_eq!(scale.value(), 2.0);
+        assert_eq!(codec.as_str(), "hevc");
+        assert_eq!(codec.options, ["h264"]);
+        assert_eq!(report.loaded, ["Scale", "Codec"]);
+        assert!(report.is_complete());
+    }
+
+    #[test]
+    fn test_color_values() {
+        let mut preset = Preset::default();
+        preset.insert_param("Tint", &Color::from((1.0f32, 0.5, 0.0, 1.0)));
+        preset.insert_param("Fill", &rgb::RGB8::new(255, 128, 0));
+        assert_eq!(
+            preset.get("Tint").unwrap(),
+            &serde_json::json!([1.0, 0.5, 0.0, 1.0])
+        );
+        assert_eq!(
+            preset.get("Fill").unwrap(),
+            &serde_json::json!({"r": 255, "g": 128, "b": 0})
+        );
+
+        let mut report = PresetReport::default();
+        let mut tint = Color::from((0.0f32, 0.0, 0.0, 0.0));
+        let mut fill = rgb::RGB8::default();
+        report.load(&preset, "Tint".to_string(), &mut tint);
+        report.load(&preset, "Fill".to_string(), &mut fill);
+        assert_eq!([tint.r, tint.g, tint.b, tint.a], [1.0, 0.5, 0.0, 1.0]);
+        assert_eq!(fill, rgb::RGB8::new(255, 128, 0));
+        assert!(report.is_complete());
+    }
+
+    #[test]
+    fn test_report() {
+        let mut preset = Preset::default();
+        preset.insert("Scale", &2.5);
+        preset.insert("Count", "many");
+
+        let mut report = PresetReport::default();
+        let mut scale = 1.0f32;
+        let mut count = 3u32;
+        let mut offset = 0.0f64;
+        report.load(&preset, "Scale".to_string(), &mut scale);
+        report.load(&preset, "Count".to_string(), &mut count);
+        report.load(&preset, "Offset".to_string(), &mut offset);
+        assert_eq!(scale, 2.5);
+        assert_eq!(count, 3);
+        assert_eq!(report.loaded, ["Scale"]);
+        assert_eq!(report.missing, ["Offset"]);
+        assert_eq!(report.invalid[0].0, "Count");
+        assert!(!report.is_complete());
+        assert_eq!(report.warnings()[0], "Preset is missing Offset");
+    }
+}
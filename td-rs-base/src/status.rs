@@ -0,0 +1,269 @@
+//! Info, warning and error messages reported by an operator instance.
+
+use crate::Op;
+use std::any::Any;
+use std::cell::Cell;
+use std::ffi::CString;
+use std::panic::AssertUnwindSafe;
+
+thread_local! {
+    /// The status of the operator instance TouchDesigner is calling into, set
+    /// by its plugin for the duration of the callback.
+    static CURRENT: Cell<*mut OpStatus> = const { Cell::new(std::ptr::null_mut()) };
+}
+
+/// How severe a status message is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Shown in the info popup of the node.
+    Info,
+    /// Shown as a warning on the node.
+    Warning,
+    /// Shown as an error on the node.
+    Error,
+}
+
+/// A single status message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusMessage {
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// The messages an operator has reported since the start of its last cook.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpStatus {
+    messages: Vec<StatusMessage>,
+}
+
+impl OpStatus {
+    /// Add a message, keeping any other messages of the same severity.
+    pub fn push(&mut self, severity: Severity, message: impl Into<String>) {
+        let message = message.into();
+        if !message.is_empty() {
+            self.messages.push(StatusMessage { severity, message });
+        }
+    }
+
+    /// Replace all messages of a severity. An empty message clears them.
+    pub fn set(&mut self, severity: Severity, message: impl Into<String>) {
+        self.messages.retain(|m| m.severity != severity);
+        self.push(severity, message);
+    }
+
+    /// All messages, in the order they were reported.
+    pub fn messages(&self) -> &[StatusMessage] {
+        &self.messages
+    }
+
+    /// The messages of a severity, in the order they were reported.
+    pub fn iter(&self, severity: Severity) -> impl Iterator<Item = &str> {
+        self.messages
+            .iter()
+            .filter(move |m| m.severity == severity)
+            .map(|m| m.message.as_str())
+    }
+
+    /// The messages of a severity joined by newlines, as shown by TouchDesigner.
+    pub fn text(&self, severity: Severity) -> String {
+        self.iter(severity).collect::<Vec<_>>().join("\n")
+    }
+
+    /// The highest severity reported, if any.
+    pub fn severity(&self) -> Option<Severity> {
+        self.messages.iter().map(|m| m.severity).max()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.severity() == Some(Severity::Error)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.messages.clear();
+    }
+}
+
+/// Make `status` the status of the operator instance for the duration of `f`.
+/// The plugin holding the instance calls this around every callback, so
+/// [`Op::add_error`] and friends reach the status of the right node.
+pub fn with_op_status<R>(status: &mut OpStatus, f: impl FnOnce() -> R) -> R {
+    struct Restore(*mut OpStatus);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            CURRENT.set(self.0);
+        }
+    }
+
+    let _restore = Restore(CURRENT.replace(status));
+    f()
+}
+
+/// Run `f` on the status of the operator instance being called into. Outside
+/// of a callback there is none and `f` isn't run.
+pub(crate) fn with_status<R>(f: impl FnOnce(&mut OpStatus) -> R) -> Option<R> {
+    // SAFETY: the pointer is only set while `with_op_status` holds a unique
+    // borrow of the status, and `f` can't reach this function again.
+    unsafe { CURRENT.get().as_mut() }.map(f)
+}
+
+/// The result of a fallible operator callback. Errors are shown on the node.
+pub type CookResult<T = ()> = anyhow::Result<T>;
+
+/// Call into an operator on behalf of TouchDesigner, with `status` as the
+/// status of the instance.
+///
+/// A panic must never unwind into TouchDesigner, so panics are caught here and,
+/// like errors returned by the callback, added to the errors of the node. The
+/// message is returned so the caller can fall back to a safe, empty result.
+pub fn catch_cook<O: Op + ?Sized, T>(
+    op: &mut O,
+    status: &mut OpStatus,
+    f: impl FnOnce(&mut O) -> CookResult<T>,
+) -> Result<T, String> {
+    let res = with_op_status(status, || {
+        std::panic::catch_unwind(AssertUnwindSafe(|| f(op)))
+    });
+    let message = match res {
+        Ok(Ok(value)) => return Ok(value),
+        Ok(Err(err)) => format!("{err:#}"),
+        Err(payload) => panic_message(payload.as_ref()),
+    };
+    status.push(Severity::Error, message.clone());
+    Err(message)
+}
+
+/// A status message as a C string for TouchDesigner. Nul bytes, which a C
+/// string can't hold, are replaced rather than failing the callback.
+pub fn message_c_string(message: &str) -> CString {
+    CString::new(message.replace('\0', "\u{FFFD}")).expect("nul bytes were replaced")
+}
+
+/// Create an operator on behalf of TouchDesigner. A panic in its constructor
+/// is caught and its message returned, so the plugin can create a [`FailedOp`]
+/// in its place.
+pub fn catch_new<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    std::panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| panic_message(payload.as_ref()))
+}
+
+/// Stands in for an operator whose constructor panicked. Every cook fails with
+/// the panic message, so the node shows it as an error and outputs nothing.
+#[derive(Debug, Clone)]
+pub struct FailedOp {
+    message: String,
+}
+
+impl FailedOp {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Fail a cook with the panic message.
+    pub fn fail<T>(&self) -> CookResult<T> {
+        Err(anyhow::anyhow!("{}", self.message))
+    }
+}
+
+impl Op for FailedOp {}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown cause");
+    format!("Operator panicked: {message}")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_set_replaces_severity() {
+        let mut status = OpStatus::default();
+        status.push(Severity::Warning, "a");
+        status.push(Severity::Warning, "b");
+        status.push(Severity::Error, "c");
+        assert_eq!(status.text(Severity::Warning), "a\nb");
+
+        status.set(Severity::Warning, "d");
+        assert_eq!(status.text(Severity::Warning), "d");
+        assert_eq!(status.text(Severity::Error), "c");
+
+        status.set(Severity::Error, "");
+        assert!(!status.has_errors());
+        assert_eq!(status.severity(), Some(Severity::Warning));
+    }
+
+    #[test]
+    fn test_status_is_per_instance() {
+        struct Zst;
+        impl Op for Zst {}
+
+        let (mut a, mut b) = (Zst, Zst);
+        let (mut status_a, mut status_b) = (OpStatus::default(), OpStatus::default());
+        with_op_status(&mut status_a, || a.add_error("a failed"));
+        with_op_status(&mut status_b, || {
+            b.add_warning("b warned");
+            assert!(b.error().is_empty());
+        });
+        assert_eq!(status_a.text(Severity::Error), "a failed");
+        assert!(status_b.iter(Severity::Error).next().is_none());
+
+        with_op_status(&mut status_a, || a.clear_status());
+        assert!(status_a.is_empty());
+        assert_eq!(status_b.text(Severity::Warning), "b warned");
+
+        // Outside of a callback there is no status to report to
+        a.add_error("dropped");
+        assert!(a.status().is_empty());
+    }
+
+    #[test]
+    fn test_catch_cook() {
+        struct Failing(u8);
+        impl Op for Failing {}
+
+        let mut op = Failing(0);
+        let mut status = OpStatus::default();
+        let res: Result<(), _> =
+            catch_cook(&mut op, &mut status, |_| Err(anyhow::anyhow!("bad input")));
+        assert_eq!(res, Err("bad input".to_string()));
+        let res: Result<(), _> = catch_cook(&mut op, &mut status, |_| panic!("oops"));
+        assert_eq!(res, Err("Operator panicked: oops".to_string()));
+        assert_eq!(
+            catch_cook(&mut op, &mut status, |op| Ok(op.error())),
+            Ok("bad input\nOperator panicked: oops".to_string())
+        );
+        assert_eq!(catch_cook(&mut op, &mut status, |op| Ok(op.0)), Ok(0));
+
+        let Err(message) = catch_new(|| -> Failing { panic!("no device") }) else {
+            panic!("constructor didn't panic");
+        };
+        let mut op = FailedOp::new(message);
+        let mut status = OpStatus::default();
+        let res: Result<(), _> = catch_cook(&mut op, &mut status, |op| op.fail());
+        assert_eq!(res, Err("Operator panicked: no device".to_string()));
+        assert_eq!(status.text(Severity::Error), "Operator panicked: no device");
+    }
+
+    #[test]
+    fn test_message_c_string() {
+        assert_eq!(message_c_string("bad input").as_bytes(), b"bad input");
+        assert_eq!(
+            message_c_string("bad\0input").to_str(),
+            Ok("bad\u{FFFD}input")
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! Timing of the current cook.
+
+use crate::cxx;
+
+/// Timing of the current cook, as seen by the operator.
+///
+/// `frame` and `rate` follow the timeline of the component the operator lives
+/// in, which can differ from the root timeline when component time is used.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TimeInfo {
+    /// Frames elapsed since TouchDesigner started. Never loops or pauses.
+    pub abs_frame: i64,
+    /// The frame on the timeline of the operator.
+    pub frame: f64,
+    /// Frames per second of the timeline of the operator.
+    pub rate: f64,
+    /// The frame on the root timeline.
+    pub root_frame: f64,
+    /// Frames per second of the root timeline.
+    pub root_rate: f64,
+    /// Timeline frames since the last cook. Greater than one when frames were
+    /// dropped, zero on the first cook.
+    pub delta_frames: f64,
+    /// Milliseconds since the last cook.
+    pub delta_ms: f64,
+}
+
+impl TimeInfo {
+    /// Seconds since TouchDesigner started.
+    pub fn elapsed_seconds(&self) -> f64 {
+        if self.root_rate > 0.0 {
+            self.abs_frame as f64 / self.root_rate
+        } else {
+            0.0
+        }
+    }
+
+    /// Seconds on the timeline of the operator, where frame 1 is zero.
+    pub fn timeline_seconds(&self) -> f64 {
+        if self.rate > 0.0 {
+            (self.frame - 1.0) / self.rate
+        } else {
+            0.0
+        }
+    }
+
+    /// Seconds since the last cook.
+    pub fn delta_seconds(&self) -> f64 {
+        self.delta_ms / 1000.0
+    }
+
+    /// Whether this is the first cook of the operator.
+    pub fn is_first_cook(&self) -> bool {
+        self.delta_frames == 0.0
+    }
+
+    /// The number of frames skipped since the last cook.
+    pub fn dropped_frames(&self) -> u64 {
+        (self.delta_frames.round() - 1.0).max(0.0) as u64
+    }
+
+    /// Whether any frames were skipped since the last cook.
+    pub fn frames_dropped(&self) -> bool {
+        self.dropped_frames() > 0
+    }
+}
+
+impl From<&cxx::OP_TimeInfo> for TimeInfo {
+    fn from(info: &cxx::OP_TimeInfo) -> Self {
+        Self {
+            abs_frame: info.absFrame,
+            frame: info.frame,
+            rate: info.rate,
+            root_frame: info.rootFrame,
+            root_rate: info.rootRate,
+            delta_frames: info.deltaFrames,
+            delta_ms: info.deltaMS,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_dropped_frames() {
+        let mut time = TimeInfo {
+            abs_frame: 120,
+            frame: 61.0,
+            rate: 60.0,
+            root_frame: 120.0,
+            root_rate: 60.0,
+            delta_frames: 1.0,
+            delta_ms: 1000.0 / 60.0,
+        };
+        assert_eq!(time.elapsed_seconds(), 2.0);
+        assert_eq!(time.timeline_seconds(), 1.0);
+        assert!(!time.frames_dropped());
+
+        time.delta_frames = 3.0;
+        assert_eq!(time.dropped_frames(), 2);
+        assert!(time.frames_dropped());
+
+        time.delta_frames = 0.0;
+        assert!(time.is_first_cook());
+        assert_eq!(time.dropped_frames(), 0);
+    }
+}
@@ -0,0 +1,585 @@
+//! Block-based audio processing inside a CHOP.
+
+use crate::timeslice::{Continuity, SampleRange, Timeslice, TimesliceChop};
+use crate::{ChopInput, ChopOutput};
+use std::slice::{ChunksExact, ChunksExactMut};
+use td_rs_base::{
+    anyhow, CookResult, DynamicMenuInfo, InfoChop, InfoDat, MenuInputs, NodeInfo, Op, OpInfo,
+    OpNew, OpStatus, OperatorInputs, OperatorParams, ParamInputs, ParamsChanged,
+};
+
+/// How the samples of the channels of a block are arranged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleLayout {
+    /// All samples of the first channel, then all of the second, and so on.
+    Planar,
+    /// The samples of every channel for the first frame, then the second, and
+    /// so on.
+    Interleaved,
+}
+
+/// What an [`AudioProcessor`] is prepared for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioConfig {
+    pub sample_rate: f64,
+    /// The largest number of frames passed to [`AudioProcessor::process`].
+    pub max_block_size: usize,
+    pub input_channels: usize,
+    pub output_channels: usize,
+}
+
+/// A block of samples to process, in the layout asked for by the processor.
+#[derive(Debug)]
+pub struct AudioBlock<'a> {
+    input: &'a [f32],
+    output: &'a mut [f32],
+    input_channels: usize,
+    output_channels: usize,
+    frames: usize,
+    layout: SampleLayout,
+}
+
+impl<'a> AudioBlock<'a> {
+    /// Create a block over buffers holding `frames` frames of every channel.
+    pub fn new(
+        input: &'a [f32],
+        output: &'a mut [f32],
+        input_channels: usize,
+        output_channels: usize,
+        layout: SampleLayout,
+    ) -> Self {
+        let frames = output
+            .len()
+            .checked_div(output_channels)
+            .or_else(|| input.len().checked_div(input_channels))
+            .unwrap_or(0);
+        assert_eq!(input.len(), frames * input_channels, "input length");
+        assert_eq!(output.len(), frames * output_channels, "output length");
+        Self {
+            input,
+            output,
+            input_channels,
+            output_channels,
+            frames,
+            layout,
+        }
+    }
+
+    pub fn input_channels(&self) -> usize {
+        self.input_channels
+    }
+
+    pub fn output_channels(&self) -> usize {
+        self.output_channels
+    }
+
+    /// The number of samples of each channel.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    pub fn layout(&self) -> SampleLayout {
+        self.layout
+    }
+
+    /// All input samples, in the layout of the block.
+    pub fn input(&self) -> &[f32] {
+        self.input
+    }
+
+    /// All output samples, in the layout of the block.
+    pub fn output(&mut self) -> &mut [f32] {
+        self.output
+    }
+
+    /// Both buffers at once, e.g. for processors taking an input and an output
+    /// slice.
+    pub fn split(&mut self) -> (&[f32], &mut [f32]) {
+        (self.input, self.output)
+    }
+
+    /// The input channels of a planar block.
+    pub fn input_planes(&self) -> ChunksExact<'_, f32> {
+        assert_eq!(self.layout, SampleLayout::Planar, "block is not planar");
+        self.input.chunks_exact(self.frames.max(1))
+    }
+
+    /// The output channels of a planar block.
+    pub fn output_planes(&mut self) -> ChunksExactMut<'_, f32> {
+        assert_eq!(self.layout, SampleLayout::Planar, "block is not planar");
+        self.output.chunks_exact_mut(self.frames.max(1))
+    }
+
+    /// The input frames of an interleaved block.
+    pub fn input_frames(&self) -> ChunksExact<'_, f32> {
+        assert_eq!(
+            self.layout,
+            SampleLayout::Interleaved,
+            "block is not interleaved"
+        );
+        self.input.chunks_exact(self.input_channels.max(1))
+    }
+
+    /// The output frames of an interleaved block.
+    pub fn output_frames(&mut self) -> ChunksExactMut<'_, f32> {
+        assert_eq!(
+            self.layout,
+            SampleLayout::Interleaved,
+            "block is not interleaved"
+        );
+        self.output.chunks_exact_mut(self.output_channels.max(1))
+    }
+}
+
+/// Processes audio a block at a time, for use with [`AudioChop`].
+///
+/// The processor is an operator of its own, so it can have parameters, an
+/// Info CHOP and so on. `AudioChop` takes care of the timeslice, of matching
+/// the rate of the input and of splitting each cook into blocks.
+pub trait AudioProcessor: Op {
+    /// The layout of the blocks passed to [`AudioProcessor::process`].
+    const LAYOUT: SampleLayout = SampleLayout::Planar;
+    /// The largest number of frames in a block.
+    const MAX_BLOCK_SIZE: usize = 1024;
+
+    /// The number of output channels for a number of input channels, or `None`
+    /// if the processor can't handle that many. Defaults to one output per
+    /// input.
+    fn output_channels(&self, input_channels: usize) -> Option<usize> {
+        Some(input_channels)
+    }
+
+    /// The sample rate to run at. Defaults to the rate of the input, or the
+    /// timeline rate without an input.
+    fn sample_rate(&self) -> Option<f64> {
+        None
+    }
+
+    /// Called before the first block and whenever the configuration changes.
+    fn prepare(&mut self, config: &AudioConfig);
+
+    fn process(&mut self, block: &mut AudioBlock) -> CookResult;
+
+    /// Clear internal state such as delay lines, e.g. when the timeline jumps.
+    fn reset(&mut self) {}
+}
+
+/// A CHOP running an [`AudioProcessor`] over its first input every cook.
+///
+/// ```ignore
+/// chop_plugin!(AudioChop<Reverb>);
+/// ```
+pub struct AudioChop<P> {
+    processor: P,
+    timeslice: Timeslice<()>,
+    config: Option<AudioConfig>,
+    /// The input channels at the rate and range of the output.
+    inputs: Vec<Vec<f32>>,
+    outputs: Vec<Vec<f32>>,
+    /// The samples of a block, in the layout of the processor.
+    input: Vec<f32>,
+    output: Vec<f32>,
+}
+
+impl<P: AudioProcessor> AudioChop<P> {
+    /// Wrap a processor. Plugins are created through [`OpNew`] instead.
+    pub fn from_processor(processor: P) -> Self {
+        Self {
+            processor,
+            timeslice: Timeslice::new(),
+            config: None,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            input: Vec::new(),
+            output: Vec::new(),
+        }
+    }
+
+    pub fn processor(&self) -> &P {
+        &self.processor
+    }
+
+    pub fn processor_mut(&mut self) -> &mut P {
+        &mut self.processor
+    }
+
+    fn input_channels(input: &OperatorInputs<ChopInput>) -> usize {
+        input.input(0).map_or(0, ChopInput::num_channels)
+    }
+
+    /// Prepare the processor if the configuration changed, or reset it if the
+    /// samples don't follow on from the previous cook.
+    fn configure(&mut self, range: &SampleRange, input_channels: usize) -> CookResult<AudioConfig> {
+        let Some(output_channels) = self.processor.output_channels(input_channels) else {
+            anyhow::bail!("{input_channels} input channels are not supported");
+        };
+        let config = AudioConfig {
+            sample_rate: range.sample_rate,
+            max_block_size: P::MAX_BLOCK_SIZE.max(1),
+            input_channels,
+            output_channels,
+        };
+        if self.config != Some(config) {
+            self.processor.prepare(&config);
+            self.config = Some(config);
+        } else if matches!(range.continuity, Continuity::Start | Continuity::Rewind) {
+            self.processor.reset();
+        }
+        Ok(config)
+    }
+
+    /// Run the processor over the first `len` samples of `inputs` into
+    /// `outputs`, a block at a time.
+    fn process_blocks(&mut self, len: usize, config: &AudioConfig) -> CookResult {
+        let AudioConfig {
+            input_channels,
+            output_channels,
+            ..
+        } = *config;
+        self.outputs.resize_with(output_channels, Vec::new);
+        for channel in &mut self.outputs {
+            channel.resize(len, 0.0);
+        }
+
+        let mut start = 0;
+        while start < len {
+            let frames = config.max_block_size.min(len - start);
+            self.input.resize(frames * input_channels, 0.0);
+            self.output.clear();
+            self.output.resize(frames * output_channels, 0.0);
+            for (c, channel) in self.inputs.iter().take(input_channels).enumerate() {
+                for (f, sample) in channel[start..start + frames].iter().enumerate() {
+                    self.input[index(P::LAYOUT, c, f, input_channels, frames)] = *sample;
+                }
+            }
+
+            let mut block = AudioBlock::new(
+                &self.input,
+                &mut self.output,
+                input_channels,
+                output_channels,
+                P::LAYOUT,
+            );
+            self.processor.process(&mut block)?;
+
+            for (c, channel) in self.outputs.iter_mut().enumerate() {
+                for (f, sample) in channel[start..start + frames].iter_mut().enumerate() {
+                    *sample = self.output[index(P::LAYOUT, c, f, output_channels, frames)];
+                }
+            }
+            start += frames;
+        }
+        Ok(())
+    }
+}
+
+impl<P: AudioProcessor + OpNew> OpNew for AudioChop<P> {
+    fn new(info: NodeInfo) -> Self {
+        Self::from_processor(P::new(info))
+    }
+}
+
+impl<P: OpInfo> OpInfo for AudioChop<P> {
+    const OPERATOR_TYPE: &'static str = P::OPERATOR_TYPE;
+    const OPERATOR_LABEL: &'static str = P::OPERATOR_LABEL;
+    const OPERATOR_ICON: &'static str = P::OPERATOR_ICON;
+    const MIN_INPUTS: usize = P::MIN_INPUTS;
+    const MAX_INPUTS: usize = P::MAX_INPUTS;
+    const AUTHOR_NAME: &'static str = P::AUTHOR_NAME;
+    const AUTHOR_EMAIL: &'static str = P::AUTHOR_EMAIL;
+    const MAJOR_VERSION: i32 = P::MAJOR_VERSION;
+    const MINOR_VERSION: i32 = P::MINOR_VERSION;
+    const COOK_ON_START: bool = P::COOK_ON_START;
+    const PYTHON_CALLBACKS_DAT: &'static str = P::PYTHON_CALLBACKS_DAT;
+}
+
+// Everything is forwarded so a processor behaves the same whether or not it
+// is wrapped.
+impl<P: AudioProcessor> Op for AudioChop<P> {
+    fn params_mut(&mut self) -> Option<Box<&mut dyn OperatorParams>> {
+        self.processor.params_mut()
+    }
+
+    fn info_dat(&self) -> Option<&dyn InfoDat> {
+        self.processor.info_dat()
+    }
+
+    fn info_chop(&self) -> Option<&dyn InfoChop> {
+        self.processor.info_chop()
+    }
+
+    fn set_info(&mut self, info: &str) {
+        self.processor.set_info(info)
+    }
+
+    fn add_info(&mut self, info: &str) {
+        self.processor.add_info(info)
+    }
+
+    fn info(&self) -> String {
+        self.processor.info()
+    }
+
+    fn set_error(&mut self, error: &str) {
+        self.processor.set_error(error)
+    }
+
+    fn add_error(&mut self, error: &str) {
+        self.processor.add_error(error)
+    }
+
+    fn error(&self) -> String {
+        self.processor.error()
+    }
+
+    fn set_warning(&mut self, warning: &str) {
+        self.processor.set_warning(warning)
+    }
+
+    fn add_warning(&mut self, warning: &str) {
+        self.processor.add_warning(warning)
+    }
+
+    fn warning(&self) -> String {
+        self.processor.warning()
+    }
+
+    fn status(&self) -> OpStatus {
+        self.processor.status()
+    }
+
+    fn clear_status(&mut self) {
+        self.processor.clear_status()
+    }
+
+    fn pulse_pressed(&mut self, name: &str) {
+        self.processor.pulse_pressed(name)
+    }
+
+    fn press_pulse(&mut self, name: &str) {
+        self.processor.press_pulse(name)
+    }
+
+    fn on_params_changed(&mut self, changed: &ParamsChanged) {
+        self.processor.on_params_changed(changed)
+    }
+
+    fn update_params(&mut self, inputs: &ParamInputs) {
+        self.processor.update_params(inputs)
+    }
+
+    fn build_params_menu(&mut self, inputs: &MenuInputs, menu: &mut DynamicMenuInfo) -> bool {
+        self.processor.build_params_menu(inputs, menu)
+    }
+}
+
+impl<P: AudioProcessor> TimesliceChop for AudioChop<P> {
+    type State = ();
+
+    fn timeslice(&mut self) -> &mut Timeslice<()> {
+        &mut self.timeslice
+    }
+
+    fn num_channels(&self, input: &OperatorInputs<ChopInput>) -> usize {
+        self.processor
+            .output_channels(Self::input_channels(input))
+            .unwrap_or(0)
+    }
+
+    fn sample_rate(&self, input: &OperatorInputs<ChopInput>) -> f32 {
+        match (self.processor.sample_rate(), input.input(0)) {
+            (Some(rate), _) => rate as f32,
+            (None, Some(chop)) => chop.sample_rate() as f32,
+            (None, None) => input.time_info().rate as f32,
+        }
+    }
+
+    fn channel_name(&self, index: usize, input: &OperatorInputs<ChopInput>) -> String {
+        let input_channels = Self::input_channels(input);
+        match input.input(0) {
+            Some(chop) if self.num_channels(input) == input_channels => {
+                chop.channel_name(index).to_string()
+            }
+            _ => format!("chan{}", index + 1),
+        }
+    }
+
+    fn cook_timeslice(
+        &mut self,
+        range: &SampleRange,
+        _states: &mut [()],
+        output: &mut ChopOutput,
+        input: &OperatorInputs<ChopInput>,
+    ) -> CookResult {
+        let input_channels = Self::input_channels(input);
+        let config = self.configure(range, input_channels)?;
+        self.inputs.resize_with(input_channels, Vec::new);
+        if let Some(chop) = input.input(0) {
+            for (i, channel) in self.inputs.iter_mut().enumerate() {
+                range.read_input_channel(chop, i, channel);
+            }
+        }
+        self.process_blocks(range.len, &config)?;
+
+        for (c, samples) in self.outputs.iter().enumerate().take(output.num_channels()) {
+            output.channel_mut(c)[..range.len].copy_from_slice(samples);
+        }
+        Ok(())
+    }
+}
+
+/// Where a sample of a channel lives in a block.
+fn index(
+    layout: SampleLayout,
+    channel: usize,
+    frame: usize,
+    channels: usize,
+    frames: usize,
+) -> usize {
+    match layout {
+        SampleLayout::Planar => channel * frames + frame,
+        SampleLayout::Interleaved => frame * channels + channel,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use td_rs_base::TimeInfo;
+
+    /// Scales every sample, recording how it is driven.
+    #[derive(Default)]
+    struct Gain {
+        gain: f32,
+        prepared: Vec<AudioConfig>,
+        resets: usize,
+        blocks: Vec<usize>,
+    }
+
+    impl Op for Gain {}
+
+    impl AudioProcessor for Gain {
+        const MAX_BLOCK_SIZE: usize = 4;
+
+        fn output_channels(&self, input_channels: usize) -> Option<usize> {
+            (input_channels <= 2).then_some(input_channels)
+        }
+
+        fn prepare(&mut self, config: &AudioConfig) {
+            self.prepared.push(*config);
+        }
+
+        fn process(&mut self, block: &mut AudioBlock) -> CookResult {
+            self.blocks.push(block.frames());
+            let (input, output) = block.split();
+            for (out, sample) in output.iter_mut().zip(input) {
+                *out = sample * self.gain;
+            }
+            Ok(())
+        }
+
+        fn reset(&mut self) {
+            self.resets += 1;
+        }
+    }
+
+    fn range(len: usize, sample_rate: f64, continuity: Continuity) -> SampleRange {
+        SampleRange {
+            start: 0,
+            len,
+            sample_rate,
+            continuity,
+            time: TimeInfo::default(),
+        }
+    }
+
+    fn gain(gain: f32) -> AudioChop<Gain> {
+        AudioChop::from_processor(Gain {
+            gain,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_gain_over_blocks() {
+        let mut chop = gain(0.5);
+        let range = range(10, 48000.0, Continuity::Start);
+        let config = chop.configure(&range, 2).unwrap();
+        chop.inputs = vec![(0..10).map(|i| i as f32).collect(), vec![2.0; 10]];
+        chop.process_blocks(range.len, &config).unwrap();
+        assert_eq!(chop.processor.blocks, [4, 4, 2]);
+        let expected = (0..10).map(|i| i as f32 * 0.5).collect::<Vec<_>>();
+        assert_eq!(chop.outputs, [expected, vec![1.0; 10]]);
+
+        // Buffers are reused by the next cook
+        let outputs = chop.outputs[0].as_ptr();
+        chop.process_blocks(3, &config).unwrap();
+        assert_eq!(chop.processor.blocks, [4, 4, 2, 3]);
+        assert_eq!(chop.outputs[0], [0.0, 0.5, 1.0]);
+        assert_eq!(chop.outputs[0].as_ptr(), outputs);
+    }
+
+    #[test]
+    fn test_unsupported_channels() {
+        let mut chop = gain(1.0);
+        let range = range(10, 48000.0, Continuity::Start);
+        let error = chop.configure(&range, 3).unwrap_err();
+        assert_eq!(error.to_string(), "3 input channels are not supported");
+        assert!(chop.processor.prepared.is_empty());
+    }
+
+    #[test]
+    fn test_prepare_and_reset() {
+        let mut chop = gain(1.0);
+        chop.configure(&range(10, 48000.0, Continuity::Start), 1)
+            .unwrap();
+        assert_eq!(chop.processor.prepared.len(), 1);
+        assert_eq!(chop.processor.resets, 0);
+
+        chop.configure(&range(10, 48000.0, Continuity::Continuous), 1)
+            .unwrap();
+        chop.configure(&range(10, 48000.0, Continuity::Gap(5)), 1)
+            .unwrap();
+        assert_eq!(chop.processor.prepared.len(), 1);
+        assert_eq!(chop.processor.resets, 0);
+
+        chop.configure(&range(10, 48000.0, Continuity::Rewind), 1)
+            .unwrap();
+        chop.configure(&range(10, 48000.0, Continuity::Start), 1)
+            .unwrap();
+        assert_eq!(chop.processor.resets, 2);
+
+        chop.configure(&range(10, 44100.0, Continuity::Start), 2)
+            .unwrap();
+        assert_eq!(chop.processor.resets, 2);
+        assert_eq!(
+            chop.processor.prepared[1],
+            AudioConfig {
+                sample_rate: 44100.0,
+                max_block_size: 4,
+                input_channels: 2,
+                output_channels: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_block_layouts() {
+        let planar = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let mut output = [0.0; 3];
+        let block = AudioBlock::new(&planar, &mut output, 2, 1, SampleLayout::Planar);
+        assert_eq!(block.frames(), 3);
+        assert_eq!(block.input_planes().nth(1), Some(&[4.0, 5.0, 6.0][..]));
+        assert_eq!(index(SampleLayout::Planar, 1, 2, 2, 3), 5);
+
+        let interleaved = [1.0, 4.0, 2.0, 5.0, 3.0, 6.0];
+        let mut output = [0.0; 6];
+        let mut block = AudioBlock::new(&interleaved, &mut output, 2, 2, SampleLayout::Interleaved);
+        assert_eq!(block.input_frames().nth(2), Some(&[3.0, 6.0][..]));
+        for frame in block.output_frames() {
+            frame[1] = 1.0;
+        }
+        assert_eq!(output, [0.0, 1.0, 0.0, 1.0, 0.0, 1.0]);
+        assert_eq!(index(SampleLayout::Interleaved, 1, 2, 2, 3), 5);
+    }
+}
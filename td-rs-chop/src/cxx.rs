@@ -11,7 +11,10 @@ use autocxx::subclass::*;
 pub use ffi::TD::*;
 pub use ffi::*;
 pub use td_rs_base::cxx::*;
-use td_rs_base::{DynamicMenuInfo, NodeInfo, OperatorInputs, ParameterManager};
+use td_rs_base::{
+    catch_cook, catch_new, message_c_string, DynamicMenuInfo, FailedOp, MenuInputs, NodeInfo,
+    OpStatus, OperatorInputs, ParameterManager,
+};
 
 use crate::{Chop, ChopOutput};
 
@@ -46,6 +49,19 @@ extern "C" {
 #[subclass(superclass("RustChopPlugin"))]
 pub struct RustChopPluginImpl {
     pub inner: Box<dyn Chop>,
+    status: OpStatus,
+}
+
+impl RustChopPluginImpl {
+    /// Wrap an operator without creating its C++ peer, e.g. to drive it from a
+    /// test host.
+    pub fn new(inner: Box<dyn Chop>) -> Self {
+        Self {
+            inner,
+            status: OpStatus::default(),
+            cpp_peer: CppSubclassCppPeerHolder::Empty,
+        }
+    }
 }
 
 // SAFETY: This can only be used with pointers returned from getNodeInstance() and
@@ -70,12 +86,13 @@ impl AsPlugin for RustChopPluginImplCpp {
 #[no_mangle]
 extern "C" fn chop_new(info: &'static OP_NodeInfo) -> *mut RustChopPluginImplCpp {
     unsafe {
-        let info = NodeInfo::new(info);
-        RustChopPluginImpl::new_cpp_owned(RustChopPluginImpl {
-            inner: chop_new_impl(info),
-            cpp_peer: CppSubclassCppPeerHolder::Empty,
+        let inner = catch_new(|| {
+            let mut chop = chop_new_impl(NodeInfo::new(info));
+            chop.attach_node(&NodeInfo::new(info));
+            chop
         })
-        .into_raw()
+        .unwrap_or_else(|message| Box::new(FailedOp::new(message)));
+        RustChopPluginImpl::new_cpp_owned(RustChopPluginImpl::new(inner)).into_raw()
     }
 }
 
@@ -91,33 +108,43 @@ impl RustChopPlugin_methods for RustChopPluginImpl {
     fn getGeneralInfo(&mut self, mut info: Pin<&mut CHOP_GeneralInfo>, input: &OP_Inputs) {
         #[cfg(feature = "tracing")]
         let _span = { tracing_base::trace_span!("getGeneralInfo").entered() };
+        // A new cook starts with a clean slate
+        self.status.clear();
         let input = OperatorInputs::new(input);
-        if let Some(params) = self.inner.params_mut() {
-            params.update(&input.params());
+        let gen_info = catch_cook(self.inner.as_mut(), &mut self.status, |chop| {
+            chop.update_params(&input.params());
+            chop.try_general_info(&input)
+        });
+        if let Ok(gen_info) = gen_info {
+            info.cookEveryFrame = gen_info.cook_every_frame;
+            info.cookEveryFrameIfAsked = gen_info.cook_every_frame_if_asked;
+            info.timeslice = gen_info.timeslice;
+            info.inputMatchIndex = gen_info.input_match_index;
         }
-        let gen_info = self.inner.general_info(&input);
-        info.cookEveryFrame = gen_info.cook_every_frame;
-        info.cookEveryFrameIfAsked = gen_info.cook_every_frame_if_asked;
-        info.timeslice = gen_info.timeslice;
-        info.inputMatchIndex = gen_info.input_match_index;
     }
 
     fn getOutputInfo(&mut self, mut info: Pin<&mut CHOP_OutputInfo>, input: &OP_Inputs) -> bool {
         #[cfg(feature = "tracing")]
         let _span = { tracing_base::trace_span!("getOutputInfo").entered() };
         let input = OperatorInputs::new(input);
-        if let Some(params) = self.inner.params_mut() {
-            params.update(&input.params());
-        }
-        let out_info = self.inner.output_info(&input);
-        if let Some(out_info) = out_info {
-            info.numChannels = out_info.num_channels as i32;
-            info.sampleRate = out_info.sample_rate;
-            info.numSamples = out_info.num_samples as i32;
-            info.startIndex = out_info.start_index as u32;
-            true
-        } else {
-            false
+        let out_info = catch_cook(self.inner.as_mut(), &mut self.status, |chop| {
+            chop.update_params(&input.params());
+            chop.try_output_info(&input)
+        });
+        match out_info {
+            Ok(Some(out_info)) => {
+                info.numChannels = out_info.num_channels as i32;
+                info.sampleRate = out_info.sample_rate;
+                info.numSamples = out_info.num_samples as i32;
+                info.startIndex = out_info.start_index as u32;
+                true
+            }
+            Ok(None) => false,
+            // Output nothing rather than whatever the input would give us
+            Err(_) => {
+                info.numChannels = 0;
+                true
+            }
         }
     }
 
@@ -125,11 +152,13 @@ impl RustChopPlugin_methods for RustChopPluginImpl {
         #[cfg(feature = "tracing")]
         let _span = { tracing_base::trace_span!("getChannelName").entered() };
         let input = OperatorInputs::new(input);
-        let chan_name = self.inner.channel_name(index as usize, &input);
-        unsafe {
-            let new_string = CString::new(chan_name.as_str()).unwrap();
-            let new_string_ptr = new_string.as_ptr();
-            name.setString(new_string_ptr);
+        let chan_name = catch_cook(self.inner.as_mut(), &mut self.status, |chop| {
+            Ok(CString::new(chop.channel_name(index as usize, &input))?)
+        });
+        if let Ok(chan_name) = chan_name {
+            unsafe {
+                name.setString(chan_name.as_ptr());
+            }
         }
     }
 
@@ -138,31 +167,41 @@ impl RustChopPlugin_methods for RustChopPluginImpl {
         let _span = { tracing_base::trace_span!("execute").entered() };
         let input = OperatorInputs::new(input);
         let mut output = ChopOutput::new(output);
-        if let Some(params) = self.inner.params_mut() {
-            params.update(&input.params());
+        let res = catch_cook(self.inner.as_mut(), &mut self.status, |chop| {
+            chop.update_params(&input.params());
+            chop.try_execute(&mut output, &input)
+        });
+        if res.is_err() {
+            for i in 0..output.num_channels() {
+                output.channel_mut(i).fill(0.0);
+            }
         }
-        self.inner.execute(&mut output, &input);
     }
 
     fn getNumInfoCHOPChans(&mut self) -> i32 {
         #[cfg(feature = "tracing")]
         let _span = { tracing_base::trace_span!("getNumInfoCHOPChans").entered() };
-        if let Some(info_chop) = self.inner.info_chop() {
-            info_chop.size() as i32
-        } else {
-            0
-        }
+        catch_cook(self.inner.as_mut(), &mut self.status, |chop| {
+            Ok(chop
+                .info_chop()
+                .map_or(0, |info_chop| info_chop.size() as i32))
+        })
+        .unwrap_or(0)
     }
 
     fn getInfoCHOPChan(&mut self, index: i32, name: Pin<&mut OP_String>, mut value: Pin<&mut f32>) {
         #[cfg(feature = "tracing")]
         let _span = { tracing_base::trace_span!("getInfoCHOPChan").entered() };
-        if let Some(info_chop) = self.inner.info_chop() {
+        let chan = catch_cook(self.inner.as_mut(), &mut self.status, |chop| {
+            let Some(info_chop) = chop.info_chop() else {
+                return Ok(None);
+            };
             let (info_name, info_value) = info_chop.channel(index as usize);
+            Ok(Some((CString::new(info_name)?, info_value)))
+        });
+        if let Ok(Some((info_name, info_value))) = chan {
             unsafe {
-                let new_string = CString::new(info_name.as_str()).unwrap();
-                let new_string_ptr = new_string.as_ptr();
-                name.setString(new_string_ptr);
+                name.setString(info_name.as_ptr());
             }
             value.set(info_value);
         }
@@ -171,8 +210,10 @@ impl RustChopPlugin_methods for RustChopPluginImpl {
     fn getInfoDATSize(&mut self, mut info: Pin<&mut OP_InfoDATSize>) -> bool {
         #[cfg(feature = "tracing")]
         let _span = { tracing_base::trace_span!("getInfoDATSize").entered() };
-        if let Some(info_dat) = self.inner.info_dat() {
-            let (rows, cols) = info_dat.size();
+        let size = catch_cook(self.inner.as_mut(), &mut self.status, |chop| {
+            Ok(chop.info_dat().map(|info_dat| info_dat.size()))
+        });
+        if let Ok(Some((rows, cols))) = size {
             info.rows = rows as i32;
             info.cols = cols as i32;
             true
@@ -184,58 +225,80 @@ impl RustChopPlugin_methods for RustChopPluginImpl {
     fn getInfoDATEntry(&mut self, index: i32, entryIndex: i32, entry: Pin<&mut OP_String>) {
         #[cfg(feature = "tracing")]
         let _span = { tracing_base::trace_span!("getInfoDATEntry").entered() };
-        if let Some(info_dat) = self.inner.info_dat() {
+        let entry_str = catch_cook(self.inner.as_mut(), &mut self.status, |chop| {
+            let Some(info_dat) = chop.info_dat() else {
+                return Ok(None);
+            };
             let entry_str = info_dat.entry(index as usize, entryIndex as usize);
             if entry_str.is_empty() {
-                return;
+                return Ok(None);
             }
+            Ok(Some(CString::new(entry_str)?))
+        });
+        if let Ok(Some(entry_str)) = entry_str {
             unsafe {
-                let new_string = CString::new(entry_str.as_str()).unwrap();
-                let new_string_ptr = new_string.as_ptr();
-                entry.setString(new_string_ptr);
+                entry.setString(entry_str.as_ptr());
             }
         }
     }
 
     fn getWarningString(&mut self, warning: Pin<&mut OP_String>) {
-        unsafe {
-            let new_string = CString::new(self.inner.warning()).unwrap();
-            let new_string_ptr = new_string.as_ptr();
-            warning.setString(new_string_ptr);
+        let new_string = catch_cook(self.inner.as_mut(), &mut self.status, |chop| {
+            Ok(chop.warning())
+        });
+        if let Ok(new_string) = new_string {
+            let new_string = message_c_string(&new_string);
+            unsafe {
+                warning.setString(new_string.as_ptr());
+            }
         }
     }
 
     fn getErrorString(&mut self, error: Pin<&mut OP_String>) {
+        // If `error` itself panics, show the panic instead
+        let new_string = catch_cook(self.inner.as_mut(), &mut self.status, |chop| {
+            Ok(chop.error())
+        })
+        .unwrap_or_else(|message| message);
+        let new_string = message_c_string(&new_string);
         unsafe {
-            let new_string = CString::new(self.inner.error()).unwrap();
-            let new_string_ptr = new_string.as_ptr();
-            error.setString(new_string_ptr);
+            error.setString(new_string.as_ptr());
         }
     }
 
     fn getInfoPopupString(&mut self, info: Pin<&mut OP_String>) {
-        unsafe {
-            let new_string = CString::new(self.inner.info()).unwrap();
-            let new_string_ptr = new_string.as_ptr();
-            info.setString(new_string_ptr);
+        let new_string = catch_cook(
+            self.inner.as_mut(),
+            &mut self.status,
+            |chop| Ok(chop.info()),
+        );
+        if let Ok(new_string) = new_string {
+            let new_string = message_c_string(&new_string);
+            unsafe {
+                info.setString(new_string.as_ptr());
+            }
         }
     }
 
     fn setupParameters(&mut self, manager: Pin<&mut OP_ParameterManager>) {
         #[cfg(feature = "tracing")]
         let _span = { tracing_base::trace_span!("setupParameters").entered() };
-        let params = self.inner.params_mut();
-        if let Some(params) = params {
-            let mut manager = ParameterManager::new(manager);
-            params.register(&mut manager);
-        }
+        let _ = catch_cook(self.inner.as_mut(), &mut self.status, |chop| {
+            if let Some(params) = chop.params_mut() {
+                let mut manager = ParameterManager::new(manager);
+                params.register(&mut manager);
+            }
+            Ok(())
+        });
     }
 
     unsafe fn pulsePressed(&mut self, name: *const std::ffi::c_char) {
         #[cfg(feature = "tracing")]
         let _span = { tracing_base::trace_span!("pulsePressed").entered() };
-        self.inner
-            .pulse_pressed(std::ffi::CStr::from_ptr(name).to_str().unwrap());
+        let _ = catch_cook(self.inner.as_mut(), &mut self.status, |chop| {
+            chop.press_pulse(std::ffi::CStr::from_ptr(name).to_str()?);
+            Ok(())
+        });
     }
 
     fn buildDynamicMenu(&mut self, inputs: &OP_Inputs, info: Pin<&mut OP_BuildDynamicMenuInfo>) {
@@ -243,6 +306,11 @@ impl RustChopPlugin_methods for RustChopPluginImpl {
         let _span = { tracing_base::trace_span!("buildDynamicMenu").entered() };
         let input = OperatorInputs::new(inputs);
         let mut info = DynamicMenuInfo::new(info);
-        self.inner.build_dynamic_menu(&input, &mut info);
+        let _ = catch_cook(self.inner.as_mut(), &mut self.status, |chop| {
+            if !chop.build_params_menu(&MenuInputs::new(inputs), &mut info) {
+                chop.build_dynamic_menu(&input, &mut info);
+            }
+            Ok(())
+        });
     }
 }
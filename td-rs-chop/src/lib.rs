@@ -1,14 +1,22 @@
 #![feature(min_specialization)]
 
 use std::fmt::Formatter;
+use std::marker::PhantomData;
 use std::ops::{Index, IndexMut};
 use std::pin::Pin;
 
 pub use td_rs_base::chop::*;
 pub use td_rs_base::*;
 
+pub mod audio;
 pub mod cxx;
+pub mod matching;
 pub mod prelude;
+pub mod timeslice;
+
+pub use audio::*;
+pub use matching::*;
+pub use timeslice::*;
 
 #[derive(Debug, Default)]
 pub struct ChopOutputInfo {
@@ -91,6 +99,158 @@ impl<'cook> ChopOutput<'cook> {
             std::slice::from_raw_parts_mut(channel_ptr, self.num_samples())
         }
     }
+
+    fn channel_ptrs(&self) -> &[*mut f32] {
+        if self.num_channels() == 0 {
+            return &[];
+        }
+
+        unsafe { std::slice::from_raw_parts(self.output.channels, self.num_channels()) }
+    }
+
+    /// Get every channel at once.
+    pub fn channels(&self) -> Vec<&[f32]> {
+        (0..self.num_channels()).map(|i| self.channel(i)).collect()
+    }
+
+    /// Get every channel at once, e.g. to write a pair of channels together.
+    pub fn channels_mut(&mut self) -> Vec<&mut [f32]> {
+        let num_samples = self.num_samples();
+        // Safety: every channel has its own buffer, so the slices don't overlap
+        self.channel_ptrs()
+            .iter()
+            .map(|&channel_ptr| unsafe { std::slice::from_raw_parts_mut(channel_ptr, num_samples) })
+            .collect()
+    }
+
+    /// Iterate over the samples in order, one frame holding a sample of every
+    /// channel at a time.
+    pub fn frames_mut(&mut self) -> impl ExactSizeIterator<Item = ChopFrameMut<'_>> {
+        let channels = self.channel_ptrs();
+        (0..self.num_samples()).map(move |index| ChopFrameMut {
+            channels,
+            index,
+            marker: PhantomData,
+        })
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl ChopOutput<'_> {
+    fn array_stride(&self) -> Option<usize> {
+        let channels = self.channel_ptrs();
+        // Safety: `*mut f32` and `*const f32` have the same layout
+        let channels = unsafe {
+            std::slice::from_raw_parts(channels.as_ptr() as *const *const f32, channels.len())
+        };
+        channel_stride(channels, self.num_samples())
+    }
+
+    /// View the samples as an array of shape `(channels, samples)`. Only
+    /// possible if the channels are evenly spaced in memory, see
+    /// [`channel_stride`].
+    pub fn as_array(&self) -> Option<ndarray::ArrayView2<'_, f32>> {
+        use ndarray::ShapeBuilder;
+
+        let shape = (self.num_channels(), self.num_samples());
+        if shape.0 == 0 || shape.1 == 0 {
+            return ndarray::ArrayView2::from_shape(shape, &[]).ok();
+        }
+        let stride = self.array_stride()?;
+        unsafe {
+            Some(ndarray::ArrayView2::from_shape_ptr(
+                shape.strides((stride, 1)),
+                self.channel_ptrs()[0],
+            ))
+        }
+    }
+
+    /// Mutable version of [`ChopOutput::as_array`].
+    pub fn as_array_mut(&mut self) -> Option<ndarray::ArrayViewMut2<'_, f32>> {
+        use ndarray::ShapeBuilder;
+
+        let shape = (self.num_channels(), self.num_samples());
+        if shape.0 == 0 || shape.1 == 0 {
+            return ndarray::ArrayViewMut2::from_shape(shape, &mut []).ok();
+        }
+        let stride = self.array_stride()?;
+        // Safety: the channels don't overlap, so neither do the rows
+        unsafe {
+            Some(ndarray::ArrayViewMut2::from_shape_ptr(
+                shape.strides((stride, 1)),
+                self.channel_ptrs()[0],
+            ))
+        }
+    }
+
+    /// Copy the samples into an array of shape `(channels, samples)`.
+    pub fn to_array(&self) -> ndarray::Array2<f32> {
+        ndarray::Array2::from_shape_fn((self.num_channels(), self.num_samples()), |(c, s)| {
+            self.channel(c)[s]
+        })
+    }
+
+    /// Copy an array of shape `(channels, samples)` into the output, whatever
+    /// the layout of the channels.
+    pub fn assign(&mut self, array: &ndarray::ArrayView2<f32>) {
+        assert_eq!(
+            array.dim(),
+            (self.num_channels(), self.num_samples()),
+            "array shape doesn't match output"
+        );
+        for (channel, row) in self.channels_mut().into_iter().zip(array.rows()) {
+            for (sample, value) in channel.iter_mut().zip(row) {
+                *sample = *value;
+            }
+        }
+    }
+}
+
+/// The samples of every output channel at one sample index.
+pub struct ChopFrameMut<'a> {
+    channels: &'a [*mut f32],
+    index: usize,
+    marker: PhantomData<&'a mut f32>,
+}
+
+impl ChopFrameMut<'_> {
+    /// Get the index of the sample in the channels.
+    pub fn sample_index(&self) -> usize {
+        self.index
+    }
+
+    /// Get the number of channels.
+    pub fn len(&self) -> usize {
+        self.channels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty()
+    }
+
+    /// Set the sample of every channel.
+    pub fn copy_from_slice(&mut self, samples: &[f32]) {
+        assert_eq!(samples.len(), self.len(), "one sample per channel");
+        for (channel, sample) in samples.iter().enumerate() {
+            self[channel] = *sample;
+        }
+    }
+}
+
+impl Index<usize> for ChopFrameMut<'_> {
+    type Output = f32;
+
+    fn index(&self, channel: usize) -> &Self::Output {
+        // Safety: frames are handed out once per sample index, so no two frames
+        // reach the same sample
+        unsafe { &*self.channels[channel].add(self.index) }
+    }
+}
+
+impl IndexMut<usize> for ChopFrameMut<'_> {
+    fn index_mut(&mut self, channel: usize) -> &mut Self::Output {
+        unsafe { &mut *self.channels[channel].add(self.index) }
+    }
 }
 
 impl Index<usize> for ChopOutput<'_> {
@@ -113,14 +273,41 @@ pub trait Chop: Op {
         String::from("")
     }
 
-    fn execute(&mut self, output: &mut ChopOutput, input: &OperatorInputs<ChopInput>);
+    fn execute(&mut self, _output: &mut ChopOutput, _input: &OperatorInputs<ChopInput>) {}
+
+    /// Fallible version of [`Chop::execute`]. On error the output is cleared and
+    /// the error is shown on the node.
+    fn try_execute(
+        &mut self,
+        output: &mut ChopOutput,
+        input: &OperatorInputs<ChopInput>,
+    ) -> CookResult {
+        self.execute(output, input);
+        Ok(())
+    }
+
+    fn general_info(&self, _input: &OperatorInputs<ChopInput>) -> ChopGeneralInfo {
+        ChopGeneralInfo::default()
+    }
 
-    fn general_info(&self, input: &OperatorInputs<ChopInput>) -> ChopGeneralInfo;
+    /// Fallible version of [`Chop::general_info`].
+    fn try_general_info(&self, input: &OperatorInputs<ChopInput>) -> CookResult<ChopGeneralInfo> {
+        Ok(self.general_info(input))
+    }
 
     fn output_info(&self, _input: &OperatorInputs<ChopInput>) -> Option<ChopOutputInfo> {
         None
     }
 
+    /// Fallible version of [`Chop::output_info`]. On error the CHOP outputs no
+    /// channels.
+    fn try_output_info(
+        &self,
+        input: &OperatorInputs<ChopInput>,
+    ) -> CookResult<Option<ChopOutputInfo>> {
+        Ok(self.output_info(input))
+    }
+
     fn build_dynamic_menu(
         &self,
         inputs: &OperatorInputs<ChopInput>,
@@ -129,6 +316,16 @@ pub trait Chop: Op {
     }
 }
 
+impl Chop for FailedOp {
+    // Output nothing rather than whatever the input would give us
+    fn try_output_info(
+        &self,
+        _input: &OperatorInputs<ChopInput>,
+    ) -> CookResult<Option<ChopOutputInfo>> {
+        self.fail()
+    }
+}
+
 #[macro_export]
 macro_rules! chop_plugin {
     ($plugin_ty:ty) => {
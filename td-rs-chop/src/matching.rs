@@ -0,0 +1,327 @@
+//! Pairing up the channels of several CHOP inputs, as the Math and Merge CHOPs
+//! do.
+
+use crate::timeslice::resample;
+use crate::ChopInput;
+use std::borrow::Cow;
+use std::ops::Index;
+use td_rs_base::{anyhow, CookResult, GetInput, OperatorInputs};
+
+/// How a channel of the first input is found in the other inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchBy {
+    /// The channel at the same index.
+    Index,
+    /// The channel with the same name.
+    Name,
+    /// The channel with the same name, or else the channel at the same index.
+    #[default]
+    NameOrIndex,
+}
+
+/// What to do when an input has no channel matching one of the first input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissingChannel {
+    /// Use a channel of zeros.
+    #[default]
+    Zero,
+    /// Leave the channel out of the match altogether.
+    Skip,
+    /// Fail the cook.
+    Error,
+}
+
+/// How the samples of the other inputs are lined up with the first input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Align {
+    /// By time, using the start index and rate of each input. Inputs at
+    /// another rate are resampled.
+    #[default]
+    Time,
+    /// Sample by sample from the start of each input, holding the last sample
+    /// of shorter inputs.
+    Index,
+}
+
+/// Pairs up the channels of several CHOPs. The first CHOP decides the channels,
+/// their order and the sample range of the match.
+///
+/// ```ignore
+/// let matched = ChannelMatcher::new()
+///     .match_by(MatchBy::Name)
+///     .missing(MissingChannel::Skip)
+///     .match_inputs(inputs)?;
+/// for (i, channel) in matched.iter().enumerate() {
+///     for (j, sample) in output[i].iter_mut().enumerate() {
+///         *sample = channel.samples(j).sum();
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChannelMatcher {
+    match_by: MatchBy,
+    missing: MissingChannel,
+    align: Align,
+}
+
+impl ChannelMatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn match_by(mut self, match_by: MatchBy) -> Self {
+        self.match_by = match_by;
+        self
+    }
+
+    pub fn missing(mut self, missing: MissingChannel) -> Self {
+        self.missing = missing;
+        self
+    }
+
+    pub fn align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// Match the channels of every connected input of an operator. The first
+    /// connected input decides the channels.
+    pub fn match_inputs<'cook>(
+        &self,
+        inputs: &OperatorInputs<'cook, ChopInput>,
+    ) -> CookResult<MatchedChannels<'cook>> {
+        let chops = (0..inputs.num_inputs())
+            .filter_map(|i| GetInput::input(inputs, i).map(|chop| (i, chop)))
+            .collect::<Vec<_>>();
+        self.match_indexed(&chops)
+    }
+
+    /// Match the channels of CHOPs, e.g. inputs and CHOP parameters.
+    pub fn match_chops<'a>(&self, chops: &[&'a ChopInput]) -> CookResult<MatchedChannels<'a>> {
+        let chops = chops.iter().copied().enumerate().collect::<Vec<_>>();
+        self.match_indexed(&chops)
+    }
+
+    /// Match the channels of CHOPs paired with their input index, which is
+    /// used to report missing channels.
+    fn match_indexed<'a>(
+        &self,
+        chops: &[(usize, &'a ChopInput)],
+    ) -> CookResult<MatchedChannels<'a>> {
+        let Some((&(_, reference), others)) = chops.split_first() else {
+            return Ok(MatchedChannels::default());
+        };
+        let num_samples = reference.num_samples();
+        let mut channels = Vec::with_capacity(reference.num_channels());
+        'channels: for (index, (name, samples)) in reference.channels().enumerate() {
+            let mut matched = Vec::with_capacity(chops.len());
+            matched.push(Cow::Borrowed(samples));
+            for &(input, chop) in others {
+                match self.find(chop, index, name) {
+                    Some(other) => matched.push(self.aligned(reference, chop, other)),
+                    None => match self.missing {
+                        MissingChannel::Zero => matched.push(Cow::Owned(vec![0.0; num_samples])),
+                        MissingChannel::Skip => continue 'channels,
+                        MissingChannel::Error => {
+                            anyhow::bail!("Input {} has no channel matching {name}", input + 1)
+                        }
+                    },
+                }
+            }
+            channels.push(MatchedChannel {
+                name,
+                samples: matched,
+            });
+        }
+        Ok(MatchedChannels {
+            channels,
+            num_samples,
+            sample_rate: reference.sample_rate(),
+            start_index: reference.start_index(),
+        })
+    }
+
+    /// The index of the channel of `chop` matching channel `index` of the first
+    /// input.
+    fn find(&self, chop: &ChopInput, index: usize, name: &str) -> Option<usize> {
+        let by_index = (index < chop.num_channels()).then_some(index);
+        match self.match_by {
+            MatchBy::Index => by_index,
+            MatchBy::Name => chop.channel_index(name),
+            MatchBy::NameOrIndex => chop.channel_index(name).or(by_index),
+        }
+    }
+
+    fn aligned<'a>(
+        &self,
+        reference: &ChopInput,
+        chop: &'a ChopInput,
+        index: usize,
+    ) -> Cow<'a, [f32]> {
+        align(
+            self.align,
+            chop.channel(index),
+            chop.sample_rate(),
+            chop.start_index(),
+            reference.sample_rate(),
+            reference.start_index(),
+            reference.num_samples(),
+        )
+    }
+}
+
+/// Line up `samples`, starting at index `start` at `rate`, with the `len`
+/// samples starting at `out_start` at `out_rate`. Borrows the samples if they
+/// already line up.
+fn align(
+    align: Align,
+    samples: &[f32],
+    rate: f64,
+    start: f64,
+    out_rate: f64,
+    out_start: f64,
+    len: usize,
+) -> Cow<'_, [f32]> {
+    if samples.len() == len && rate == out_rate && (align == Align::Index || start == out_start) {
+        return Cow::Borrowed(samples);
+    }
+    match align {
+        Align::Time => {
+            let mut aligned = vec![0.0; len];
+            resample(samples, rate, start, out_rate, out_start, &mut aligned);
+            Cow::Owned(aligned)
+        }
+        Align::Index => Cow::Owned(
+            (0..len)
+                .map(|i| samples.get(i).or(samples.last()).copied().unwrap_or(0.0))
+                .collect(),
+        ),
+    }
+}
+
+/// A channel of the first input along with the matching channel of every other
+/// input, lined up with it.
+#[derive(Debug, Clone)]
+pub struct MatchedChannel<'a> {
+    name: &'a str,
+    samples: Vec<Cow<'a, [f32]>>,
+}
+
+impl MatchedChannel<'_> {
+    /// The name of the channel in the first input.
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
+    /// The number of inputs matched.
+    pub fn num_inputs(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// The samples of the channel in an input.
+    pub fn input(&self, input: usize) -> &[f32] {
+        &self.samples[input]
+    }
+
+    /// Iterate over the samples of the channel in every input.
+    pub fn inputs(&self) -> impl Iterator<Item = &[f32]> {
+        self.samples.iter().map(|samples| &samples[..])
+    }
+
+    /// Iterate over the sample at an index of every input, e.g. to fold them
+    /// with an operation.
+    pub fn samples(&self, index: usize) -> impl Iterator<Item = f32> + '_ {
+        self.samples.iter().map(move |samples| samples[index])
+    }
+}
+
+impl Index<usize> for MatchedChannel<'_> {
+    type Output = [f32];
+
+    fn index(&self, input: usize) -> &Self::Output {
+        self.input(input)
+    }
+}
+
+/// The channels matched across inputs, in the order of the first input.
+#[derive(Debug, Clone, Default)]
+pub struct MatchedChannels<'a> {
+    channels: Vec<MatchedChannel<'a>>,
+    num_samples: usize,
+    sample_rate: f64,
+    start_index: f64,
+}
+
+impl<'a> MatchedChannels<'a> {
+    /// The number of matched channels.
+    pub fn len(&self) -> usize {
+        self.channels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&MatchedChannel<'a>> {
+        self.channels.get(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &MatchedChannel<'a>> {
+        self.channels.iter()
+    }
+
+    /// The names of the matched channels, e.g. to name the output channels.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.channels.iter().map(MatchedChannel::name)
+    }
+
+    /// The number of samples of every matched channel, that of the first input.
+    pub fn num_samples(&self) -> usize {
+        self.num_samples
+    }
+
+    /// The sample rate of the first input.
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// The start index of the first input.
+    pub fn start_index(&self) -> f64 {
+        self.start_index
+    }
+}
+
+impl<'a> Index<usize> for MatchedChannels<'a> {
+    type Output = MatchedChannel<'a>;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.channels[index]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_align() {
+        let samples = [1.0, 2.0, 3.0];
+        let aligned = align(Align::Time, &samples, 60.0, 4.0, 60.0, 4.0, 3);
+        assert!(matches!(aligned, Cow::Borrowed(_)));
+
+        let aligned = align(Align::Time, &samples, 60.0, 5.0, 60.0, 4.0, 4);
+        assert_eq!(&aligned[..], [1.0, 1.0, 2.0, 3.0]);
+
+        let aligned = align(Align::Time, &samples, 30.0, 2.0, 60.0, 4.0, 4);
+        assert_eq!(&aligned[..], [1.0, 1.5, 2.0, 2.5]);
+
+        let aligned = align(Align::Index, &samples, 60.0, 5.0, 60.0, 4.0, 3);
+        assert!(matches!(aligned, Cow::Borrowed(_)));
+
+        let aligned = align(Align::Index, &samples, 60.0, 0.0, 60.0, 0.0, 5);
+        assert_eq!(&aligned[..], [1.0, 2.0, 3.0, 3.0, 3.0]);
+
+        let aligned = align(Align::Index, &[], 60.0, 0.0, 60.0, 0.0, 2);
+        assert_eq!(&aligned[..], [0.0, 0.0]);
+    }
+}
@@ -0,0 +1,347 @@
+//! CHOPs that output a continuous stream of samples, one timeslice per cook.
+
+use crate::{Chop, ChopGeneralInfo, ChopInput, ChopOutput, ChopOutputInfo};
+use std::borrow::Cow;
+use std::ops::Range;
+use td_rs_base::{CookResult, Op, OperatorInputs, TimeInfo};
+
+/// How the samples of a cook follow on from those of the previous cook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Continuity {
+    /// The first cook, or the first since the sample rate changed or the state
+    /// was reset.
+    Start,
+    /// The samples directly follow the previous cook.
+    Continuous,
+    /// Samples were skipped since the previous cook, e.g. when frames were
+    /// dropped.
+    Gap(u64),
+    /// The samples start before the end of the previous cook, e.g. when the
+    /// timeline looped or was scrubbed.
+    Rewind,
+}
+
+/// The samples to output in a cook.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SampleRange {
+    /// Index of the first sample, counted at `sample_rate`.
+    pub start: u64,
+    /// The number of samples.
+    pub len: usize,
+    /// Samples per second of the output.
+    pub sample_rate: f64,
+    pub continuity: Continuity,
+    /// Timing of the cook.
+    pub time: TimeInfo,
+}
+
+impl SampleRange {
+    /// Index of the sample after the last one.
+    pub fn end(&self) -> u64 {
+        self.start + self.len as u64
+    }
+
+    /// Indices of the samples.
+    pub fn indices(&self) -> Range<u64> {
+        self.start..self.end()
+    }
+
+    /// The number of samples skipped since the previous cook. Generators that
+    /// accumulate state per sample can advance by this much to stay in phase.
+    pub fn skipped(&self) -> u64 {
+        match self.continuity {
+            Continuity::Gap(skipped) => skipped,
+            _ => 0,
+        }
+    }
+
+    /// Seconds between samples.
+    pub fn sample_period(&self) -> f64 {
+        if self.sample_rate > 0.0 {
+            1.0 / self.sample_rate
+        } else {
+            0.0
+        }
+    }
+
+    /// Time in seconds of the sample at `offset` from the start of the range.
+    pub fn seconds(&self, offset: usize) -> f64 {
+        (self.start + offset as u64) as f64 * self.sample_period()
+    }
+
+    /// A channel of an input, resampled to the rate and range of the output.
+    ///
+    /// Input samples are aligned by time using the start index and rate of the
+    /// input and interpolated linearly, holding the first and last samples
+    /// outside of the input. Borrows the channel if it already matches.
+    pub fn input_channel<'a>(&self, input: &'a ChopInput, index: usize) -> Cow<'a, [f32]> {
+        let samples = input.channel(index);
+        if self.is_aligned(input, samples) {
+            return Cow::Borrowed(samples);
+        }
+        let mut resampled = vec![0.0; self.len];
+        self.resample_input(input, samples, &mut resampled);
+        Cow::Owned(resampled)
+    }
+
+    /// Like [`SampleRange::input_channel`], but into a buffer that can be
+    /// reused between cooks.
+    pub fn read_input_channel(&self, input: &ChopInput, index: usize, out: &mut Vec<f32>) {
+        let samples = input.channel(index);
+        out.resize(self.len, 0.0);
+        if self.is_aligned(input, samples) {
+            out.copy_from_slice(samples);
+        } else {
+            self.resample_input(input, samples, out);
+        }
+    }
+
+    fn is_aligned(&self, input: &ChopInput, samples: &[f32]) -> bool {
+        input.sample_rate() == self.sample_rate
+            && input.start_index() == self.start as f64
+            && samples.len() == self.len
+    }
+
+    fn resample_input(&self, input: &ChopInput, samples: &[f32], out: &mut [f32]) {
+        resample(
+            samples,
+            input.sample_rate(),
+            input.start_index(),
+            self.sample_rate,
+            self.start as f64,
+            out,
+        );
+    }
+}
+
+/// Resample `samples`, starting at index `start` at `rate`, to the samples
+/// starting at `out_start` at `out_rate`. Samples are aligned by time and
+/// interpolated linearly, holding the first and last samples outside of the
+/// input.
+pub(crate) fn resample(
+    samples: &[f32],
+    rate: f64,
+    start: f64,
+    out_rate: f64,
+    out_start: f64,
+    out: &mut [f32],
+) {
+    let (Some(&first), Some(&last)) = (samples.first(), samples.last()) else {
+        out.fill(0.0);
+        return;
+    };
+    let step = if out_rate > 0.0 { rate / out_rate } else { 0.0 };
+    let offset = out_start * step - start;
+    for (i, out) in out.iter_mut().enumerate() {
+        let pos = offset + i as f64 * step;
+        *out = if pos <= 0.0 {
+            first
+        } else if pos >= (samples.len() - 1) as f64 {
+            last
+        } else {
+            let index = pos.floor();
+            let frac = (pos - index) as f32;
+            let index = index as usize;
+            samples[index] + (samples[index + 1] - samples[index]) * frac
+        };
+    }
+}
+
+/// Per-channel state of a [`TimesliceChop`], kept across cooks.
+#[derive(Debug, Clone)]
+pub struct Timeslice<S> {
+    states: Vec<S>,
+    next: Option<u64>,
+    sample_rate: f64,
+}
+
+impl<S> Default for Timeslice<S> {
+    fn default() -> Self {
+        Self {
+            states: Vec::new(),
+            next: None,
+            sample_rate: 0.0,
+        }
+    }
+}
+
+impl<S: Default> Timeslice<S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The state of each output channel.
+    pub fn states(&self) -> &[S] {
+        &self.states
+    }
+
+    /// Drop the state of every channel. The next cook starts afresh.
+    pub fn reset(&mut self) {
+        self.states.clear();
+        self.next = None;
+    }
+
+    /// Work out the range of a cook and make room for the state of each
+    /// channel. Channels that appear get default state, the state of channels
+    /// that disappear is dropped.
+    pub fn advance(
+        &mut self,
+        start: u64,
+        len: usize,
+        sample_rate: f64,
+        num_channels: usize,
+        time: &TimeInfo,
+    ) -> SampleRange {
+        if sample_rate != self.sample_rate {
+            self.reset();
+            self.sample_rate = sample_rate;
+        }
+        let continuity = match self.next {
+            None => Continuity::Start,
+            Some(next) if start == next => Continuity::Continuous,
+            Some(next) if start > next => Continuity::Gap(start - next),
+            Some(_) => Continuity::Rewind,
+        };
+        self.states.resize_with(num_channels, S::default);
+        self.next = Some(start + len as u64);
+        SampleRange {
+            start,
+            len,
+            sample_rate,
+            continuity,
+            time: *time,
+        }
+    }
+}
+
+/// A CHOP that outputs a timeslice every frame, such as an oscillator or an
+/// audio generator.
+///
+/// Every type implementing this trait is a [`Chop`]. Each cook hands the
+/// operator the exact range of samples since the previous cook together with
+/// the state of every channel, so output stays continuous across cooks.
+pub trait TimesliceChop: Op {
+    /// The state of a channel carried from one cook to the next, e.g. the
+    /// phase of an oscillator.
+    type State: Default;
+
+    /// Where the state of the channels is kept.
+    fn timeslice(&mut self) -> &mut Timeslice<Self::State>;
+
+    fn num_channels(&self, input: &OperatorInputs<ChopInput>) -> usize;
+
+    /// Samples per second of the output. Defaults to the rate of the first
+    /// input, or the timeline rate without inputs.
+    fn sample_rate(&self, input: &OperatorInputs<ChopInput>) -> f32 {
+        match input.input(0) {
+            Some(chop) => chop.sample_rate() as f32,
+            None => input.time_info().rate as f32,
+        }
+    }
+
+    fn channel_name(&self, index: usize, _input: &OperatorInputs<ChopInput>) -> String {
+        format!("chan{}", index + 1)
+    }
+
+    /// Fill `output` with the samples of `range`. `states` holds the state of
+    /// each output channel.
+    fn cook_timeslice(
+        &mut self,
+        range: &SampleRange,
+        states: &mut [Self::State],
+        output: &mut ChopOutput,
+        input: &OperatorInputs<ChopInput>,
+    ) -> CookResult;
+}
+
+impl<T: TimesliceChop> Chop for T {
+    fn channel_name(&self, index: usize, input: &OperatorInputs<ChopInput>) -> String {
+        TimesliceChop::channel_name(self, index, input)
+    }
+
+    fn try_execute(
+        &mut self,
+        output: &mut ChopOutput,
+        input: &OperatorInputs<ChopInput>,
+    ) -> CookResult {
+        // Taken out of the operator for the cook so both can be borrowed
+        let mut timeslice = std::mem::take(self.timeslice());
+        let range = timeslice.advance(
+            output.start_index() as u64,
+            output.num_samples(),
+            output.sample_rate() as f64,
+            output.num_channels(),
+            &input.time_info(),
+        );
+        let res = self.cook_timeslice(&range, &mut timeslice.states, output, input);
+        *self.timeslice() = timeslice;
+        res
+    }
+
+    fn general_info(&self, _input: &OperatorInputs<ChopInput>) -> ChopGeneralInfo {
+        ChopGeneralInfo {
+            cook_every_frame: true,
+            cook_every_frame_if_asked: false,
+            timeslice: true,
+            input_match_index: 0,
+        }
+    }
+
+    fn output_info(&self, input: &OperatorInputs<ChopInput>) -> Option<ChopOutputInfo> {
+        // The length and start of a timeslice are chosen by TouchDesigner
+        Some(ChopOutputInfo {
+            num_channels: self.num_channels(input) as u32,
+            sample_rate: self.sample_rate(input),
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn time(delta_frames: f64) -> TimeInfo {
+        TimeInfo {
+            rate: 60.0,
+            root_rate: 60.0,
+            delta_frames,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_advance() {
+        let mut timeslice = Timeslice::<f32>::new();
+        let range = timeslice.advance(0, 800, 48000.0, 2, &time(0.0));
+        assert_eq!(range.continuity, Continuity::Start);
+        assert_eq!(timeslice.states().len(), 2);
+
+        timeslice.states[0] = 0.5;
+        let range = timeslice.advance(800, 800, 48000.0, 2, &time(1.0));
+        assert_eq!(range.continuity, Continuity::Continuous);
+        assert_eq!(timeslice.states()[0], 0.5);
+
+        let range = timeslice.advance(2400, 800, 48000.0, 3, &time(2.0));
+        assert_eq!(range.continuity, Continuity::Gap(800));
+        assert_eq!(range.skipped(), 800);
+        assert_eq!(timeslice.states(), &[0.5, 0.0, 0.0]);
+
+        let range = timeslice.advance(0, 800, 48000.0, 3, &time(1.0));
+        assert_eq!(range.continuity, Continuity::Rewind);
+
+        let range = timeslice.advance(800, 1600, 96000.0, 3, &time(1.0));
+        assert_eq!(range.continuity, Continuity::Start);
+        assert_eq!(timeslice.states(), &[0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_resample() {
+        let mut out = [0.0; 4];
+        resample(&[0.0, 1.0, 2.0], 60.0, 2.0, 120.0, 4.0, &mut out);
+        assert_eq!(out, [0.0, 0.5, 1.0, 1.5]);
+
+        resample(&[1.0, 2.0], 60.0, 10.0, 120.0, 4.0, &mut out);
+        assert_eq!(out, [1.0; 4]);
+    }
+}
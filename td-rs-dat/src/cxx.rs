@@ -8,7 +8,10 @@ use autocxx::subclass::*;
 use std::ffi::CString;
 
 use std::pin::Pin;
-use td_rs_base::{param::ParameterManager, DynamicMenuInfo, NodeInfo, OperatorInputs};
+use td_rs_base::{
+    catch_cook, catch_new, message_c_string, param::ParameterManager, DynamicMenuInfo, FailedOp,
+    MenuInputs, NodeInfo, OpStatus, OperatorInputs,
+};
 
 include_cpp! {
     #include "DAT_CPlusPlusBase.h"
@@ -61,17 +64,31 @@ impl AsPlugin for RustDatPluginImplCpp {
 #[subclass(superclass("RustDatPlugin"))]
 pub struct RustDatPluginImpl {
     inner: Box<dyn Dat>,
+    status: OpStatus,
+}
+
+impl RustDatPluginImpl {
+    /// Wrap an operator without creating its C++ peer, e.g. to drive it from a
+    /// test host.
+    pub fn new(inner: Box<dyn Dat>) -> Self {
+        Self {
+            inner,
+            status: OpStatus::default(),
+            cpp_peer: CppSubclassCppPeerHolder::Empty,
+        }
+    }
 }
 
 #[no_mangle]
 extern "C" fn dat_new(info: &'static OP_NodeInfo) -> *mut RustDatPluginImplCpp {
     unsafe {
-        let info = NodeInfo::new(info);
-        RustDatPluginImpl::new_cpp_owned(RustDatPluginImpl {
-            inner: dat_new_impl(info),
-            cpp_peer: CppSubclassCppPeerHolder::Empty,
+        let inner = catch_new(|| {
+            let mut dat = dat_new_impl(NodeInfo::new(info));
+            dat.attach_node(&NodeInfo::new(info));
+            dat
         })
-        .into_raw()
+        .unwrap_or_else(|message| Box::new(FailedOp::new(message)));
+        RustDatPluginImpl::new_cpp_owned(RustDatPluginImpl::new(inner)).into_raw()
     }
 }
 
@@ -87,47 +104,59 @@ impl RustDatPlugin_methods for RustDatPluginImpl {
     fn getGeneralInfo(&mut self, mut info: Pin<&mut DAT_GeneralInfo>, inputs: &OP_Inputs) {
         #[cfg(feature = "tracing")]
         let _span = { tracing_base::trace_span!("getGeneralInfo").entered() };
+        // A new cook starts with a clean slate
+        self.status.clear();
         let input = OperatorInputs::new(inputs);
-        if let Some(params) = self.inner.params_mut() {
-            params.update(&input.params());
+        let gen_info = catch_cook(self.inner.as_mut(), &mut self.status, |dat| {
+            dat.update_params(&input.params());
+            dat.try_general_info(&input)
+        });
+        if let Ok(gen_info) = gen_info {
+            info.cookEveryFrame = gen_info.cook_every_frame;
+            info.cookEveryFrameIfAsked = gen_info.cook_every_frame_if_asked;
         }
-        let gen_info = self.inner.general_info(&input);
-        info.cookEveryFrame = gen_info.cook_every_frame;
-        info.cookEveryFrameIfAsked = gen_info.cook_every_frame_if_asked;
     }
 
-    fn execute(&mut self, outputs: Pin<&mut DAT_Output>, inputs: &OP_Inputs) {
+    fn execute(&mut self, mut outputs: Pin<&mut DAT_Output>, inputs: &OP_Inputs) {
         #[cfg(feature = "tracing")]
         let span = {
             tracing_base::trace_span!("execute").entered();
         };
         let input = OperatorInputs::new(inputs);
-        let output = DatOutput::new(outputs);
-        if let Some(params) = self.inner.params_mut() {
-            params.update(&input.params());
+        let res = catch_cook(self.inner.as_mut(), &mut self.status, |dat| {
+            dat.update_params(&input.params());
+            dat.try_execute(DatOutput::new(outputs.as_mut()), &input)
+        });
+        if res.is_err() {
+            outputs.as_mut().setOutputDataType(DAT_OutDataType::Table);
+            outputs.as_mut().setTableSize(0, 0);
         }
-        self.inner.execute(output, &input);
     }
 
     fn getNumInfoCHOPChans(&mut self) -> i32 {
         #[cfg(feature = "tracing")]
         let _span = { tracing_base::trace_span!("getNumInfoCHOPChans").entered() };
-        if let Some(info_chop) = self.inner.info_chop() {
-            info_chop.size() as i32
-        } else {
-            0
-        }
+        catch_cook(self.inner.as_mut(), &mut self.status, |dat| {
+            Ok(dat
+                .info_chop()
+                .map_or(0, |info_chop| info_chop.size() as i32))
+        })
+        .unwrap_or(0)
     }
 
     fn getInfoCHOPChan(&mut self, index: i32, name: Pin<&mut OP_String>, mut value: Pin<&mut f32>) {
         #[cfg(feature = "tracing")]
         let _span = { tracing_base::trace_span!("getInfoCHOPChan").entered() };
-        if let Some(info_chop) = self.inner.info_chop() {
+        let chan = catch_cook(self.inner.as_mut(), &mut self.status, |dat| {
+            let Some(info_chop) = dat.info_chop() else {
+                return Ok(None);
+            };
             let (info_name, info_value) = info_chop.channel(index as usize);
+            Ok(Some((CString::new(info_name)?, info_value)))
+        });
+        if let Ok(Some((info_name, info_value))) = chan {
             unsafe {
-                let new_string = CString::new(info_name.as_str()).unwrap();
-                let new_string_ptr = new_string.as_ptr();
-                name.setString(new_string_ptr);
+                name.setString(info_name.as_ptr());
             }
             value.set(info_value);
         }
@@ -136,8 +165,10 @@ impl RustDatPlugin_methods for RustDatPluginImpl {
     fn getInfoDATSize(&mut self, mut info: Pin<&mut OP_InfoDATSize>) -> bool {
         #[cfg(feature = "tracing")]
         let _span = { tracing_base::trace_span!("getInfoDATSize").entered() };
-        if let Some(info_dat) = self.inner.info_dat() {
-            let (rows, cols) = info_dat.size();
+        let size = catch_cook(self.inner.as_mut(), &mut self.status, |dat| {
+            Ok(dat.info_dat().map(|info_dat| info_dat.size()))
+        });
+        if let Ok(Some((rows, cols))) = size {
             info.rows = rows as i32;
             info.cols = cols as i32;
             true
@@ -149,58 +180,74 @@ impl RustDatPlugin_methods for RustDatPluginImpl {
     fn getInfoDATEntry(&mut self, index: i32, entryIndex: i32, entry: Pin<&mut OP_String>) {
         #[cfg(feature = "tracing")]
         let _span = { tracing_base::trace_span!("getInfoDATEntry").entered() };
-        if let Some(info_dat) = self.inner.info_dat() {
+        let entry_str = catch_cook(self.inner.as_mut(), &mut self.status, |dat| {
+            let Some(info_dat) = dat.info_dat() else {
+                return Ok(None);
+            };
             let entry_str = info_dat.entry(index as usize, entryIndex as usize);
             if entry_str.is_empty() {
-                return;
+                return Ok(None);
             }
+            Ok(Some(CString::new(entry_str)?))
+        });
+        if let Ok(Some(entry_str)) = entry_str {
             unsafe {
-                let new_string = CString::new(entry_str.as_str()).unwrap();
-                let new_string_ptr = new_string.as_ptr();
-                entry.setString(new_string_ptr);
+                entry.setString(entry_str.as_ptr());
             }
         }
     }
 
     fn getWarningString(&mut self, warning: Pin<&mut OP_String>) {
-        unsafe {
-            let new_string = CString::new(self.inner.warning()).unwrap();
-            let new_string_ptr = new_string.as_ptr();
-            warning.setString(new_string_ptr);
+        let new_string = catch_cook(self.inner.as_mut(), &mut self.status, |dat| {
+            Ok(dat.warning())
+        });
+        if let Ok(new_string) = new_string {
+            let new_string = message_c_string(&new_string);
+            unsafe {
+                warning.setString(new_string.as_ptr());
+            }
         }
     }
 
     fn getErrorString(&mut self, error: Pin<&mut OP_String>) {
+        // If `error` itself panics, show the panic instead
+        let new_string = catch_cook(self.inner.as_mut(), &mut self.status, |dat| Ok(dat.error()))
+            .unwrap_or_else(|message| message);
+        let new_string = message_c_string(&new_string);
         unsafe {
-            let new_string = CString::new(self.inner.error()).unwrap();
-            let new_string_ptr = new_string.as_ptr();
-            error.setString(new_string_ptr);
+            error.setString(new_string.as_ptr());
         }
     }
 
     fn getInfoPopupString(&mut self, info: Pin<&mut OP_String>) {
-        unsafe {
-            let new_string = CString::new(self.inner.info()).unwrap();
-            let new_string_ptr = new_string.as_ptr();
-            info.setString(new_string_ptr);
+        let new_string = catch_cook(self.inner.as_mut(), &mut self.status, |dat| Ok(dat.info()));
+        if let Ok(new_string) = new_string {
+            let new_string = message_c_string(&new_string);
+            unsafe {
+                info.setString(new_string.as_ptr());
+            }
         }
     }
 
     fn setupParameters(&mut self, manager: Pin<&mut OP_ParameterManager>) {
         #[cfg(feature = "tracing")]
         let _span = { tracing_base::trace_span!("setupParameters").entered() };
-        let params = self.inner.params_mut();
-        if let Some(params) = params {
-            let mut manager = ParameterManager::new(manager);
-            params.register(&mut manager);
-        }
+        let _ = catch_cook(self.inner.as_mut(), &mut self.status, |dat| {
+            if let Some(params) = dat.params_mut() {
+                let mut manager = ParameterManager::new(manager);
+                params.register(&mut manager);
+            }
+            Ok(())
+        });
     }
 
     unsafe fn pulsePressed(&mut self, name: *const std::ffi::c_char) {
         #[cfg(feature = "tracing")]
         let _span = { tracing_base::trace_span!("pulsePressed").entered() };
-        self.inner
-            .pulse_pressed(std::ffi::CStr::from_ptr(name).to_str().unwrap());
+        let _ = catch_cook(self.inner.as_mut(), &mut self.status, |dat| {
+            dat.press_pulse(std::ffi::CStr::from_ptr(name).to_str()?);
+            Ok(())
+        });
     }
 
     fn buildDynamicMenu(&mut self, inputs: &OP_Inputs, info: Pin<&mut OP_BuildDynamicMenuInfo>) {
@@ -208,6 +255,11 @@ impl RustDatPlugin_methods for RustDatPluginImpl {
         let _span = { tracing_base::trace_span!("buildDynamicMenu").entered() };
         let input = OperatorInputs::new(inputs);
         let mut info = DynamicMenuInfo::new(info);
-        self.inner.build_dynamic_menu(&input, &mut info);
+        let _ = catch_cook(self.inner.as_mut(), &mut self.status, |dat| {
+            if !dat.build_params_menu(&MenuInputs::new(inputs), &mut info) {
+                dat.build_dynamic_menu(&input, &mut info);
+            }
+            Ok(())
+        });
     }
 }
@@ -235,10 +235,22 @@ pub trait Dat: Op {
         DatGeneralInfo::default()
     }
 
+    /// Fallible version of [`Dat::general_info`].
+    fn try_general_info(&self, input: &OperatorInputs<DatInput>) -> CookResult<DatGeneralInfo> {
+        Ok(self.general_info(input))
+    }
+
     fn execute(&mut self, _output: DatOutput, _input: &OperatorInputs<DatInput>) {
         // Do nothing by default.
     }
 
+    /// Fallible version of [`Dat::execute`]. On error the DAT is emptied and
+    /// the error is shown on the node.
+    fn try_execute(&mut self, output: DatOutput, input: &OperatorInputs<DatInput>) -> CookResult {
+        self.execute(output, input);
+        Ok(())
+    }
+
     fn build_dynamic_menu(
         &mut self,
         inputs: &OperatorInputs<DatInput>,
@@ -247,6 +259,12 @@ pub trait Dat: Op {
     }
 }
 
+impl Dat for FailedOp {
+    fn try_execute(&mut self, _output: DatOutput, _input: &OperatorInputs<DatInput>) -> CookResult {
+        self.fail()
+    }
+}
+
 #[macro_export]
 macro_rules! dat_plugin {
     ($plugin_ty:ty) => {
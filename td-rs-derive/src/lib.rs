@@ -4,36 +4,106 @@ use proc_macro::TokenStream;
 
 use quote::quote;
 
-use syn::{
-    parse_macro_input, Data, DeriveInput, Fields, Lit, Meta, MetaNameValue, NestedMeta, Variant,
-};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit_mut::VisitMut;
+use syn::{parenthesized, parse_macro_input, token, Data, DeriveInput, Fields, Lit, LitStr, Token};
 
-#[proc_macro_derive(Param)]
+#[proc_macro_derive(Param, attributes(menu))]
 pub fn derive_param(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
+    impl_param(&input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
 
-    let enum_ident = input.ident;
-    let enum_data = if let Data::Enum(data) = input.data {
-        data
-    } else {
-        panic!("`Param` can only be derived for enums");
+fn impl_param(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let enum_ident = &input.ident;
+    let Data::Enum(enum_data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            enum_ident,
+            "`Param` can only be derived for enums",
+        ));
     };
+    if enum_data.variants.is_empty() {
+        return Err(syn::Error::new_spanned(
+            enum_ident,
+            "`Param` can't be derived for an enum without variants",
+        ));
+    }
 
-    let variant_names = enum_data
-        .variants
-        .iter()
-        .map(|variant| variant.ident.to_string())
-        .collect::<Vec<String>>();
+    // `#[menu(skip_serde)]` leaves the preset serialization to the enum, e.g.
+    // when it derives `Serialize` and `Deserialize` itself
+    let mut skip_serde = false;
+    for attr in input.attrs.iter().filter(|attr| attr.path.is_ident("menu")) {
+        for arg in parse_args(attr)? {
+            match arg {
+                AttrArg::Flag(key) if key == "skip_serde" => skip_serde = true,
+                arg => {
+                    return Err(syn::Error::new_spanned(
+                        arg.key(),
+                        "unknown `menu` argument on an enum, expected `skip_serde`",
+                    ))
+                }
+            }
+        }
+    }
 
-    let variant_labels = variant_names
-        .iter()
-        .map(|name| camel_case_to_words(name))
-        .collect::<Vec<String>>();
+    let mut variant_idents = Vec::new();
+    let mut variant_names = Vec::new();
+    let mut variant_labels = Vec::new();
+    for variant in &enum_data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(
+                &variant.fields,
+                "menu variants can't have fields",
+            ));
+        }
+
+        let mut name = variant.ident.to_string();
+        let mut label = camel_case_to_words(&name);
+        for attr in variant
+            .attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("menu"))
+        {
+            for arg in parse_args(attr)? {
+                match arg {
+                    AttrArg::Value(key, value) if key == "name" => {
+                        name = expr_lit_str(&value)?.value()
+                    }
+                    AttrArg::Value(key, value) if key == "label" => {
+                        label = expr_lit_str(&value)?.value()
+                    }
+                    arg => {
+                        return Err(syn::Error::new_spanned(
+                            arg.key(),
+                            "unknown `menu` argument, expected `name` or `label`",
+                        ))
+                    }
+                }
+            }
+        }
+        if variant_names.contains(&name) {
+            return Err(syn::Error::new_spanned(
+                &variant.ident,
+                format!("duplicate menu name `{name}`"),
+            ));
+        }
 
-    let variants = enum_data.variants.into_iter().collect::<Vec<Variant>>();
-    let try_from_i32_match_arms = try_from_i32_match_arms(&variants);
+        variant_idents.push(&variant.ident);
+        variant_names.push(name);
+        variant_labels.push(label);
+    }
+    let indexes = (0..variant_idents.len() as i32).collect::<Vec<_>>();
+    let serde_code = if skip_serde {
+        quote! {}
+    } else {
+        quote! { ::td_rs_base::menu_param_serde!(#enum_ident); }
+    };
 
-    let output = quote! {
+    Ok(quote! {
         impl MenuParam for #enum_ident {
             fn names() -> Vec<String> {
                 vec![
@@ -46,50 +116,67 @@ pub fn derive_param(input: TokenStream) -> TokenStream {
                     #(String::from(#variant_labels)),*
                 ]
             }
+
+            fn from_menu_index(index: i32) -> Option<Self> {
+                match index {
+                    #(#indexes => Some(Self::#variant_idents),)*
+                    _ => None,
+                }
+            }
+
+            fn menu_index(&self) -> i32 {
+                match *self {
+                    #(Self::#variant_idents => #indexes,)*
+                }
+            }
         }
 
         impl std::convert::TryFrom<i32> for #enum_ident {
             type Error = String;
 
+            /// The variant at a position in the menu.
             fn try_from(value: i32) -> Result<Self, Self::Error> {
-                match value {
-                    #try_from_i32_match_arms
-                    _ => Err(format!("Invalid value for {}: {}", stringify!(#enum_ident), value)),
-                }
+                <Self as MenuParam>::from_menu_index(value)
+                    .ok_or_else(|| format!("Invalid value for {}: {}", stringify!(#enum_ident), value))
+            }
+        }
+
+        impl #enum_ident {
+            /// The variant with a discriminant, which may differ from its
+            /// position in the menu.
+            pub fn from_discriminant(value: i32) -> Option<Self> {
+                #(
+                    if value == Self::#variant_idents as i32 {
+                        return Some(Self::#variant_idents);
+                    }
+                )*
+                None
             }
         }
 
+        #serde_code
+
         impl Param for #enum_ident {
             fn register(&self, options: ParamOptions, parameter_manager: &mut ParameterManager) {
-                let param: StringParameter = options.into();
-                let names = #enum_ident::names();
-                let labels = #enum_ident::labels();
+                let mut param: StringParameter = options.into();
+                let names = <Self as MenuParam>::names();
+                let labels = <Self as MenuParam>::labels();
+                param.default_value = names[MenuParam::menu_index(self) as usize].clone();
                 parameter_manager.append_menu(param, &names, &labels);
             }
 
-            fn update(&mut self, name: &str, inputs: &ParamInputs) {
-                let idx = inputs.get_int(name, 0);
-                let value = #enum_ident::try_from(idx).unwrap();
+            fn update(&mut self, name: &'static std::ffi::CStr, inputs: &ParamInputs) -> bool {
+                // Keep the current value if TouchDesigner reports an index
+                // outside of the menu, e.g. one set by an expression
+                let Some(value) = <Self as MenuParam>::from_menu_index(inputs.get_int(name, 0)) else {
+                    return false;
+                };
+                let changed = std::mem::discriminant(self) != std::mem::discriminant(&value);
                 *self = value;
+                changed
             }
         }
-    };
-
-    output.into()
-}
-
-fn try_from_i32_match_arms(variants: &[Variant]) -> proc_macro2::TokenStream {
-    let arms = variants.iter().enumerate().map(|(idx, variant)| {
-        let ident = &variant.ident;
-        let index = idx as i32;
-        quote! {
-            #index => Ok(Self::#ident),
-        }
-    });
-
-    quote! {
-        #( #arms )*
-    }
+    })
 }
 
 fn camel_case_to_words(s: &str) -> String {
@@ -105,140 +192,906 @@ fn camel_case_to_words(s: &str) -> String {
     words
 }
 
-#[proc_macro_derive(Params, attributes(param))]
+/// An argument of a derive attribute, e.g. `flatten`, `label = "Speed"`,
+/// `min = -1.0`, `options = ["a", "b"]` or `default(0.0, 1.0, 0.0)`.
+enum AttrArg {
+    Flag(syn::Ident),
+    Value(syn::Ident, Box<syn::Expr>),
+    List(syn::Ident, Punctuated<syn::Expr, Token![,]>),
+}
+
+impl AttrArg {
+    fn key(&self) -> &syn::Ident {
+        match self {
+            AttrArg::Flag(key) | AttrArg::Value(key, _) | AttrArg::List(key, _) => key,
+        }
+    }
+}
+
+impl Parse for AttrArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key: syn::Ident = input.parse()?;
+        if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            Ok(AttrArg::Value(key, Box::new(input.parse()?)))
+        } else if input.peek(token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            Ok(AttrArg::List(key, Punctuated::parse_terminated(&content)?))
+        } else {
+            Ok(AttrArg::Flag(key))
+        }
+    }
+}
+
+fn parse_args(attr: &syn::Attribute) -> syn::Result<Punctuated<AttrArg, Token![,]>> {
+    attr.parse_args_with(Punctuated::parse_terminated)
+}
+
+const PARAM_ARGS: &str = "label, page, min, max, min_slider, max_slider, clamp, default, \
+    style, options, header, enable_if, menu_provider, on_pulse or prefix";
+
+/// The arguments of the `#[param(...)]` attributes of a field.
+#[derive(Default)]
+struct FieldAttrs {
+    label: Option<String>,
+    page: Option<String>,
+    min: Option<Vec<f64>>,
+    max: Option<Vec<f64>>,
+    min_slider: Option<Vec<f64>>,
+    max_slider: Option<Vec<f64>>,
+    clamp: Option<bool>,
+    default: Option<Vec<f64>>,
+    style: Option<proc_macro2::Ident>,
+    options: Option<Vec<String>>,
+    flatten: bool,
+    prefix: Option<String>,
+    enable_if: Option<syn::Expr>,
+    skip_preset: bool,
+    menu_provider: Option<syn::Expr>,
+    on_pulse: Option<syn::Ident>,
+    header: Option<String>,
+    separator: bool,
+}
+
+impl FieldAttrs {
+    fn parse(field: &syn::Field, field_names: &[syn::Ident]) -> syn::Result<Self> {
+        let mut attrs = FieldAttrs::default();
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("param"))
+        {
+            for arg in parse_args(attr)? {
+                match arg {
+                    AttrArg::Flag(key) if key == "flatten" => attrs.flatten = true,
+                    AttrArg::Flag(key) if key == "skip_preset" => attrs.skip_preset = true,
+                    AttrArg::Flag(key) if key == "separator" => attrs.separator = true,
+                    AttrArg::Flag(key) => {
+                        return Err(syn::Error::new_spanned(
+                            &key,
+                            format!(
+                                "unknown `param` flag `{key}`, expected `flatten`, `skip_preset` or `separator`"
+                            ),
+                        ))
+                    }
+                    AttrArg::Value(key, value) => match key.to_string().as_str() {
+                        "label" => attrs.label = Some(expr_lit_str(&value)?.value()),
+                        "page" => attrs.page = Some(expr_lit_str(&value)?.value()),
+                        "prefix" => attrs.prefix = Some(expr_lit_str(&value)?.value()),
+                        "clamp" => attrs.clamp = Some(expr_bool(&value)?),
+                        "style" => attrs.style = Some(param_style(expr_lit_str(&value)?)?),
+                        "options" => attrs.options = Some(expr_str_list(&value)?),
+                        "enable_if" => {
+                            attrs.enable_if =
+                                Some(enable_if_expr(expr_lit_str(&value)?, field_names)?)
+                        }
+                        "menu_provider" => {
+                            attrs.menu_provider = Some(expr_lit_str(&value)?.parse()?)
+                        }
+                        "on_pulse" => attrs.on_pulse = Some(expr_lit_str(&value)?.parse()?),
+                        "header" => attrs.header = Some(expr_lit_str(&value)?.value()),
+                        _ => match attrs.components_mut(&key) {
+                            Some(components) => *components = Some(vec![expr_f64(&value)?]),
+                            None => {
+                                return Err(syn::Error::new_spanned(
+                                    &key,
+                                    format!(
+                                        "unknown `param` argument `{key}`, expected one of {PARAM_ARGS}"
+                                    ),
+                                ))
+                            }
+                        },
+                    },
+                    // Per component values, e.g. `default(0.0, 1.0, 0.0)`
+                    AttrArg::List(key, values) => {
+                        let values = values.iter().map(expr_f64).collect::<syn::Result<_>>()?;
+                        match attrs.components_mut(&key) {
+                            Some(components) => *components = Some(values),
+                            None => {
+                                return Err(syn::Error::new_spanned(
+                                    &key,
+                                    format!(
+                                        "`{key}` doesn't take a list, only min, max, min_slider, max_slider and default do"
+                                    ),
+                                ))
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        if attrs.flatten && (attrs.enable_if.is_some() || attrs.on_pulse.is_some()) {
+            return Err(syn::Error::new_spanned(
+                &field.ident,
+                "`enable_if` and `on_pulse` are not supported on flattened fields",
+            ));
+        }
+        Ok(attrs)
+    }
+
+    fn components_mut(&mut self, key: &syn::Ident) -> Option<&mut Option<Vec<f64>>> {
+        match key.to_string().as_str() {
+            "min" => Some(&mut self.min),
+            "max" => Some(&mut self.max),
+            "min_slider" => Some(&mut self.min_slider),
+            "max_slider" => Some(&mut self.max_slider),
+            "default" => Some(&mut self.default),
+            _ => None,
+        }
+    }
+}
+
+#[proc_macro_derive(Params, attributes(param, params))]
 pub fn params_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     impl_params(&input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
 }
 
-fn impl_params(input: &DeriveInput) -> TokenStream {
+fn impl_params(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
     let struct_name = &input.ident;
     let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
 
     let mut register_code = Vec::new();
     let mut update_code = Vec::new(); // Add this line to store update code
+    let mut enable_code = Vec::new();
+    let mut save_code = Vec::new();
+    let mut menu_code = Vec::new();
+    let mut load_code = Vec::new();
+    let mut controls_code = Vec::new();
+    let mut press_code = Vec::new();
+    let mut handler_code = Vec::new();
+    // The types of flattened fields along with the index of their scope, whose
+    // pulse handlers are called in turn
+    let mut nested_handlers = Vec::new();
+    // The scoped names of the parameters and the scopes of flattened structs,
+    // worked out once per scope, see `ScopedNames`
+    let mut scoped_names = Vec::new();
+    let mut nested_scopes = Vec::new();
 
-    if let Data::Struct(data_struct) = &input.data {
-        if let Fields::Named(named_fields) = &data_struct.fields {
-            for field in named_fields.named.iter() {
-                let field_name = field.ident.as_ref().unwrap();
-                let _field_type = &field.ty;
-
-                let mut label = None;
-                let mut page = None;
-                let mut min = None;
-                let mut max = None;
-                let mut min_slider = None;
-                let mut max_slider = None;
-                let mut clamp = None;
-                let mut default = None;
-
-                for attr in &field.attrs {
-                    if attr.path.is_ident("param") {
-                        if let Ok(Meta::List(meta_list)) = attr.parse_meta() {
-                            for nested_meta in meta_list.nested.iter() {
-                                if let NestedMeta::Meta(Meta::NameValue(MetaNameValue {
-                                    path,
-                                    lit,
-                                    ..
-                                })) = nested_meta
-                                {
-                                    if path.is_ident("label") {
-                                        if let Lit::Str(lit_str) = lit {
-                                            label = Some(lit_str.value());
-                                        }
-                                    } else if path.is_ident("page") {
-                                        if let Lit::Str(lit_str) = lit {
-                                            page = Some(lit_str.value());
-                                        }
-                                    } else if path.is_ident("min") {
-                                        if let Lit::Float(lit_float) = lit {
-                                            min = Some(lit_float.base10_parse().unwrap());
-                                        }
-                                    } else if path.is_ident("max") {
-                                        if let Lit::Float(lit_float) = lit {
-                                            max = Some(lit_float.base10_parse().unwrap());
-                                        }
-                                    } else if path.is_ident("min_slider") {
-                                        if let Lit::Float(lit_float) = lit {
-                                            min_slider = Some(lit_float.base10_parse().unwrap());
-                                        }
-                                    } else if path.is_ident("max_slider") {
-                                        if let Lit::Float(lit_float) = lit {
-                                            max_slider = Some(lit_float.base10_parse().unwrap());
-                                        }
-                                    } else if path.is_ident("clamp") {
-                                        if let Lit::Bool(lit_bool) = lit {
-                                            clamp = Some(lit_bool.value);
-                                        }
-                                    } else if path.is_ident("default") {
-                                        if let Lit::Float(lit_float) = lit {
-                                            default = Some(lit_float.base10_parse().unwrap());
-                                        }
-                                        if let Lit::Int(lit_int) = lit {
-                                            default = Some(lit_int.base10_parse().unwrap());
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
+    // `#[params(presets)]` implements `PresetParams`, `#[params(op = "...")]`
+    // implements `PulseHandlers` for the operator and `#[params(pages = [...])]`
+    // orders the pages
+    let mut presets = false;
+    let mut op = None;
+    let mut pages = None;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("params"))
+    {
+        for arg in parse_args(attr)? {
+            match arg {
+                AttrArg::Flag(key) if key == "presets" => presets = true,
+                AttrArg::Value(key, value) if key == "op" => {
+                    op = Some(expr_lit_str(&value)?.parse::<syn::Type>()?)
                 }
+                AttrArg::Value(key, value) if key == "pages" => {
+                    pages = Some(expr_str_list(&value)?)
+                }
+                arg => {
+                    return Err(syn::Error::new_spanned(
+                        arg.key(),
+                        "unknown `params` argument, expected `presets`, `op` or `pages`",
+                    ))
+                }
+            }
+        }
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data_struct) => match &data_struct.fields {
+            Fields::Named(named_fields) => named_fields.named.iter().collect::<Vec<_>>(),
+            Fields::Unit => Vec::new(),
+            Fields::Unnamed(fields) => {
+                return Err(syn::Error::new_spanned(
+                    fields,
+                    "`Params` can't be derived for tuple structs",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                struct_name,
+                "`Params` can only be derived for structs",
+            ))
+        }
+    };
+    let field_names = fields
+        .iter()
+        .filter_map(|field| field.ident.clone())
+        .collect::<Vec<_>>();
 
-                let field_name_upper = format_name(&field_name.to_string());
-                let default_label = format!("{}", field_name);
-                let label = label.unwrap_or(default_label);
-                let default_page = "Custom".to_string();
-                let page = page.unwrap_or(default_page);
-                let min = min.unwrap_or(0.0);
-                let max = max.unwrap_or(1.0);
-                let min_slider = min_slider.unwrap_or(min);
-                let max_slider = max_slider.unwrap_or(max);
-                let clamp = clamp.unwrap_or(false);
-                let default = default.unwrap_or(0.0);
-
-                let register_field_code = quote! {
-                    {
-                        let options = ParamOptions {
-                            name: #field_name_upper.to_string(),
-                            label: #label.to_string(),
-                            page: #page.to_string(),
-                            min: #min,
-                            max: #max,
-                            min_slider: #min_slider,
-                            max_slider: #max_slider,
-                            clamp: #clamp,
-                            default: #default,
-                        };
-                        Param::register(&self.#field_name, options, parameter_manager);
+    for field in fields {
+        let field_name = field.ident.as_ref().unwrap();
+        let FieldAttrs {
+            label,
+            page,
+            min,
+            max,
+            min_slider,
+            max_slider,
+            clamp,
+            default,
+            style,
+            options,
+            flatten,
+            prefix,
+            enable_if,
+            skip_preset,
+            menu_provider,
+            on_pulse,
+            header,
+            separator,
+        } = FieldAttrs::parse(field, &field_names)?;
+        let field_name_upper = format_name(&field_name.to_string());
+
+        // Embed another parameter struct, prefixing its parameter names
+        if flatten {
+            if (header.is_some() || separator) && page.is_none() {
+                return Err(syn::Error::new_spanned(
+                    field_name,
+                    "`header` and `separator` on a flattened field need a `page`",
+                ));
+            }
+            let header_code = header_code(&field_name_upper, header, separator, page.as_deref());
+            let page_order = page.clone();
+            let prefix = prefix.unwrap_or_default();
+            let page = match page {
+                Some(page) => quote! { Some(#page) },
+                None => quote! { None },
+            };
+            let nested = nested_scopes.len();
+            nested_scopes.push(quote! { scope.nested(#prefix, #page) });
+            nested_handlers.push((&field.ty, nested));
+            register_code.push((
+                page_order,
+                quote! {
+                    #header_code
+                    OperatorParams::register_scoped(
+                        &mut self.#field_name,
+                        scoped.nested(#nested),
+                        parameter_manager,
+                    );
+                },
+            ));
+            update_code.push(quote! {
+                changed.extend(OperatorParams::update_scoped(
+                    &mut self.#field_name,
+                    scoped.nested(#nested),
+                    inputs,
+                ));
+            });
+            menu_code.push(quote! {
+                if OperatorParams::build_menu_scoped(
+                    &self.#field_name,
+                    scoped.nested(#nested),
+                    inputs,
+                    menu,
+                ) {
+                    return true;
+                }
+            });
+            press_code.push(quote! {
+                if OperatorParams::press_pulse_scoped(
+                    &mut self.#field_name,
+                    scoped.nested(#nested),
+                    name,
+                ) {
+                    return true;
+                }
+            });
+            if !skip_preset {
+                save_code.push(quote! {
+                    PresetParams::save_scoped(
+                        &self.#field_name,
+                        &scope.nested(#prefix, #page),
+                        preset,
+                    );
+                });
+                load_code.push(quote! {
+                    PresetParams::load_scoped(
+                        &mut self.#field_name,
+                        &scope.nested(#prefix, #page),
+                        preset,
+                        report,
+                    );
+                });
+                controls_code.push(quote! {
+                    if let Some(controls) = PresetParams::preset_controls(&mut self.#field_name) {
+                        return Some(controls);
                     }
-                };
-                register_code.push(register_field_code);
+                });
+            }
+            continue;
+        }
+
+        let default_label = format!("{}", field_name);
+        let label = label.unwrap_or(default_label);
+        let default_page = "Custom".to_string();
+        let page = page.unwrap_or(default_page);
+        let min = components(min, [0.0; 4]);
+        let max = components(max, [1.0; 4]);
+        let min_slider = components(min_slider, min);
+        let max_slider = components(max_slider, max);
+        let clamp = clamp.unwrap_or(false);
+        let default = components(default, [0.0; 4]);
+        let style = style.unwrap_or_else(|| quote::format_ident!("Default"));
+        let options = options.unwrap_or_default();
+        let header_code = header_code(&field_name_upper, header, separator, Some(&page));
 
-                let update_field_code = quote! {
-                    // TODO: Field name should be null terminated
-                    Param::update(&mut self.#field_name, &(#field_name_upper.to_string()), inputs);
+        let register_field_code = quote! {
+            #header_code
+            {
+                let options = ParamOptions {
+                    name: scope.name(#field_name_upper),
+                    label: scope.label(#label),
+                    page: scope.page(#page),
+                    min: [#(#min),*],
+                    max: [#(#max),*],
+                    min_slider: [#(#min_slider),*],
+                    max_slider: [#(#max_slider),*],
+                    clamp: #clamp,
+                    default: [#(#default),*],
+                    style: ParamStyle::#style,
+                    options: vec![#(String::from(#options)),*],
                 };
+                Param::register(&self.#field_name, options, parameter_manager);
+            }
+        };
+        register_code.push((Some(page), register_field_code));
+
+        let c_name = proc_macro2::Literal::c_string(
+            &std::ffi::CString::new(field_name_upper.clone()).unwrap(),
+        );
+        let index = scoped_names.len();
+        scoped_names.push(quote! { scope.c_name(#c_name) });
+        let update_field_code = quote! {
+            {
+                let name = scoped.name(#index);
+                if Param::update(&mut self.#field_name, name, inputs) {
+                    changed.insert(name);
+                }
+            }
+        };
+
+        update_code.push(update_field_code);
+
+        if !skip_preset {
+            if presets && matches!(field.ty, syn::Type::Ptr(_)) {
+                return Err(syn::Error::new_spanned(
+                    &field.ty,
+                    "pointers such as Python objects can't be stored in presets, mark the field `#[param(skip_preset)]`",
+                ));
+            }
+            save_code.push(quote! {
+                preset.insert_param(scope.name(#field_name_upper), &self.#field_name);
+            });
+            load_code.push(quote! {
+                report.load(preset, scope.name(#field_name_upper), &mut self.#field_name);
+            });
+        }
+
+        if let Some(menu_provider) = menu_provider {
+            menu_code.push(quote! {
+                if menu.param_name().as_bytes() == scoped.name(#index).to_bytes() {
+                    MenuProvider::build(&#menu_provider, scope, inputs, menu);
+                    return true;
+                }
+            });
+        }
 
-                update_code.push(update_field_code);
+        press_code.push(quote! {
+            if name.as_bytes() == scoped.name(#index).to_bytes() {
+                Param::press(&mut self.#field_name);
+                return true;
             }
+        });
+
+        if let Some(handler) = on_pulse {
+            let Some(op_type) = &op else {
+                return Err(syn::Error::new_spanned(
+                    handler,
+                    "`on_pulse` needs the operator type, e.g. `#[params(op = \"MyOp\")]`",
+                ));
+            };
+            handler_code.push(quote! {
+                if name.as_bytes() == scoped.name(#index).to_bytes() {
+                    <#op_type>::#handler(op);
+                    return true;
+                }
+            });
+        }
+
+        if let Some(enable_if) = enable_if {
+            enable_code.push(quote! {
+                inputs.enable_param(scoped.name(#index), #enable_if);
+            });
         }
     }
 
+    if let Some(pages) = pages {
+        register_code = order_by_page(&pages, register_code);
+    }
+    let register_code = register_code.into_iter().map(|(_, code)| code);
     let register_code = quote! { #(#register_code)* };
 
+    let preset_impl = if presets {
+        quote! {
+            impl #impl_generics PresetParams for #struct_name #ty_generics #where_clause {
+                fn save_scoped(&self, scope: &ParamScope, preset: &mut Preset) {
+                    #(#save_code)*
+                }
+
+                fn load_scoped(
+                    &mut self,
+                    scope: &ParamScope,
+                    preset: &Preset,
+                    report: &mut PresetReport,
+                ) {
+                    #(#load_code)*
+                }
+
+                fn preset_controls(&mut self) -> Option<&mut PresetControls> {
+                    #(#controls_code)*
+                    None
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+    let presets_fn = if presets {
+        quote! {
+            fn presets(&mut self) -> Option<&mut dyn PresetParams> {
+                Some(self)
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // Structs without an operator type call the handlers of their flattened
+    // fields for any operator those have handlers for
+    let handler_op = match &op {
+        Some(op) => quote! { #op },
+        None => quote! { __O },
+    };
+    let mut handler_generics = input.generics.clone();
+    if op.is_none() {
+        handler_generics
+            .params
+            .push(syn::parse_quote! { __O: ?Sized });
+    }
+    let handler_where = handler_generics.make_where_clause();
+    for (ty, nested) in &nested_handlers {
+        handler_where
+            .predicates
+            .push(syn::parse_quote! { #ty: PulseHandlers<#handler_op> });
+        handler_code.push(quote! {
+            if <#ty as PulseHandlers<#handler_op>>::handle_pulse_scoped(
+                op,
+                scoped.nested(#nested),
+                name,
+            ) {
+                return true;
+            }
+        });
+    }
+    let (handler_impl_generics, _, handler_where) = handler_generics.split_for_impl();
+    let handler_lookup = if handler_code.is_empty() {
+        quote! {}
+    } else {
+        quote! { let scoped = scoped_names(scope); }
+    };
+    let handlers_impl = quote! {
+        impl #handler_impl_generics PulseHandlers<#handler_op> for #struct_name #ty_generics #handler_where {
+            fn handle_pulse_scoped(op: &mut #handler_op, scope: &ParamScope, name: &str) -> bool {
+                #handler_lookup
+                #(#handler_code)*
+                false
+            }
+        }
+    };
+
+    // `scoped` is only bound where it is used, so that structs without fields
+    // of some kind don't warn about it
+    let lookup = quote! { let scoped = scoped_names(scope); };
+    let register_lookup = if nested_scopes.is_empty() {
+        quote! { scoped_names(scope); }
+    } else {
+        lookup.clone()
+    };
+    let update_lookup = if scoped_names.is_empty() && nested_scopes.is_empty() {
+        quote! {}
+    } else {
+        lookup.clone()
+    };
+    let menu_lookup = if menu_code.is_empty() {
+        quote! {}
+    } else {
+        lookup.clone()
+    };
+    let press_lookup = if press_code.is_empty() {
+        quote! {}
+    } else {
+        lookup.clone()
+    };
+
     let gen = quote! {
-        impl #impl_generics OperatorParams for #struct_name #ty_generics #where_clause {
-            fn register(&mut self, parameter_manager: &mut ParameterManager) {
-                #register_code
+        const _: () = {
+            static SCOPED_NAMES: ScopedNames = ScopedNames::new();
+
+            /// The names of the parameters and the scopes of the flattened
+            /// fields, worked out the first time a scope is registered.
+            fn scoped_names(scope: &ParamScope) -> &'static ScopedEntry {
+                SCOPED_NAMES.get(scope, || {
+                    (
+                        vec![#(#scoped_names),*],
+                        vec![#(#nested_scopes),*],
+                    )
+                })
+            }
+
+            impl #impl_generics OperatorParams for #struct_name #ty_generics #where_clause {
+                fn register_scoped(&mut self, scope: &ParamScope, parameter_manager: &mut ParameterManager) {
+                    #register_lookup
+                    #register_code
+                }
+
+                fn update_scoped(&mut self, scope: &ParamScope, inputs: &ParamInputs) -> ParamsChanged {
+                    #update_lookup
+                    let mut changed = ParamsChanged::default();
+                    #(#update_code)*
+                    #(#enable_code)*
+                    changed
+                }
+
+                fn build_menu_scoped(
+                    &self,
+                    scope: &ParamScope,
+                    inputs: &MenuInputs,
+                    menu: &mut DynamicMenuInfo,
+                ) -> bool {
+                    #menu_lookup
+                    #(#menu_code)*
+                    false
+                }
+
+                fn press_pulse_scoped(&mut self, scope: &ParamScope, name: &str) -> bool {
+                    #press_lookup
+                    #(#press_code)*
+                    false
+                }
+
+                #presets_fn
+            }
+
+            #preset_impl
+            #handlers_impl
+        };
+    };
+    Ok(gen)
+}
+
+/// Headers and separators appended before a field. A separator is a header
+/// without a label.
+fn header_code(
+    field_name: &str,
+    header: Option<String>,
+    separator: bool,
+    page: Option<&str>,
+) -> proc_macro2::TokenStream {
+    let page = page.unwrap_or("Custom");
+    let separator_name = format!("{field_name}sep");
+    let header_name = format!("{field_name}header");
+    let separator = separator.then(|| {
+        quote! {
+            parameter_manager.append_header(StringParameter {
+                name: scope.name(#separator_name),
+                page: scope.page(#page),
+                ..Default::default()
+            });
+        }
+    });
+    let header = header.map(|header| {
+        quote! {
+            parameter_manager.append_header(StringParameter {
+                name: scope.name(#header_name),
+                label: scope.label(#header),
+                page: scope.page(#page),
+                ..Default::default()
+            });
+        }
+    });
+    quote! {
+        #separator
+        #header
+    }
+}
+
+/// Sort registrations by page, in the order of `pages` and then in the order
+/// the other pages first appear, as TouchDesigner orders pages by their first
+/// parameter. A flattened struct without a page is a group of its own.
+fn order_by_page(
+    pages: &[String],
+    register_code: Vec<(Option<String>, proc_macro2::TokenStream)>,
+) -> Vec<(Option<String>, proc_macro2::TokenStream)> {
+    let mut order = pages.iter().cloned().map(Some).collect::<Vec<_>>();
+    let mut ranked = Vec::new();
+    for (page, code) in register_code {
+        // Each flattened struct without a page is its own group
+        let rank = page.as_ref().and(order.iter().position(|p| *p == page));
+        let rank = rank.unwrap_or_else(|| {
+            order.push(page.clone());
+            order.len() - 1
+        });
+        ranked.push((rank, (page, code)));
+    }
+    ranked.sort_by_key(|(rank, _)| *rank);
+    ranked.into_iter().map(|(_, entry)| entry).collect()
+}
+
+/// Parse an `enable_if` condition, where fields of the struct are referred to
+/// by name, e.g. `apply_scale` or `mode == Mode::Advanced`.
+fn enable_if_expr(condition: &LitStr, field_names: &[syn::Ident]) -> syn::Result<syn::Expr> {
+    struct FieldsToSelf<'a>(&'a [syn::Ident]);
+
+    impl VisitMut for FieldsToSelf<'_> {
+        fn visit_expr_mut(&mut self, expr: &mut syn::Expr) {
+            if let syn::Expr::Path(path) = expr {
+                if path.qself.is_none() {
+                    if let Some(ident) = path.path.get_ident() {
+                        if self.0.contains(ident) {
+                            *expr = syn::parse_quote! { self.#ident };
+                            return;
+                        }
+                    }
+                }
+            }
+            syn::visit_mut::visit_expr_mut(self, expr);
+        }
+    }
+
+    let mut expr = condition.parse::<syn::Expr>()?;
+    FieldsToSelf(field_names).visit_expr_mut(&mut expr);
+    Ok(expr)
+}
+
+#[proc_macro_derive(InfoChop, attributes(info))]
+pub fn info_chop_derive(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    impl_info_chop(&input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+fn impl_info_chop(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let struct_name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let fields = info_fields(input, "InfoChop")?;
+    let size = fields.len();
+    let indexes = 0..size;
+    let idents = fields.iter().map(|(ident, _)| ident);
+    let names = fields.iter().map(|(_, name)| name);
+
+    Ok(quote! {
+        impl #impl_generics InfoChop for #struct_name #ty_generics #where_clause {
+            fn size(&self) -> usize {
+                #size
+            }
+
+            fn channel(&self, index: usize) -> (String, f32) {
+                match index {
+                    #(#indexes => (#names.to_string(), InfoChopValue::info_value(&self.#idents)),)*
+                    _ => panic!("Invalid channel index {index}"),
+                }
+            }
+        }
+    })
+}
+
+#[proc_macro_derive(InfoDat, attributes(info))]
+pub fn info_dat_derive(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    impl_info_dat(&input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+fn impl_info_dat(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let struct_name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let fields = info_fields(input, "InfoDat")?;
+    let cols = fields.len() as u32;
+    let indexes = 0..fields.len();
+    let idents = fields.iter().map(|(ident, _)| ident);
+    let names = fields.iter().map(|(_, name)| name);
+
+    Ok(quote! {
+        impl #impl_generics InfoDatRow for #struct_name #ty_generics #where_clause {
+            fn columns() -> &'static [&'static str] {
+                &[#(#names),*]
+            }
+
+            fn cell(&self, column: usize) -> String {
+                match column {
+                    #(#indexes => self.#idents.to_string(),)*
+                    _ => panic!("Invalid column index {column}"),
+                }
+            }
+        }
+
+        // A header row naming the fields, then a row of values
+        impl #impl_generics InfoDat for #struct_name #ty_generics #where_clause {
+            fn size(&self) -> (u32, u32) {
+                (2, #cols)
+            }
+
+            fn entry(&self, index: usize, entry_index: usize) -> String {
+                match index {
+                    0 => <Self as InfoDatRow>::columns()[entry_index].to_string(),
+                    _ => InfoDatRow::cell(self, entry_index),
+                }
             }
+        }
+    })
+}
+
+/// The fields of a struct deriving `InfoChop` or `InfoDat` with the names they
+/// are published under, leaving out those marked `#[info(skip)]`.
+fn info_fields(input: &DeriveInput, derive: &str) -> syn::Result<Vec<(syn::Ident, String)>> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    format!("`{derive}` can only be derived for structs with named fields"),
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                format!("`{derive}` can only be derived for structs"),
+            ))
+        }
+    };
 
-            fn update(&mut self, inputs: &ParamInputs) {
-                #(#update_code)*
+    let mut info_fields = Vec::new();
+    for field in &fields.named {
+        let ident = field.ident.clone().unwrap();
+        let mut name = ident.to_string();
+        let mut skip = false;
+        for attr in field.attrs.iter().filter(|attr| attr.path.is_ident("info")) {
+            for arg in parse_args(attr)? {
+                match arg {
+                    AttrArg::Flag(key) if key == "skip" => skip = true,
+                    AttrArg::Value(key, value) if key == "name" => {
+                        name = expr_lit_str(&value)?.value()
+                    }
+                    arg => {
+                        return Err(syn::Error::new_spanned(
+                            arg.key(),
+                            format!(
+                                "unknown `info` argument `{}`, expected `name` or `skip`",
+                                arg.key()
+                            ),
+                        ))
+                    }
+                }
             }
         }
+        if !skip {
+            info_fields.push((ident, name));
+        }
+    }
+    Ok(info_fields)
+}
+
+fn expr_lit_str(expr: &syn::Expr) -> syn::Result<&LitStr> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: Lit::Str(lit_str),
+            ..
+        }) => Ok(lit_str),
+        _ => Err(syn::Error::new_spanned(expr, "expected a string literal")),
+    }
+}
+
+fn expr_str_list(expr: &syn::Expr) -> syn::Result<Vec<String>> {
+    match expr {
+        syn::Expr::Array(array) => array
+            .elems
+            .iter()
+            .map(|elem| expr_lit_str(elem).map(LitStr::value))
+            .collect(),
+        _ => Err(syn::Error::new_spanned(
+            expr,
+            "expected a list of strings, e.g. `[\"a\", \"b\"]`",
+        )),
+    }
+}
+
+fn expr_bool(expr: &syn::Expr) -> syn::Result<bool> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: Lit::Bool(lit_bool),
+            ..
+        }) => Ok(lit_bool.value),
+        _ => Err(syn::Error::new_spanned(expr, "expected `true` or `false`")),
+    }
+}
+
+fn expr_f64(expr: &syn::Expr) -> syn::Result<f64> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: Lit::Float(lit_float),
+            ..
+        }) => lit_float.base10_parse(),
+        syn::Expr::Lit(syn::ExprLit {
+            lit: Lit::Int(lit_int),
+            ..
+        }) => lit_int.base10_parse(),
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) => expr_f64(expr).map(|value| -value),
+        _ => Err(syn::Error::new(expr.span(), "expected a number")),
+    }
+}
+
+/// Values for each of the four components, repeating the last one given.
+fn components(values: Option<Vec<f64>>, fallback: [f64; 4]) -> [f64; 4] {
+    match values {
+        Some(values) if !values.is_empty() => {
+            std::array::from_fn(|i| values[i.min(values.len() - 1)])
+        }
+        _ => fallback,
+    }
+}
+
+fn param_style(style: &LitStr) -> syn::Result<proc_macro2::Ident> {
+    let variant = match style.value().as_str() {
+        "xy" => "Xy",
+        "xyz" => "Xyz",
+        "uv" => "Uv",
+        "uvw" => "Uvw",
+        "wh" => "Wh",
+        "rgb" => "Rgb",
+        "rgba" => "Rgba",
+        _ => {
+            return Err(syn::Error::new_spanned(
+                style,
+                "unknown param style, expected one of xy, xyz, uv, uvw, wh, rgb or rgba",
+            ))
+        }
     };
-    gen.into()
+    Ok(quote::format_ident!("{}", variant))
 }
 
 fn format_name(name: &str) -> String {
@@ -257,3 +1110,73 @@ fn capitalize_first(s: &str) -> String {
 fn remove_underscores(s: &str) -> String {
     s.replace('_', "")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registered(
+        entries: &[(Option<&str>, &str)],
+    ) -> Vec<(Option<String>, proc_macro2::TokenStream)> {
+        entries
+            .iter()
+            .map(|(page, field)| {
+                let field = quote::format_ident!("{}", field);
+                (page.map(String::from), quote! { #field })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_order_by_page() {
+        let pages = ["Main".to_string(), "Advanced".to_string()];
+        let entries = registered(&[
+            (Some("Advanced"), "gain"),
+            (Some("Extra"), "seed"),
+            (None, "net"),
+            (Some("Main"), "speed"),
+            (Some("Advanced"), "mix"),
+            (Some("Extra"), "jitter"),
+            (None, "proxy"),
+            (Some("Main"), "scale"),
+        ]);
+        let order = order_by_page(&pages, entries)
+            .into_iter()
+            .map(|(page, code)| (page, code.to_string()))
+            .collect::<Vec<_>>();
+        let expected = registered(&[
+            (Some("Main"), "speed"),
+            (Some("Main"), "scale"),
+            (Some("Advanced"), "gain"),
+            (Some("Advanced"), "mix"),
+            (Some("Extra"), "seed"),
+            (Some("Extra"), "jitter"),
+            (None, "net"),
+            (None, "proxy"),
+        ])
+        .into_iter()
+        .map(|(page, code)| (page, code.to_string()))
+        .collect::<Vec<_>>();
+        assert_eq!(order, expected);
+    }
+
+    #[test]
+    fn test_header_code() {
+        assert!(header_code("Scale", None, false, None).is_empty());
+
+        let code =
+            header_code("Scale", Some("Transform".to_string()), true, Some("Main")).to_string();
+        let separator = code.find("\"Scalesep\"").unwrap();
+        let header = code.find("\"Scaleheader\"").unwrap();
+        assert!(separator < header);
+        assert_eq!(code.matches("append_header").count(), 2);
+        assert_eq!(code.matches("\"Main\"").count(), 2);
+        assert!(code[header..].contains("\"Transform\""));
+        assert!(!code[..header].contains("\"Transform\""));
+
+        let code = header_code("Scale", None, true, None).to_string();
+        assert!(code.contains("\"Scalesep\""));
+        assert!(code.contains("\"Custom\""));
+        assert!(!code.contains("label"));
+    }
+}
@@ -0,0 +1,57 @@
+#![allow(unused)]
+
+use td_rs_base::*;
+use td_rs_derive::*;
+
+#[derive(Params)]
+struct UnknownArgument {
+    #[param(lable = "Speed")]
+    speed: f32,
+}
+
+#[derive(Params)]
+struct WrongValue {
+    #[param(label = 1.0)]
+    speed: f32,
+}
+
+#[derive(Params)]
+#[params(presets)]
+struct StoredPointer {
+    object: *mut u8,
+}
+
+#[derive(Param)]
+struct NotAnEnum {
+    value: f32,
+}
+
+#[derive(Param)]
+enum WithFields {
+    Value(f32),
+}
+
+#[derive(Param)]
+enum UnknownMenuArgument {
+    #[menu(title = "A")]
+    A,
+}
+
+#[derive(Param)]
+#[menu(serde = false)]
+enum UnknownEnumArgument {
+    A,
+}
+
+#[derive(InfoChop)]
+struct UnknownInfoArgument {
+    #[info(label = "Cooks")]
+    cooks: u32,
+}
+
+#[derive(InfoDat)]
+enum InfoNotAStruct {
+    A,
+}
+
+fn main() {}
@@ -6,23 +6,111 @@ use td_rs_derive::*;
 #[derive(Param)]
 enum TestEnum {
     Hi,
-    Hello,
+    #[menu(name = "hello", label = "Hello!")]
+    Hello = 4,
     Goodbye,
 }
 
+#[derive(Param, serde::Serialize, serde::Deserialize)]
+#[serde(crate = "td_rs_base::serde")]
+#[menu(skip_serde)]
+enum Quality {
+    Low,
+    High,
+}
+
 #[derive(Params)]
+#[params(pages = ["Custom", "Advanced"])]
 struct TestParameter {
-    #[param(label = "Hi")]
+    #[param(label = "Hi", min = -1.0)]
     float2: f32,
     float3: f64,
     int: i16,
     int2: u32,
+    #[param(page = "Advanced", header = "Limits")]
     int3: i64,
     hi: String,
+    #[param(page = "Advanced", separator)]
     menu: TestEnum,
+    object: ObjectParam,
+    #[param(style = "rgb", default(1.0, 0.5, 0.0), max(1.0), header = "Vectors")]
+    color: [f32; 3],
+    offset: [i32; 2],
+    #[param(options = ["h264", "hevc", "prores"])]
+    codec: StringMenuParam,
     // rgb: rgb::RGB<u8>,
 }
 
+#[derive(Params, Default)]
+#[params(op = "Counter")]
+struct CounterParams {
+    #[param(on_pulse = "reset")]
+    reset: Pulse,
+    hold: Momentary,
+    #[param(flatten, prefix = "Net")]
+    net: NetParams,
+}
+
+#[derive(Params, Default)]
+#[params(op = "Counter")]
+struct NetParams {
+    #[param(on_pulse = "reconnect")]
+    reconnect: Pulse,
+    #[param(flatten, prefix = "Proxy")]
+    proxy: ProxyParams,
+}
+
+#[derive(Params, Default)]
+struct ProxyParams {
+    clear: Pulse,
+}
+
+#[derive(Params)]
+#[params(presets)]
+struct ShowParams {
+    scale: f32,
+    menu: TestEnum,
+    chop: ChopParam,
+    #[param(skip_preset)]
+    note: String,
+    #[param(flatten, prefix = "Net")]
+    net: AddressParams,
+    #[param(flatten, page = "Presets")]
+    presets: PresetControls,
+}
+
+#[derive(Params, Default)]
+#[params(presets)]
+struct AddressParams {
+    host: String,
+    port: u16,
+}
+
+#[derive(Default)]
+struct Counter {
+    count: u32,
+    reconnects: u32,
+}
+
+impl Counter {
+    fn reset(&mut self) {
+        self.count = 0;
+    }
+
+    fn reconnect(&mut self) {
+        self.reconnects += 1;
+    }
+}
+
+#[derive(InfoChop, InfoDat)]
+struct TestInfo {
+    cooks: u32,
+    #[info(name = "cook_ms")]
+    cook_time: f64,
+    #[info(skip)]
+    name: String,
+}
+
 fn main() {
     let mut param = TestParameter {
         // Initialize fields
@@ -33,14 +121,90 @@ fn main() {
         int3: 0,
         hi: "".to_string(),
         menu: TestEnum::Hi,
+        object: ObjectParam::default(),
+        color: [1.0, 0.5, 0.0],
+        offset: [0, 0],
+        codec: StringMenuParam::default().with_value("h264"),
     };
 
     assert_eq!(
         TestEnum::names(),
         [
             String::from("Hi"),
-            String::from("Hello"),
+            String::from("hello"),
             String::from("Goodbye")
         ]
     );
+    assert_eq!(TestEnum::try_from(2).ok().map(|e| e.menu_index()), Some(2));
+    assert!(TestEnum::try_from(5).is_err());
+    assert_eq!(
+        TestEnum::from_discriminant(5).map(|e| e.menu_index()),
+        Some(2)
+    );
+    assert!(TestEnum::from_discriminant(2).is_none());
+
+    let info = TestInfo {
+        cooks: 2,
+        cook_time: 0.5,
+        name: String::new(),
+    };
+    assert_eq!(InfoChop::channel(&info, 1), ("cook_ms".to_string(), 0.5));
+    assert_eq!(InfoDat::size(&info), (2, 2));
+
+    let mut params = CounterParams::default();
+    assert!(params.press_pulse("Reset"));
+    assert!(params.press_pulse("Netreconnect"));
+    assert!(params.press_pulse("Netproxyclear"));
+    assert!(!params.press_pulse("Reconnect"));
+    assert!(!params.press_pulse("Other"));
+    assert!(params.reset.take());
+    assert!(!params.reset.take());
+    assert!(params.net.reconnect.take());
+    assert!(params.net.proxy.clear.take());
+
+    let mut counter = Counter {
+        count: 5,
+        reconnects: 0,
+    };
+    assert!(CounterParams::handle_pulse(&mut counter, "Reset"));
+    assert_eq!(counter.count, 0);
+    assert!(CounterParams::handle_pulse(&mut counter, "Netreconnect"));
+    assert_eq!(counter.reconnects, 1);
+    assert!(!CounterParams::handle_pulse(&mut counter, "Reconnect"));
+    assert!(!CounterParams::handle_pulse(&mut counter, "Netproxyclear"));
+    assert_eq!(counter.reconnects, 1);
+
+    let mut show = ShowParams {
+        scale: 2.0,
+        menu: TestEnum::Hello,
+        chop: ChopParam::default(),
+        note: "kept".to_string(),
+        net: AddressParams {
+            host: "localhost".to_string(),
+            port: 80,
+        },
+        presets: PresetControls::default(),
+    };
+    let scope = ParamScope::default().nested("Show", None);
+    let mut preset = Preset::default();
+    show.save_scoped(&scope, &mut preset);
+    assert_eq!(
+        preset.names().collect::<Vec<_>>(),
+        ["Showmenu", "Shownethost", "Shownetport", "Showscale"]
+    );
+    assert_eq!(preset.get("Showmenu").unwrap(), "hello");
+
+    preset.insert("Showscale", &3.0);
+    preset.insert("Showmenu", "Goodbye");
+    preset.insert("Shownetport", "http");
+    show.note.clear();
+    let mut report = PresetReport::default();
+    show.load_scoped(&scope, &preset, &mut report);
+    assert_eq!(show.scale, 3.0);
+    assert_eq!(show.menu.menu_index(), 2);
+    assert!(show.note.is_empty());
+    assert_eq!(report.loaded, ["Showscale", "Showmenu", "Shownethost"]);
+    assert_eq!(report.invalid[0].0, "Shownetport");
+    assert!(report.missing.is_empty());
+    assert!(show.presets().is_some());
 }
@@ -8,5 +8,5 @@ fn parameter_macro_tests() {
     t.pass("tests/parameter_macro/pass.rs");
 
     // Test case for expected error
-    // t.compile_fail("tests/parameter_macro/fail.rs");
+    t.compile_fail("tests/parameter_macro/fail.rs");
 }
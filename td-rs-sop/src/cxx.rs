@@ -8,7 +8,10 @@ use autocxx::subclass::*;
 use std::ffi::CString;
 
 use std::pin::Pin;
-use td_rs_base::{param::ParameterManager, DynamicMenuInfo, NodeInfo, OperatorInputs};
+use td_rs_base::{
+    catch_cook, catch_new, message_c_string, param::ParameterManager, DynamicMenuInfo, FailedOp,
+    MenuInputs, NodeInfo, OpStatus, OperatorInputs,
+};
 
 include_cpp! {
     #include "SOP_CPlusPlusBase.h"
@@ -53,6 +56,19 @@ extern "C" {
 #[subclass(superclass("RustSopPlugin"))]
 pub struct RustSopPluginImpl {
     inner: Box<dyn Sop>,
+    status: OpStatus,
+}
+
+impl RustSopPluginImpl {
+    /// Wrap an operator without creating its C++ peer, e.g. to drive it from a
+    /// test host.
+    pub fn new(inner: Box<dyn Sop>) -> Self {
+        Self {
+            inner,
+            status: OpStatus::default(),
+            cpp_peer: CppSubclassCppPeerHolder::Empty,
+        }
+    }
 }
 
 // SAFETY: This can only be used with pointers returned from getNodeInstance() and
@@ -77,12 +93,13 @@ impl AsPlugin for RustSopPluginImplCpp {
 #[no_mangle]
 extern "C" fn sop_new(info: &'static OP_NodeInfo) -> *mut RustSopPluginImplCpp {
     unsafe {
-        let info = NodeInfo::new(info);
-        RustSopPluginImpl::new_cpp_owned(RustSopPluginImpl {
-            inner: sop_new_impl(info),
-            cpp_peer: CppSubclassCppPeerHolder::Empty,
+        let inner = catch_new(|| {
+            let mut sop = sop_new_impl(NodeInfo::new(info));
+            sop.attach_node(&NodeInfo::new(info));
+            sop
         })
-        .into_raw()
+        .unwrap_or_else(|message| Box::new(FailedOp::new(message)));
+        RustSopPluginImpl::new_cpp_owned(RustSopPluginImpl::new(inner)).into_raw()
     }
 }
 
@@ -98,14 +115,18 @@ impl RustSopPlugin_methods for RustSopPluginImpl {
     fn getGeneralInfo(&mut self, mut info: Pin<&mut SOP_GeneralInfo>, inputs: &OP_Inputs) {
         #[cfg(feature = "tracing")]
         let _span = { tracing_base::trace_span!("getGeneralInfo").entered() };
+        // A new cook starts with a clean slate
+        self.status.clear();
         let input = OperatorInputs::new(inputs);
-        if let Some(params) = self.inner.params_mut() {
-            params.update(&input.params());
+        let gen_info = catch_cook(self.inner.as_mut(), &mut self.status, |sop| {
+            sop.update_params(&input.params());
+            sop.try_general_info(&input)
+        });
+        if let Ok(gen_info) = gen_info {
+            info.cookEveryFrame = gen_info.cook_every_frame;
+            info.cookEveryFrameIfAsked = gen_info.cook_every_frame_if_asked;
+            info.directToGPU = gen_info.direct_to_gpu;
         }
-        let gen_info = self.inner.general_info(&input);
-        info.cookEveryFrame = gen_info.cook_every_frame;
-        info.cookEveryFrameIfAsked = gen_info.cook_every_frame_if_asked;
-        info.directToGPU = gen_info.direct_to_gpu;
         info.winding = SOP_Winding::CCW;
     }
 
@@ -114,10 +135,11 @@ impl RustSopPlugin_methods for RustSopPluginImpl {
         let _span = { tracing_base::trace_span!("execute").entered() };
         let input = OperatorInputs::new(inputs);
         let mut output = SopOutput::new(outputs);
-        if let Some(params) = self.inner.params_mut() {
-            params.update(&input.params());
-        }
-        self.inner.execute(&mut output, &input);
+        // See the known limitation of `Sop::try_execute`
+        let _ = catch_cook(self.inner.as_mut(), &mut self.status, |sop| {
+            sop.update_params(&input.params());
+            sop.try_execute(&mut output, &input)
+        });
     }
 
     fn executeVBO(&mut self, output: Pin<&mut SOP_VBOOutput>, inputs: &OP_Inputs) {
@@ -125,28 +147,35 @@ impl RustSopPlugin_methods for RustSopPluginImpl {
         let _span = { tracing_base::trace_span!("executeVBO").entered() };
         let input = OperatorInputs::new(inputs);
         let output = SopVboOutput::<Unalloc>::new(output);
-        self.inner.execute_vbo(output, &input);
+        let _ = catch_cook(self.inner.as_mut(), &mut self.status, |sop| {
+            sop.try_execute_vbo(output, &input)
+        });
     }
 
     fn getNumInfoCHOPChans(&mut self) -> i32 {
         #[cfg(feature = "tracing")]
         let _span = { tracing_base::trace_span!("getNumInfoCHOPChans").entered() };
-        if let Some(info_chop) = self.inner.info_chop() {
-            info_chop.size() as i32
-        } else {
-            0
-        }
+        catch_cook(self.inner.as_mut(), &mut self.status, |sop| {
+            Ok(sop
+                .info_chop()
+                .map_or(0, |info_chop| info_chop.size() as i32))
+        })
+        .unwrap_or(0)
     }
 
     fn getInfoCHOPChan(&mut self, index: i32, name: Pin<&mut OP_String>, mut value: Pin<&mut f32>) {
         #[cfg(feature = "tracing")]
         let _span = { tracing_base::trace_span!("getInfoCHOPChan").entered() };
-        if let Some(info_chop) = self.inner.info_chop() {
+        let chan = catch_cook(self.inner.as_mut(), &mut self.status, |sop| {
+            let Some(info_chop) = sop.info_chop() else {
+                return Ok(None);
+            };
             let (info_name, info_value) = info_chop.channel(index as usize);
+            Ok(Some((CString::new(info_name)?, info_value)))
+        });
+        if let Ok(Some((info_name, info_value))) = chan {
             unsafe {
-                let new_string = CString::new(info_name.as_str()).unwrap();
-                let new_string_ptr = new_string.as_ptr();
-                name.setString(new_string_ptr);
+                name.setString(info_name.as_ptr());
             }
             value.set(info_value);
         }
@@ -155,8 +184,10 @@ impl RustSopPlugin_methods for RustSopPluginImpl {
     fn getInfoDATSize(&mut self, mut info: Pin<&mut OP_InfoDATSize>) -> bool {
         #[cfg(feature = "tracing")]
         let _span = { tracing_base::trace_span!("getInfoDATSize").entered() };
-        if let Some(info_dat) = self.inner.info_dat() {
-            let (rows, cols) = info_dat.size();
+        let size = catch_cook(self.inner.as_mut(), &mut self.status, |sop| {
+            Ok(sop.info_dat().map(|info_dat| info_dat.size()))
+        });
+        if let Ok(Some((rows, cols))) = size {
             info.rows = rows as i32;
             info.cols = cols as i32;
             true
@@ -168,58 +199,74 @@ impl RustSopPlugin_methods for RustSopPluginImpl {
     fn getInfoDATEntry(&mut self, index: i32, entryIndex: i32, entry: Pin<&mut OP_String>) {
         #[cfg(feature = "tracing")]
         let _span = { tracing_base::trace_span!("getInfoDATEntry").entered() };
-        if let Some(info_dat) = self.inner.info_dat() {
+        let entry_str = catch_cook(self.inner.as_mut(), &mut self.status, |sop| {
+            let Some(info_dat) = sop.info_dat() else {
+                return Ok(None);
+            };
             let entry_str = info_dat.entry(index as usize, entryIndex as usize);
             if entry_str.is_empty() {
-                return;
+                return Ok(None);
             }
+            Ok(Some(CString::new(entry_str)?))
+        });
+        if let Ok(Some(entry_str)) = entry_str {
             unsafe {
-                let new_string = CString::new(entry_str.as_str()).unwrap();
-                let new_string_ptr = new_string.as_ptr();
-                entry.setString(new_string_ptr);
+                entry.setString(entry_str.as_ptr());
             }
         }
     }
 
     fn getWarningString(&mut self, warning: Pin<&mut OP_String>) {
-        unsafe {
-            let new_string = CString::new(self.inner.warning()).unwrap();
-            let new_string_ptr = new_string.as_ptr();
-            warning.setString(new_string_ptr);
+        let new_string = catch_cook(self.inner.as_mut(), &mut self.status, |sop| {
+            Ok(sop.warning())
+        });
+        if let Ok(new_string) = new_string {
+            let new_string = message_c_string(&new_string);
+            unsafe {
+                warning.setString(new_string.as_ptr());
+            }
         }
     }
 
     fn getErrorString(&mut self, error: Pin<&mut OP_String>) {
+        // If `error` itself panics, show the panic instead
+        let new_string = catch_cook(self.inner.as_mut(), &mut self.status, |sop| Ok(sop.error()))
+            .unwrap_or_else(|message| message);
+        let new_string = message_c_string(&new_string);
         unsafe {
-            let new_string = CString::new(self.inner.error()).unwrap();
-            let new_string_ptr = new_string.as_ptr();
-            error.setString(new_string_ptr);
+            error.setString(new_string.as_ptr());
         }
     }
 
     fn getInfoPopupString(&mut self, info: Pin<&mut OP_String>) {
-        unsafe {
-            let new_string = CString::new(self.inner.info()).unwrap();
-            let new_string_ptr = new_string.as_ptr();
-            info.setString(new_string_ptr);
+        let new_string = catch_cook(self.inner.as_mut(), &mut self.status, |sop| Ok(sop.info()));
+        if let Ok(new_string) = new_string {
+            let new_string = message_c_string(&new_string);
+            unsafe {
+                info.setString(new_string.as_ptr());
+            }
         }
     }
 
     fn setupParameters(&mut self, manager: Pin<&mut OP_ParameterManager>) {
         #[cfg(feature = "tracing")]
         let _span = { tracing_base::trace_span!("setupParameters").entered() };
-        let params = self.inner.params_mut();
-        if let Some(params) = params {
-            let mut manager = ParameterManager::new(manager);
-            params.register(&mut manager);
-        }
+        let _ = catch_cook(self.inner.as_mut(), &mut self.status, |sop| {
+            if let Some(params) = sop.params_mut() {
+                let mut manager = ParameterManager::new(manager);
+                params.register(&mut manager);
+            }
+            Ok(())
+        });
     }
 
     unsafe fn pulsePressed(&mut self, name: *const std::ffi::c_char) {
         #[cfg(feature = "tracing")]
         let _span = { tracing_base::trace_span!("pulsePressed").entered() };
-        self.inner
-            .pulse_pressed(std::ffi::CStr::from_ptr(name).to_str().unwrap());
+        let _ = catch_cook(self.inner.as_mut(), &mut self.status, |sop| {
+            sop.press_pulse(std::ffi::CStr::from_ptr(name).to_str()?);
+            Ok(())
+        });
     }
 
     fn buildDynamicMenu(&mut self, inputs: &OP_Inputs, info: Pin<&mut OP_BuildDynamicMenuInfo>) {
@@ -227,6 +274,11 @@ impl RustSopPlugin_methods for RustSopPluginImpl {
         let _span = { tracing_base::trace_span!("buildDynamicMenu").entered() };
         let input = OperatorInputs::new(inputs);
         let mut info = DynamicMenuInfo::new(info);
-        self.inner.build_dynamic_menu(&input, &mut info);
+        let _ = catch_cook(self.inner.as_mut(), &mut self.status, |sop| {
+            if !sop.build_params_menu(&MenuInputs::new(inputs), &mut info) {
+                sop.build_dynamic_menu(&input, &mut info);
+            }
+            Ok(())
+        });
     }
 }
@@ -678,14 +678,47 @@ pub trait Sop: Op {
         SopGeneralInfo::default()
     }
 
+    /// Fallible version of [`Sop::general_info`].
+    fn try_general_info(&self, input: &OperatorInputs<SopInput>) -> CookResult<SopGeneralInfo> {
+        Ok(self.general_info(input))
+    }
+
     fn execute(&mut self, _output: &mut SopOutput, _inputs: &OperatorInputs<SopInput>) {
         // Do nothing by default.
     }
 
+    /// Fallible version of [`Sop::execute`]. On error the error is shown on the
+    /// node.
+    ///
+    /// # Known limitation
+    ///
+    /// Unlike the other families, a failed SOP cook doesn't output nothing.
+    /// `SOP_Output` can only be appended to, so geometry added before the error
+    /// or panic stays in the output. Check inputs and parameters before adding
+    /// geometry where a partial result would be misleading.
+    fn try_execute(
+        &mut self,
+        output: &mut SopOutput,
+        inputs: &OperatorInputs<SopInput>,
+    ) -> CookResult {
+        self.execute(output, inputs);
+        Ok(())
+    }
+
     fn execute_vbo(&mut self, _output: SopVboOutput<Unalloc>, _inputs: &OperatorInputs<SopInput>) {
         // Do nothing by default.
     }
 
+    /// Fallible version of [`Sop::execute_vbo`].
+    fn try_execute_vbo(
+        &mut self,
+        output: SopVboOutput<Unalloc>,
+        inputs: &OperatorInputs<SopInput>,
+    ) -> CookResult {
+        self.execute_vbo(output, inputs);
+        Ok(())
+    }
+
     fn build_dynamic_menu(
         &mut self,
         inputs: &OperatorInputs<SopInput>,
@@ -694,6 +727,16 @@ pub trait Sop: Op {
     }
 }
 
+impl Sop for FailedOp {
+    fn try_execute(
+        &mut self,
+        _output: &mut SopOutput,
+        _inputs: &OperatorInputs<SopInput>,
+    ) -> CookResult {
+        self.fail()
+    }
+}
+
 #[macro_export]
 macro_rules! sop_plugin {
     ($plugin_ty:ty) => {
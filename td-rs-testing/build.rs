@@ -0,0 +1,12 @@
+fn main() -> miette::Result<()> {
+    td_rs_autocxx_build::build_with_includes(
+        "td-rs-testing",
+        true,
+        &[
+            "../td-rs-chop/src",
+            "../td-rs-dat/src",
+            "../td-rs-sop/src",
+            "../td-rs-top/src",
+        ],
+    )
+}
@@ -0,0 +1,1435 @@
+#include "CPlusPlus_Common.h"
+#include "CHOP_CPlusPlusBase.h"
+#include "DAT_CPlusPlusBase.h"
+#include "SOP_CPlusPlusBase.h"
+#include "TOP_CPlusPlusBase.h"
+#include <algorithm>
+#include <array>
+#include <cmath>
+#include <cstdio>
+#include <cstdlib>
+#include <cstring>
+#include <map>
+#include <memory>
+#include <string>
+#include <vector>
+
+#ifndef TD_RS_TESTHOST_H
+#define TD_RS_TESTHOST_H
+
+using namespace TD;
+
+// Stand-ins for the objects TouchDesigner hands to a custom operator. None of
+// these try to reproduce TouchDesigner's behaviour exactly; they record what the
+// operator does and serve back whatever the test has configured.
+
+class TestString : public OP_String {
+public:
+    TestString() {}
+    virtual ~TestString() {}
+
+    void setString(const char *val) override {
+        value = val ? val : "";
+    }
+
+    const char *get() const { return value.c_str(); }
+
+    OP_String &asString() { return *this; }
+
+private:
+    std::string value;
+};
+
+struct TestParameter {
+    std::string type;
+    std::string name;
+    std::string label;
+    std::string page;
+    int32_t size = 0;
+    double defaultValues[4] = {0.0, 0.0, 0.0, 0.0};
+    double minValues[4] = {0.0, 0.0, 0.0, 0.0};
+    double maxValues[4] = {1.0, 1.0, 1.0, 1.0};
+    std::string defaultString;
+    std::vector<std::string> menuNames;
+    std::vector<std::string> menuLabels;
+};
+
+class TestInputs;
+
+class TestParameterManager : public OP_ParameterManager {
+public:
+    explicit TestParameterManager(TestInputs *inputs) : inputs(inputs) {}
+    virtual ~TestParameterManager() {}
+
+    OP_ParAppendResult appendFloat(const OP_NumericParameter &np, int32_t size = 1) override {
+        return appendNumeric("Float", np, size);
+    }
+    OP_ParAppendResult appendInt(const OP_NumericParameter &np, int32_t size = 1) override {
+        return appendNumeric("Int", np, size);
+    }
+    OP_ParAppendResult appendXY(const OP_NumericParameter &np) override {
+        return appendNumeric("XY", np, 2);
+    }
+    OP_ParAppendResult appendXYZ(const OP_NumericParameter &np) override {
+        return appendNumeric("XYZ", np, 3);
+    }
+    OP_ParAppendResult appendUV(const OP_NumericParameter &np) override {
+        return appendNumeric("UV", np, 2);
+    }
+    OP_ParAppendResult appendUVW(const OP_NumericParameter &np) override {
+        return appendNumeric("UVW", np, 3);
+    }
+    OP_ParAppendResult appendRGB(const OP_NumericParameter &np) override {
+        return appendNumeric("RGB", np, 3);
+    }
+    OP_ParAppendResult appendRGBA(const OP_NumericParameter &np) override {
+        return appendNumeric("RGBA", np, 4);
+    }
+    OP_ParAppendResult appendToggle(const OP_NumericParameter &np) override {
+        return appendNumeric("Toggle", np, 1);
+    }
+    OP_ParAppendResult appendPulse(const OP_NumericParameter &np) override {
+        return appendNumeric("Pulse", np, 1);
+    }
+    OP_ParAppendResult appendString(const OP_StringParameter &sp) override {
+        return appendStr("String", sp);
+    }
+    OP_ParAppendResult appendFile(const OP_StringParameter &sp) override {
+        return appendStr("File", sp);
+    }
+    OP_ParAppendResult appendFolder(const OP_StringParameter &sp) override {
+        return appendStr("Folder", sp);
+    }
+    OP_ParAppendResult appendDAT(const OP_StringParameter &sp) override {
+        return appendStr("DAT", sp);
+    }
+    OP_ParAppendResult appendCHOP(const OP_StringParameter &sp) override {
+        return appendStr("CHOP", sp);
+    }
+    OP_ParAppendResult appendTOP(const OP_StringParameter &sp) override {
+        return appendStr("TOP", sp);
+    }
+    OP_ParAppendResult appendObject(const OP_StringParameter &sp) override {
+        return appendStr("Object", sp);
+    }
+    OP_ParAppendResult appendMenu(const OP_StringParameter &sp, int32_t nitems,
+                                  const char **names, const char **labels) override {
+        return appendMenuItems("Menu", sp, nitems, names, labels);
+    }
+    OP_ParAppendResult appendStringMenu(const OP_StringParameter &sp, int32_t nitems,
+                                        const char **names, const char **labels) override {
+        return appendMenuItems("StringMenu", sp, nitems, names, labels);
+    }
+    OP_ParAppendResult appendSOP(const OP_StringParameter &sp) override {
+        return appendStr("SOP", sp);
+    }
+    OP_ParAppendResult appendPython(const OP_StringParameter &sp) override {
+        return appendStr("Python", sp);
+    }
+    OP_ParAppendResult appendOP(const OP_StringParameter &sp) override {
+        return appendStr("OP", sp);
+    }
+    OP_ParAppendResult appendCOMP(const OP_StringParameter &sp) override {
+        return appendStr("COMP", sp);
+    }
+    OP_ParAppendResult appendMAT(const OP_StringParameter &sp) override {
+        return appendStr("MAT", sp);
+    }
+    OP_ParAppendResult appendPanelCOMP(const OP_StringParameter &sp) override {
+        return appendStr("PanelCOMP", sp);
+    }
+    OP_ParAppendResult appendHeader(const OP_StringParameter &sp) override {
+        return appendStr("Header", sp);
+    }
+    OP_ParAppendResult appendMomentary(const OP_NumericParameter &np) override {
+        return appendNumeric("Momentary", np, 1);
+    }
+    OP_ParAppendResult appendWH(const OP_NumericParameter &np) override {
+        return appendNumeric("WH", np, 2);
+    }
+    OP_ParAppendResult appendDynamicStringMenu(const OP_StringParameter &sp) override {
+        return appendStr("DynamicStringMenu", sp);
+    }
+    OP_ParAppendResult appendDynamicMenu(const OP_NumericParameter &np) override {
+        return appendNumeric("DynamicMenu", np, 1);
+    }
+    OP_ParAppendResult appendXYZW(const OP_NumericParameter &np) override {
+        return appendNumeric("XYZW", np, 4);
+    }
+
+    OP_ParameterManager &asManager() { return *this; }
+
+    int32_t numParameters() const { return (int32_t)params.size(); }
+    const char *parameterType(int32_t i) const { return params[i].type.c_str(); }
+    const char *parameterName(int32_t i) const { return params[i].name.c_str(); }
+    const char *parameterLabel(int32_t i) const { return params[i].label.c_str(); }
+    const char *parameterPage(int32_t i) const { return params[i].page.c_str(); }
+    int32_t parameterSize(int32_t i) const { return params[i].size; }
+    double parameterDefault(int32_t i, int32_t c) const { return params[i].defaultValues[c]; }
+    double parameterMin(int32_t i, int32_t c) const { return params[i].minValues[c]; }
+    double parameterMax(int32_t i, int32_t c) const { return params[i].maxValues[c]; }
+    const char *parameterDefaultString(int32_t i) const { return params[i].defaultString.c_str(); }
+    int32_t parameterNumMenuItems(int32_t i) const { return (int32_t)params[i].menuNames.size(); }
+    const char *parameterMenuName(int32_t i, int32_t item) const { return params[i].menuNames[item].c_str(); }
+    const char *parameterMenuLabel(int32_t i, int32_t item) const { return params[i].menuLabels[item].c_str(); }
+
+private:
+    static std::string str(const char *s) { return s ? s : ""; }
+
+    bool isDuplicate(const char *name) const {
+        if (!name || !*name) {
+            return true;
+        }
+        for (const auto &p : params) {
+            if (p.name == name) {
+                return true;
+            }
+        }
+        return false;
+    }
+
+    OP_ParAppendResult appendNumeric(const char *type, const OP_NumericParameter &np, int32_t size);
+    OP_ParAppendResult appendStr(const char *type, const OP_StringParameter &sp);
+    OP_ParAppendResult appendMenuItems(const char *type, const OP_StringParameter &sp, int32_t nitems,
+                                       const char **names, const char **labels);
+
+    TestInputs *inputs;
+    std::vector<TestParameter> params;
+};
+
+class TestCHOP {
+public:
+    TestCHOP(const char *path, uint32_t id, int32_t numSamples, double sampleRate, double startIndex)
+        : path(path) {
+        memset(&input, 0, sizeof(input));
+        input.opId = id;
+        input.numSamples = numSamples;
+        input.sampleRate = sampleRate;
+        input.startIndex = startIndex;
+        sync();
+    }
+
+    void addChannel(const char *name, const float *values) {
+        names.emplace_back(name);
+        data.emplace_back(values, values + input.numSamples);
+        sync();
+    }
+
+    const OP_CHOPInput *get() const { return &input; }
+    const std::string &getPath() const { return path; }
+
+private:
+    void sync() {
+        namePtrs.clear();
+        dataPtrs.clear();
+        for (size_t i = 0; i < names.size(); i++) {
+            namePtrs.push_back(names[i].c_str());
+            dataPtrs.push_back(data[i].data());
+        }
+        input.opPath = path.c_str();
+        input.numChannels = (int32_t)names.size();
+        input.nameData = namePtrs.data();
+        input.channelData = dataPtrs.data();
+    }
+
+    std::string path;
+    std::vector<std::string> names;
+    std::vector<std::vector<float>> data;
+    std::vector<const char *> namePtrs;
+    std::vector<const float *> dataPtrs;
+    OP_CHOPInput input;
+};
+
+class TestDAT {
+public:
+    TestDAT(const char *path, uint32_t id, int32_t rows, int32_t cols, bool isTable)
+        : path(path), cells((size_t)rows * cols) {
+        memset(&input, 0, sizeof(input));
+        input.opId = id;
+        input.numRows = rows;
+        input.numCols = cols;
+        input.isTable = isTable;
+        sync();
+    }
+
+    void setCell(int32_t row, int32_t col, const char *value) {
+        cells[(size_t)row * input.numCols + col] = value ? value : "";
+        sync();
+    }
+
+    const OP_DATInput *get() const { return &input; }
+    const std::string &getPath() const { return path; }
+
+private:
+    void sync() {
+        cellPtrs.clear();
+        for (const auto &c : cells) {
+            cellPtrs.push_back(c.c_str());
+        }
+        input.opPath = path.c_str();
+        input.cellData = cellPtrs.data();
+    }
+
+    std::string path;
+    std::vector<std::string> cells;
+    std::vector<const char *> cellPtrs;
+    OP_DATInput input;
+};
+
+class TestSOP : public OP_SOPInput {
+public:
+    TestSOP(const char *path, uint32_t id) : path(path) {
+        opPath = this->path.c_str();
+        opId = id;
+        myPrimsInfo = nullptr;
+        myPrimPointIndices = nullptr;
+        totalCooks = 0;
+        customOP = nullptr;
+        memset(reserved, 0, sizeof(reserved));
+    }
+    virtual ~TestSOP() {}
+
+    void addPoint(float x, float y, float z) {
+        positions.emplace_back(x, y, z);
+        sync();
+    }
+
+    void setNormal(int32_t index, float x, float y, float z) {
+        normals.resize(positions.size());
+        normals[index] = Vector(x, y, z);
+        sync();
+    }
+
+    void setColor(int32_t index, float r, float g, float b, float a) {
+        colors.resize(positions.size(), Color(1.0f, 1.0f, 1.0f, 1.0f));
+        colors[index] = Color(r, g, b, a);
+        sync();
+    }
+
+    void addPrimitive(const int32_t *indices, int32_t numVertices, bool isClosed) {
+        SOP_PrimitiveInfo prim;
+        prim.numVertices = numVertices;
+        prim.type = PrimitiveType::Polygon;
+        prim.pointIndicesOffset = (int32_t)pointIndices.size();
+        prim.isClosed = isClosed;
+        prims.push_back(prim);
+        pointIndices.insert(pointIndices.end(), indices, indices + numVertices);
+        sync();
+    }
+
+    int32_t getNumPoints() const override { return (int32_t)positions.size(); }
+    int32_t getNumVertices() const override { return (int32_t)pointIndices.size(); }
+    int32_t getNumPrimitives() const override { return (int32_t)prims.size(); }
+    int32_t getNumCustomAttributes() const override { return 0; }
+    const Position *getPointPositions() const override { return positions.data(); }
+    const SOP_NormalInfo *getNormals() const override { return &normalInfo; }
+    const SOP_ColorInfo *getColors() const override { return &colorInfo; }
+    const SOP_TextureInfo *getTextures() const override { return &textureInfo; }
+    const SOP_CustomAttribData *getCustomAttribute(int32_t) const override { return nullptr; }
+    const SOP_CustomAttribData *getCustomAttribute(const char *) const override { return nullptr; }
+    bool hasNormals() const override { return !normals.empty(); }
+    bool hasColors() const override { return !colors.empty(); }
+    bool isInside(const Position &) override { return false; }
+    bool sendRay(const Position &, const Vector &, Position &, float &, Vector &, float &, float &,
+                 int &) override {
+        return false;
+    }
+    const SOP_ColorInfo *getVtxColors() const override { return &emptyColors; }
+    const SOP_TextureInfo *getVtxTextures() const override { return &textureInfo; }
+    const SOP_ColorInfo *getPrimColors() const override { return &emptyColors; }
+    bool hasVtxColors() const override { return false; }
+    bool hasPrimColors() const override { return false; }
+
+    const std::string &getPath() const { return path; }
+
+private:
+    void sync() {
+        opPath = path.c_str();
+        normalInfo.numNormals = (int32_t)normals.size();
+        normalInfo.normals = normals.data();
+        colorInfo.numColors = (int32_t)colors.size();
+        colorInfo.colors = colors.data();
+        for (auto &prim : prims) {
+            prim.pointIndices = pointIndices.data() + prim.pointIndicesOffset;
+        }
+        myPrimsInfo = prims.data();
+        myPrimPointIndices = pointIndices.data();
+    }
+
+    std::string path;
+    std::vector<Position> positions;
+    std::vector<Vector> normals;
+    std::vector<Color> colors;
+    std::vector<SOP_PrimitiveInfo> prims;
+    std::vector<int32_t> pointIndices;
+    SOP_NormalInfo normalInfo;
+    SOP_ColorInfo colorInfo;
+    SOP_ColorInfo emptyColors;
+    SOP_TextureInfo textureInfo;
+};
+
+class TestDownloadResult : public OP_TOPDownloadResult {
+public:
+    TestDownloadResult(const std::vector<uint8_t> &pixels, const OP_TextureDesc &desc) : pixels(pixels) {
+        size = this->pixels.size();
+        textureDesc = desc;
+    }
+
+    void *getData() override { return pixels.data(); }
+
+protected:
+    virtual ~TestDownloadResult() {}
+
+    void acquire() override { refCount++; }
+    void release() override {
+        if (--refCount == 0) {
+            delete this;
+        }
+    }
+    void reserved0() override {}
+    void reserved1() override {}
+    void reserved2() override {}
+    void reserved3() override {}
+    void reserved4() override {}
+
+private:
+    std::vector<uint8_t> pixels;
+    int32_t refCount = 0;
+};
+
+class TestTOP : public OP_TOPInput {
+public:
+    TestTOP(const char *path, uint32_t id, uint32_t width, uint32_t height, OP_PixelFormat pixelFormat,
+            const uint8_t *data, uint64_t size)
+        : path(path), pixels(data, data + size) {
+        opPath = this->path.c_str();
+        opId = id;
+        textureDesc.width = width;
+        textureDesc.height = height;
+        textureDesc.texDim = OP_TexDim::e2D;
+        textureDesc.pixelFormat = pixelFormat;
+        textureDesc.aspectX = (float)width;
+        textureDesc.aspectY = (float)height;
+        totalCooks = 0;
+        customOP = nullptr;
+        memset(reserved, 0, sizeof(reserved));
+    }
+    virtual ~TestTOP() {}
+
+    // Pixels are handed back as they were provided; the requested pixel format
+    // and vertical flip are not applied.
+    OP_SmartRef<OP_TOPDownloadResult> downloadTexture(const OP_TOPInputDownloadOptions &,
+                                                      void *) const override {
+        return OP_SmartRef<OP_TOPDownloadResult>(new TestDownloadResult(pixels, textureDesc));
+    }
+
+    const OP_CUDAArrayInfo *getCUDAArray(const OP_CUDAAcquireInfo &, void *) const override {
+        return nullptr;
+    }
+
+    const std::string &getPath() const { return path; }
+
+protected:
+    void *reserved0() override { return nullptr; }
+    void *reserved1() override { return nullptr; }
+    void *reserved2() override { return nullptr; }
+    void *reserved3() override { return nullptr; }
+    void *reserved4() override { return nullptr; }
+
+private:
+    std::string path;
+    std::vector<uint8_t> pixels;
+};
+
+class TestObject {
+public:
+    TestObject(const char *path, uint32_t id, const double *world, const double *local,
+               int64_t totalCooks)
+        : path(path) {
+        memset(&input, 0, sizeof(input));
+        input.opPath = this->path.c_str();
+        input.opId = id;
+        memcpy(input.worldTransform, world, sizeof(input.worldTransform));
+        memcpy(input.localTransform, local, sizeof(input.localTransform));
+        input.totalCooks = totalCooks;
+    }
+
+    const OP_ObjectInput *get() const { return &input; }
+    const std::string &getPath() const { return path; }
+
+private:
+    std::string path;
+    OP_ObjectInput input;
+};
+
+// Inverts a 4x4 matrix by Gauss-Jordan elimination, false if it is singular.
+inline bool invertMatrix(const double m[4][4], double out[4][4]) {
+    double a[4][8];
+    for (int r = 0; r < 4; r++) {
+        for (int c = 0; c < 4; c++) {
+            a[r][c] = m[r][c];
+            a[r][c + 4] = r == c ? 1.0 : 0.0;
+        }
+    }
+    for (int c = 0; c < 4; c++) {
+        int pivot = c;
+        for (int r = c + 1; r < 4; r++) {
+            if (std::fabs(a[r][c]) > std::fabs(a[pivot][c])) pivot = r;
+        }
+        if (std::fabs(a[pivot][c]) < 1e-12) return false;
+        for (int k = 0; k < 8; k++) std::swap(a[c][k], a[pivot][k]);
+        double d = a[c][c];
+        for (int k = 0; k < 8; k++) a[c][k] /= d;
+        for (int r = 0; r < 4; r++) {
+            if (r == c) continue;
+            double f = a[r][c];
+            for (int k = 0; k < 8; k++) a[r][k] -= f * a[c][k];
+        }
+    }
+    for (int r = 0; r < 4; r++) {
+        for (int c = 0; c < 4; c++) out[r][c] = a[r][c + 4];
+    }
+    return true;
+}
+
+class TestInputs : public OP_Inputs {
+public:
+    TestInputs() : manager(this) {
+        memset(&timeInfo, 0, sizeof(timeInfo));
+        applyTime(0, 1.0, 60.0, 0.0);
+    }
+    virtual ~TestInputs() {}
+
+    const OP_Inputs &asInputs() const { return *this; }
+    OP_ParameterManager &parameterManager() { return manager; }
+    const TestParameterManager &parameters() const { return manager; }
+
+    // Parameter values
+
+    void setParDouble(const char *name, int32_t index, double value) {
+        numeric[name][index] = value;
+    }
+
+    void setParString(const char *name, const char *value) {
+        strings[name] = value ? value : "";
+    }
+
+    bool isParEnabled(const char *name) const {
+        auto it = enabled.find(name);
+        return it == enabled.end() || it->second;
+    }
+
+    // Time
+
+    // The timing of the next cook.
+    void setTimeInfo(int64_t absFrame, double frame, double rate, double deltaFrames) {
+        applyTime(absFrame, frame, rate, deltaFrames);
+        timeSet = true;
+    }
+
+    // Called at the start of every cook. The first cook is at frame 1 with no
+    // frames elapsed, each later one a frame after the previous cook unless
+    // the time was set in between.
+    void beginCook() {
+        if (!timeSet && cooked) {
+            applyTime(timeInfo.absFrame + 1, timeInfo.frame + 1.0, timeInfo.rate, 1.0);
+        }
+        timeSet = false;
+        cooked = true;
+    }
+
+    // Operators. Each add* call returns an id that can then be wired as a
+    // numbered input, referenced from a parameter, or looked up by path.
+
+    int32_t addCHOP(const char *path, int32_t numSamples, double sampleRate, double startIndex) {
+        chops.emplace_back(new TestCHOP(path, nextId++, numSamples, sampleRate, startIndex));
+        return (int32_t)chops.size() - 1;
+    }
+    void addCHOPChannel(int32_t chop, const char *name, const float *values) {
+        chops[chop]->addChannel(name, values);
+    }
+    void setInputCHOP(int32_t index, int32_t chop) { inputCHOPs[index] = chop; }
+    void setParCHOP(const char *name, int32_t chop) {
+        parCHOPs[name] = chop;
+        strings[name] = chops[chop]->getPath();
+    }
+
+    int32_t addDAT(const char *path, int32_t rows, int32_t cols, bool isTable) {
+        dats.emplace_back(new TestDAT(path, nextId++, rows, cols, isTable));
+        return (int32_t)dats.size() - 1;
+    }
+    void setDATCell(int32_t dat, int32_t row, int32_t col, const char *value) {
+        dats[dat]->setCell(row, col, value);
+    }
+    void setInputDAT(int32_t index, int32_t dat) { inputDATs[index] = dat; }
+    void setParDAT(const char *name, int32_t dat) {
+        parDATs[name] = dat;
+        strings[name] = dats[dat]->getPath();
+    }
+
+    int32_t addSOP(const char *path) {
+        sops.emplace_back(new TestSOP(path, nextId++));
+        return (int32_t)sops.size() - 1;
+    }
+    void addSOPPoint(int32_t sop, float x, float y, float z) { sops[sop]->addPoint(x, y, z); }
+    void setSOPNormal(int32_t sop, int32_t index, float x, float y, float z) {
+        sops[sop]->setNormal(index, x, y, z);
+    }
+    void setSOPColor(int32_t sop, int32_t index, float r, float g, float b, float a) {
+        sops[sop]->setColor(index, r, g, b, a);
+    }
+    void addSOPPrimitive(int32_t sop, const int32_t *indices, int32_t numVertices, bool isClosed) {
+        sops[sop]->addPrimitive(indices, numVertices, isClosed);
+    }
+    void setInputSOP(int32_t index, int32_t sop) { inputSOPs[index] = sop; }
+    void setParSOP(const char *name, int32_t sop) {
+        parSOPs[name] = sop;
+        strings[name] = sops[sop]->getPath();
+    }
+
+    int32_t addTOP(const char *path, uint32_t width, uint32_t height, OP_PixelFormat pixelFormat,
+                   const uint8_t *data, uint64_t size) {
+        tops.emplace_back(new TestTOP(path, nextId++, width, height, pixelFormat, data, size));
+        return (int32_t)tops.size() - 1;
+    }
+    void setInputTOP(int32_t index, int32_t top) { inputTOPs[index] = top; }
+    void setParTOP(const char *name, int32_t top) {
+        parTOPs[name] = top;
+        strings[name] = tops[top]->getPath();
+    }
+
+    int32_t addObject(const char *path, const double *world, const double *local,
+                      int64_t totalCooks) {
+        objects.emplace_back(new TestObject(path, nextId++, world, local, totalCooks));
+        return (int32_t)objects.size() - 1;
+    }
+    void setParObject(const char *name, int32_t object) {
+        parObjects[name] = object;
+        strings[name] = objects[object]->getPath();
+    }
+
+    // OP_Inputs
+
+    int32_t getNumInputs() const override {
+        int32_t n = 0;
+        for (auto &i : inputCHOPs) n = std::max(n, i.first + 1);
+        for (auto &i : inputDATs) n = std::max(n, i.first + 1);
+        for (auto &i : inputSOPs) n = std::max(n, i.first + 1);
+        for (auto &i : inputTOPs) n = std::max(n, i.first + 1);
+        return n;
+    }
+
+    const OP_CHOPInput *getInputCHOP(int32_t index) const override {
+        auto it = inputCHOPs.find(index);
+        return it == inputCHOPs.end() ? nullptr : chops[it->second]->get();
+    }
+
+    const OP_DATInput *getParDAT(const char *name) const override {
+        auto it = parDATs.find(name);
+        return it == parDATs.end() ? nullptr : dats[it->second]->get();
+    }
+
+    const OP_CHOPInput *getParCHOP(const char *name) const override {
+        auto it = parCHOPs.find(name);
+        return it == parCHOPs.end() ? nullptr : chops[it->second]->get();
+    }
+
+    const OP_ObjectInput *getParObject(const char *name) const override {
+        auto it = parObjects.find(name);
+        return it == parObjects.end() ? nullptr : objects[it->second]->get();
+    }
+
+    double getParDouble(const char *name, int32_t index = 0) const override {
+        auto it = numeric.find(name);
+        return it == numeric.end() ? 0.0 : it->second[index];
+    }
+
+    bool getParDouble2(const char *name, double &v0, double &v1) const override {
+        v0 = getParDouble(name, 0);
+        v1 = getParDouble(name, 1);
+        return numeric.count(name) > 0;
+    }
+
+    bool getParDouble3(const char *name, double &v0, double &v1, double &v2) const override {
+        v2 = getParDouble(name, 2);
+        return getParDouble2(name, v0, v1);
+    }
+
+    bool getParDouble4(const char *name, double &v0, double &v1, double &v2,
+                       double &v3) const override {
+        v3 = getParDouble(name, 3);
+        return getParDouble3(name, v0, v1, v2);
+    }
+
+    int32_t getParInt(const char *name, int32_t index = 0) const override {
+        return (int32_t)getParDouble(name, index);
+    }
+
+    bool getParInt2(const char *name, int32_t &v0, int32_t &v1) const override {
+        v0 = getParInt(name, 0);
+        v1 = getParInt(name, 1);
+        return numeric.count(name) > 0;
+    }
+
+    bool getParInt3(const char *name, int32_t &v0, int32_t &v1, int32_t &v2) const override {
+        v2 = getParInt(name, 2);
+        return getParInt2(name, v0, v1);
+    }
+
+    bool getParInt4(const char *name, int32_t &v0, int32_t &v1, int32_t &v2,
+                    int32_t &v3) const override {
+        v3 = getParInt(name, 3);
+        return getParInt3(name, v0, v1, v2);
+    }
+
+    const char *getParString(const char *name) const override {
+        auto it = strings.find(name);
+        return it == strings.end() ? "" : it->second.c_str();
+    }
+
+    const char *getParFilePath(const char *name) const override { return getParString(name); }
+
+    // The transform from the space of one object into the space of the other,
+    // inverse(to.world) * from.world.
+    bool getRelativeTransform(const char *from_name, const char *to_name,
+                              double matrix[4][4]) const override {
+        const OP_ObjectInput *from = getParObject(from_name);
+        const OP_ObjectInput *to = getParObject(to_name);
+        double toInverse[4][4];
+        if (!from || !to || !invertMatrix(to->worldTransform, toInverse)) return false;
+        for (int r = 0; r < 4; r++) {
+            for (int c = 0; c < 4; c++) {
+                matrix[r][c] = 0.0;
+                for (int k = 0; k < 4; k++) {
+                    matrix[r][c] += toInverse[r][k] * from->worldTransform[k][c];
+                }
+            }
+        }
+        return true;
+    }
+
+    void enablePar(const char *name, bool onoff) const override { enabled[name] = onoff; }
+
+    const OP_DATInput *getDAT(const char *path) const override {
+        for (auto &dat : dats) {
+            if (dat->getPath() == path) return dat->get();
+        }
+        return nullptr;
+    }
+
+    const OP_CHOPInput *getCHOP(const char *path) const override {
+        for (auto &chop : chops) {
+            if (chop->getPath() == path) return chop->get();
+        }
+        return nullptr;
+    }
+
+    const OP_ObjectInput *getObject(const char *path) const override {
+        for (auto &object : objects) {
+            if (object->getPath() == path) return object->get();
+        }
+        return nullptr;
+    }
+
+    const OP_SOPInput *getParSOP(const char *name) const override {
+        auto it = parSOPs.find(name);
+        return it == parSOPs.end() ? nullptr : sops[it->second].get();
+    }
+
+    const OP_SOPInput *getInputSOP(int32_t index) const override {
+        auto it = inputSOPs.find(index);
+        return it == inputSOPs.end() ? nullptr : sops[it->second].get();
+    }
+
+    const OP_SOPInput *getSOP(const char *path) const override {
+        for (auto &sop : sops) {
+            if (sop->getPath() == path) return sop.get();
+        }
+        return nullptr;
+    }
+
+    const OP_DATInput *getInputDAT(int32_t index) const override {
+        auto it = inputDATs.find(index);
+        return it == inputDATs.end() ? nullptr : dats[it->second]->get();
+    }
+
+    PyObject *getParPython(const char *) const override { return nullptr; }
+
+    const OP_TimeInfo *getTimeInfo() const override { return &timeInfo; }
+
+    const OP_TOPInput *getTOP(const char *path) const override {
+        for (auto &top : tops) {
+            if (top->getPath() == path) return top.get();
+        }
+        return nullptr;
+    }
+
+    const OP_TOPInput *getInputTOP(int32_t index) const override {
+        auto it = inputTOPs.find(index);
+        return it == inputTOPs.end() ? nullptr : tops[it->second].get();
+    }
+
+    const OP_TOPInput *getParTOP(const char *name) const override {
+        auto it = parTOPs.find(name);
+        return it == parTOPs.end() ? nullptr : tops[it->second].get();
+    }
+
+private:
+    const OP_TOPInputOpenGL *getInputTOPOpenGL(int32_t) const override { return nullptr; }
+    const OP_TOPInputOpenGL *getParTOPOpenGL(const char *) const override { return nullptr; }
+    const OP_TOPInputOpenGL *getTOPOpenGL(const char *) const override { return nullptr; }
+    void *getTOPDataInCPUMemory(const OP_TOPInputOpenGL *,
+                                const OP_TOPInputDownloadOptionsOpenGL *) const override {
+        return nullptr;
+    }
+
+    void applyTime(int64_t absFrame, double frame, double rate, double deltaFrames) {
+        timeInfo.absFrame = absFrame;
+        timeInfo.frame = frame;
+        timeInfo.rate = rate;
+        timeInfo.rootFrame = (double)absFrame;
+        timeInfo.rootRate = rate;
+        timeInfo.deltaFrames = deltaFrames;
+        timeInfo.deltaMS = rate > 0.0 ? deltaFrames * 1000.0 / rate : 0.0;
+    }
+
+    TestParameterManager manager;
+    std::map<std::string, std::array<double, 4>> numeric;
+    std::map<std::string, std::string> strings;
+    mutable std::map<std::string, bool> enabled;
+    OP_TimeInfo timeInfo;
+    bool timeSet = false;
+    bool cooked = false;
+    uint32_t nextId = 1;
+
+    std::vector<std::unique_ptr<TestCHOP>> chops;
+    std::vector<std::unique_ptr<TestDAT>> dats;
+    std::vector<std::unique_ptr<TestSOP>> sops;
+    std::vector<std::unique_ptr<TestTOP>> tops;
+    std::vector<std::unique_ptr<TestObject>> objects;
+
+    std::map<int32_t, int32_t> inputCHOPs;
+    std::map<int32_t, int32_t> inputDATs;
+    std::map<int32_t, int32_t> inputSOPs;
+    std::map<int32_t, int32_t> inputTOPs;
+
+    std::map<std::string, int32_t> parCHOPs;
+    std::map<std::string, int32_t> parDATs;
+    std::map<std::string, int32_t> parSOPs;
+    std::map<std::string, int32_t> parTOPs;
+    std::map<std::string, int32_t> parObjects;
+};
+
+// Registering a parameter also gives it its default value, like a freshly
+// placed operator would have.
+
+inline OP_ParAppendResult TestParameterManager::appendNumeric(const char *type, const OP_NumericParameter &np,
+                                                              int32_t size) {
+    if (isDuplicate(np.name)) {
+        return OP_ParAppendResult::InvalidName;
+    }
+    if (size < 1 || size > 4) {
+        return OP_ParAppendResult::InvalidSize;
+    }
+    TestParameter p;
+    p.type = type;
+    p.name = str(np.name);
+    p.label = str(np.label);
+    p.page = str(np.page);
+    p.size = size;
+    for (int i = 0; i < 4; i++) {
+        p.defaultValues[i] = np.defaultValues[i];
+        p.minValues[i] = np.minValues[i];
+        p.maxValues[i] = np.maxValues[i];
+        inputs->setParDouble(np.name, i, np.defaultValues[i]);
+    }
+    params.push_back(p);
+    return OP_ParAppendResult::Success;
+}
+
+inline OP_ParAppendResult TestParameterManager::appendStr(const char *type, const OP_StringParameter &sp) {
+    if (isDuplicate(sp.name)) {
+        return OP_ParAppendResult::InvalidName;
+    }
+    TestParameter p;
+    p.type = type;
+    p.name = str(sp.name);
+    p.label = str(sp.label);
+    p.page = str(sp.page);
+    p.size = 1;
+    p.defaultString = str(sp.defaultValue);
+    inputs->setParString(sp.name, sp.defaultValue);
+    params.push_back(p);
+    return OP_ParAppendResult::Success;
+}
+
+inline OP_ParAppendResult TestParameterManager::appendMenuItems(const char *type, const OP_StringParameter &sp,
+                                                                int32_t nitems, const char **names,
+                                                                const char **labels) {
+    OP_ParAppendResult res = appendStr(type, sp);
+    if (res != OP_ParAppendResult::Success) {
+        return res;
+    }
+    TestParameter &p = params.back();
+    for (int32_t i = 0; i < nitems; i++) {
+        p.menuNames.push_back(str(names[i]));
+        p.menuLabels.push_back(str(labels[i]));
+        if (p.menuNames.back() == p.defaultString) {
+            inputs->setParDouble(sp.name, 0, i);
+        }
+    }
+    return OP_ParAppendResult::Success;
+}
+
+class TestDynamicMenuInfo : public OP_BuildDynamicMenuInfo {
+public:
+    explicit TestDynamicMenuInfo(const char *param) : param(param) {
+        instance = nullptr;
+        name = this->param.c_str();
+        memset(reserved, 0, sizeof(reserved));
+    }
+    virtual ~TestDynamicMenuInfo() {}
+
+    bool addMenuEntry(const char *n, const char *label) override {
+        names.emplace_back(n ? n : "");
+        labels.emplace_back(label ? label : "");
+        return true;
+    }
+
+    OP_BuildDynamicMenuInfo &asMenuInfo() { return *this; }
+
+    int32_t numEntries() const { return (int32_t)names.size(); }
+    const char *entryName(int32_t i) const { return names[i].c_str(); }
+    const char *entryLabel(int32_t i) const { return labels[i].c_str(); }
+
+private:
+    std::string param;
+    std::vector<std::string> names;
+    std::vector<std::string> labels;
+};
+
+class TestDatOutput : public DAT_Output {
+public:
+    TestDatOutput() {}
+    virtual ~TestDatOutput() {}
+
+    void setOutputDataType(DAT_OutDataType t) override { type = t; }
+    DAT_OutDataType getOutputDataType() override { return type; }
+
+    void setTableSize(const int32_t r, const int32_t c) override {
+        std::vector<std::string> resized((size_t)r * c);
+        for (int32_t i = 0; i < std::min(r, rows); i++) {
+            for (int32_t j = 0; j < std::min(c, cols); j++) {
+                resized[(size_t)i * c + j] = cells[(size_t)i * cols + j];
+            }
+        }
+        cells = resized;
+        rows = r;
+        cols = c;
+    }
+
+    void getTableSize(int32_t *r, int32_t *c) const override {
+        *r = rows;
+        *c = cols;
+    }
+
+    bool setText(const char *str) override {
+        text = str ? str : "";
+        return type == DAT_OutDataType::Text;
+    }
+
+    int32_t findRow(const char *rowName, int32_t = -1) override {
+        for (int32_t i = 0; i < rows && cols > 0; i++) {
+            if (cells[(size_t)i * cols] == rowName) return i;
+        }
+        return -1;
+    }
+
+    int32_t findCol(const char *colName, int32_t = -1) override {
+        for (int32_t j = 0; j < cols && rows > 0; j++) {
+            if (cells[j] == colName) return j;
+        }
+        return -1;
+    }
+
+    bool setCellString(int32_t row, int32_t col, const char *str) override {
+        if (!inRange(row, col)) return false;
+        cells[(size_t)row * cols + col] = str ? str : "";
+        return true;
+    }
+
+    bool setCellInt(int32_t row, int32_t col, int32_t value) override {
+        return setCellString(row, col, std::to_string(value).c_str());
+    }
+
+    bool setCellDouble(int32_t row, int32_t col, double value) override {
+        char buf[64];
+        snprintf(buf, sizeof(buf), "%g", value);
+        return setCellString(row, col, buf);
+    }
+
+    const char *getCellString(int32_t row, int32_t col) const override {
+        return inRange(row, col) ? cells[(size_t)row * cols + col].c_str() : nullptr;
+    }
+
+    bool getCellInt(int32_t row, int32_t col, int32_t *res) const override {
+        if (!inRange(row, col)) return false;
+        *res = atoi(cells[(size_t)row * cols + col].c_str());
+        return true;
+    }
+
+    bool getCellDouble(int32_t row, int32_t col, double *res) const override {
+        if (!inRange(row, col)) return false;
+        *res = atof(cells[(size_t)row * cols + col].c_str());
+        return true;
+    }
+
+    void *asOutput() { return static_cast<DAT_Output *>(this); }
+
+    bool isTable() const { return type == DAT_OutDataType::Table; }
+    int32_t numRows() const { return rows; }
+    int32_t numCols() const { return cols; }
+    const char *cell(int32_t row, int32_t col) const { return getCellString(row, col); }
+    const char *getText() const { return text.c_str(); }
+
+private:
+    bool inRange(int32_t row, int32_t col) const {
+        return row >= 0 && col >= 0 && row < rows && col < cols;
+    }
+
+    DAT_OutDataType type = DAT_OutDataType::Table;
+    int32_t rows = 0;
+    int32_t cols = 0;
+    std::vector<std::string> cells;
+    std::string text;
+};
+
+// Primitive kinds recorded by the SOP outputs.
+enum class TestPrimitiveKind : int32_t {
+    Triangle = 0,
+    Line,
+    ParticleSystem,
+};
+
+struct TestPrimitive {
+    TestPrimitiveKind kind;
+    int32_t offset;
+    int32_t count;
+};
+
+class TestSopOutput : public SOP_Output {
+public:
+    TestSopOutput() {}
+    virtual ~TestSopOutput() {}
+
+    int32_t addPoint(const Position &pos) override {
+        positions.push_back(pos);
+        return (int32_t)positions.size() - 1;
+    }
+
+    bool addPoints(const Position *pos, int32_t numPoints) override {
+        positions.insert(positions.end(), pos, pos + numPoints);
+        return true;
+    }
+
+    int32_t getNumPoints() override { return (int32_t)positions.size(); }
+
+    bool setNormal(const Vector &n, int32_t pointIdx) override { return setNormals(&n, 1, pointIdx); }
+
+    bool setNormals(const Vector *n, int32_t numPoints, int32_t startPointIdx) override {
+        if (startPointIdx + numPoints > getNumPoints()) return false;
+        normals.resize(positions.size());
+        std::copy(n, n + numPoints, normals.begin() + startPointIdx);
+        return true;
+    }
+
+    bool hasNormal() override { return !normals.empty(); }
+
+    bool setColor(const Color &c, int32_t pointIdx) override { return setColors(&c, 1, pointIdx); }
+
+    bool setColors(const Color *c, int32_t numPoints, int32_t startPointIdx) override {
+        if (startPointIdx + numPoints > getNumPoints()) return false;
+        colors.resize(positions.size());
+        std::copy(c, c + numPoints, colors.begin() + startPointIdx);
+        return true;
+    }
+
+    bool hasColor() override { return !colors.empty(); }
+
+    bool setTexCoord(const TexCoord *tex, int32_t numLayers, int32_t pointIdx) override {
+        return setTexCoords(tex, 1, numLayers, pointIdx);
+    }
+
+    bool setTexCoords(const TexCoord *t, int32_t numPoints, int32_t numLayers, int32_t startPointIdx) override {
+        if (startPointIdx + numPoints > getNumPoints() || numLayers < 1) return false;
+        texLayers = numLayers;
+        texCoords.resize(positions.size() * numLayers);
+        std::copy(t, t + (size_t)numPoints * numLayers, texCoords.begin() + (size_t)startPointIdx * numLayers);
+        return true;
+    }
+
+    bool hasTexCoord() override { return !texCoords.empty(); }
+
+    int32_t getNumTexCoordLayers() override { return texLayers; }
+
+    bool setCustomAttribute(const SOP_CustomAttribData *cu, int32_t) override {
+        customAttributes.emplace_back(cu->name ? cu->name : "");
+        return true;
+    }
+
+    bool hasCustomAttibutes() override { return !customAttributes.empty(); }
+
+    bool addTriangle(int32_t ptIdx1, int32_t ptIdx2, int32_t ptIdx3) override {
+        int32_t indices[3] = {ptIdx1, ptIdx2, ptIdx3};
+        return addTriangles(indices, 3);
+    }
+
+    bool addTriangles(const int32_t *indices, int32_t size) override {
+        for (int32_t i = 0; i + 2 < size; i += 3) {
+            addPrimitive(TestPrimitiveKind::Triangle, indices + i, 3);
+        }
+        return true;
+    }
+
+    bool addParticleSystem(int32_t numParticles, int32_t startIndex) override {
+        std::vector<int32_t> indices(numParticles);
+        for (int32_t i = 0; i < numParticles; i++) indices[i] = startIndex + i;
+        addPrimitive(TestPrimitiveKind::ParticleSystem, indices.data(), numParticles);
+        return true;
+    }
+
+    bool addLine(const int32_t *indices, int32_t size) override {
+        addPrimitive(TestPrimitiveKind::Line, indices, size);
+        return true;
+    }
+
+    bool addLines(const int32_t *indices, int32_t *sizeOfEachLine, int32_t numOfLines) override {
+        for (int32_t i = 0; i < numOfLines; i++) {
+            addLine(indices, sizeOfEachLine[i]);
+            indices += sizeOfEachLine[i];
+        }
+        return true;
+    }
+
+    int32_t getNumPrimitives() override { return (int32_t)prims.size(); }
+
+    bool setBoundingBox(const BoundingBox &bbox) override {
+        bounds = {bbox.minX, bbox.minY, bbox.minZ, bbox.maxX, bbox.maxY, bbox.maxZ};
+        return true;
+    }
+
+    bool addGroup(const SOP_GroupType &type, const char *name) override {
+        groups[groupKey(type, name)];
+        return true;
+    }
+
+    bool destroyGroup(const SOP_GroupType &type, const char *name) override {
+        return groups.erase(groupKey(type, name)) > 0;
+    }
+
+    bool addPointToGroup(int index, const char *name) override {
+        return addToGroup(index, SOP_GroupType::Point, name);
+    }
+
+    bool addPrimToGroup(int index, const char *name) override {
+        return addToGroup(index, SOP_GroupType::Primitive, name);
+    }
+
+    bool addToGroup(int index, const SOP_GroupType &type, const char *name) override {
+        auto it = groups.find(groupKey(type, name));
+        if (it == groups.end()) return false;
+        it->second.push_back(index);
+        return true;
+    }
+
+    bool discardFromPointGroup(int index, const char *name) override {
+        return discardFromGroup(index, SOP_GroupType::Point, name);
+    }
+
+    bool discardFromPrimGroup(int index, const char *name) override {
+        return discardFromGroup(index, SOP_GroupType::Primitive, name);
+    }
+
+    bool discardFromGroup(int index, const SOP_GroupType &type, const char *name) override {
+        auto it = groups.find(groupKey(type, name));
+        if (it == groups.end()) return false;
+        auto &members = it->second;
+        members.erase(std::remove(members.begin(), members.end(), index), members.end());
+        return true;
+    }
+
+    void *asOutput() { return static_cast<SOP_Output *>(this); }
+
+    int32_t numPoints() const { return (int32_t)positions.size(); }
+    const float *pointData() const { return reinterpret_cast<const float *>(positions.data()); }
+    const float *normalData() const { return reinterpret_cast<const float *>(normals.data()); }
+    int32_t numNormals() const { return (int32_t)normals.size(); }
+    const float *colorData() const { return reinterpret_cast<const float *>(colors.data()); }
+    int32_t numColors() const { return (int32_t)colors.size(); }
+    const float *texCoordData() const { return reinterpret_cast<const float *>(texCoords.data()); }
+    int32_t numTexCoords() const { return (int32_t)texCoords.size(); }
+    int32_t numTexCoordLayers() const { return texLayers; }
+    int32_t numPrims() const { return (int32_t)prims.size(); }
+    TestPrimitiveKind primitiveKind(int32_t i) const { return prims[i].kind; }
+    int32_t primitiveOffset(int32_t i) const { return prims[i].offset; }
+    int32_t primitiveCount(int32_t i) const { return prims[i].count; }
+    const int32_t *indexData() const { return indices.data(); }
+    int32_t numCustomAttributes() const { return (int32_t)customAttributes.size(); }
+    const char *customAttributeName(int32_t i) const { return customAttributes[i].c_str(); }
+    bool inGroup(int32_t index, bool primitive, const char *name) const {
+        auto it = groups.find(groupKey(primitive ? SOP_GroupType::Primitive : SOP_GroupType::Point, name));
+        return it != groups.end() &&
+               std::find(it->second.begin(), it->second.end(), index) != it->second.end();
+    }
+
+private:
+    static std::string groupKey(const SOP_GroupType &type, const char *name) {
+        return (type == SOP_GroupType::Point ? "point:" : "prim:") + std::string(name ? name : "");
+    }
+
+    void addPrimitive(TestPrimitiveKind kind, const int32_t *idx, int32_t count) {
+        prims.push_back({kind, (int32_t)indices.size(), count});
+        indices.insert(indices.end(), idx, idx + count);
+    }
+
+    std::vector<Position> positions;
+    std::vector<Vector> normals;
+    std::vector<Color> colors;
+    std::vector<TexCoord> texCoords;
+    int32_t texLayers = 0;
+    std::vector<TestPrimitive> prims;
+    std::vector<int32_t> indices;
+    std::vector<std::string> customAttributes;
+    std::array<float, 6> bounds = {0, 0, 0, 0, 0, 0};
+    std::map<std::string, std::vector<int32_t>> groups;
+};
+
+class TestSopVboOutput : public SOP_VBOOutput {
+public:
+    TestSopVboOutput() {}
+    virtual ~TestSopVboOutput() {}
+
+    void enableNormal() override { normalsEnabled = true; }
+    void enableColor() override { colorsEnabled = true; }
+    void enableTexCoord(int32_t numLayers = 0) override { texLayers = std::max(numLayers, 1); }
+    bool hasNormal() override { return normalsEnabled; }
+    bool hasColor() override { return colorsEnabled; }
+    bool hasTexCoord() override { return texLayers > 0; }
+    bool hasCustomAttibutes() override { return false; }
+    bool addCustomAttribute(const SOP_CustomAttribInfo &) override { return false; }
+
+    void allocVBO(int32_t numVertices, int32_t numIndices, VBOBufferMode) override {
+        positions.assign(numVertices, Position());
+        normals.assign(normalsEnabled ? numVertices : 0, Vector());
+        colors.assign(colorsEnabled ? numVertices : 0, Color());
+        texCoords.assign((size_t)numVertices * texLayers, TexCoord());
+        // Reserved up front so pointers handed out by add* stay valid.
+        indices.reserve(numIndices);
+    }
+
+    Position *getPos() override { return positions.data(); }
+    Vector *getNormals() override { return normalsEnabled ? normals.data() : nullptr; }
+    Color *getColors() override { return colorsEnabled ? colors.data() : nullptr; }
+    TexCoord *getTexCoords() override { return texLayers > 0 ? texCoords.data() : nullptr; }
+    int32_t getNumTexCoordLayers() override { return texLayers; }
+
+    int32_t *addTriangles(int32_t numTriangles) override {
+        int32_t *start = grow(numTriangles * 3);
+        for (int32_t i = 0; i < numTriangles; i++) {
+            prims.push_back({TestPrimitiveKind::Triangle, (int32_t)(start - indices.data()) + i * 3, 3});
+        }
+        return start;
+    }
+
+    int32_t *addParticleSystem(int32_t numParticles) override {
+        int32_t *start = grow(numParticles);
+        prims.push_back({TestPrimitiveKind::ParticleSystem, (int32_t)(start - indices.data()), numParticles});
+        return start;
+    }
+
+    int32_t *addLines(int32_t numIndices) override {
+        int32_t *start = grow(numIndices);
+        prims.push_back({TestPrimitiveKind::Line, (int32_t)(start - indices.data()), numIndices});
+        return start;
+    }
+
+    bool getCustomAttribute(SOP_CustomAttribData *, const char *) override { return false; }
+
+    void updateComplete() override { complete = true; }
+
+    bool setBoundingBox(const BoundingBox &) override { return true; }
+
+    void *asOutput() { return static_cast<SOP_VBOOutput *>(this); }
+
+    bool isComplete() const { return complete; }
+    int32_t numPoints() const { return (int32_t)positions.size(); }
+    const float *pointData() const { return reinterpret_cast<const float *>(positions.data()); }
+    const float *normalData() const { return reinterpret_cast<const float *>(normals.data()); }
+    int32_t numNormals() const { return (int32_t)normals.size(); }
+    const float *colorData() const { return reinterpret_cast<const float *>(colors.data()); }
+    int32_t numColors() const { return (int32_t)colors.size(); }
+    const float *texCoordData() const { return reinterpret_cast<const float *>(texCoords.data()); }
+    int32_t numTexCoords() const { return (int32_t)texCoords.size(); }
+    int32_t numTexCoordLayers() const { return texLayers; }
+    int32_t numPrims() const { return (int32_t)prims.size(); }
+    TestPrimitiveKind primitiveKind(int32_t i) const { return prims[i].kind; }
+    int32_t primitiveOffset(int32_t i) const { return prims[i].offset; }
+    int32_t primitiveCount(int32_t i) const { return prims[i].count; }
+    const int32_t *indexData() const { return indices.data(); }
+
+private:
+    int32_t *grow(int32_t count) {
+        size_t start = indices.size();
+        if (start + count > indices.capacity()) {
+            return nullptr;
+        }
+        indices.resize(start + count, 0);
+        return indices.data() + start;
+    }
+
+    bool normalsEnabled = false;
+    bool colorsEnabled = false;
+    bool complete = false;
+    int32_t texLayers = 0;
+    std::vector<Position> positions;
+    std::vector<Vector> normals;
+    std::vector<Color> colors;
+    std::vector<TexCoord> texCoords;
+    std::vector<TestPrimitive> prims;
+    std::vector<int32_t> indices;
+};
+
+class TestTopBuffer : public TOP_Buffer {
+public:
+    TestTopBuffer(uint64_t bufSize, TOP_BufferFlags bufFlags) : storage(bufSize) {
+        data = storage.data();
+        size = bufSize;
+        flags = bufFlags;
+    }
+
+protected:
+    virtual ~TestTopBuffer() {}
+
+    void acquire() override { refCount++; }
+    void release() override {
+        if (--refCount == 0) {
+            delete this;
+        }
+    }
+    void reserved0() override {}
+    void reserved1() override {}
+    void reserved2() override {}
+    void reserved3() override {}
+    void reserved4() override {}
+
+private:
+    std::vector<uint8_t> storage;
+    int32_t refCount = 0;
+};
+
+struct TestUpload {
+    std::vector<uint8_t> data;
+    OP_TextureDesc textureDesc;
+    uint32_t colorBufferIndex;
+};
+
+class TestTopOutput : public TOP_Output {
+public:
+    TestTopOutput() {}
+    virtual ~TestTopOutput() {}
+
+    void uploadBuffer(OP_SmartRef<TOP_Buffer> *buf, const TOP_UploadInfo &info, void *) override {
+        TestUpload upload;
+        if (buf && *buf) {
+            const uint8_t *start = static_cast<const uint8_t *>((*buf)->data);
+            uint64_t size = (*buf)->size;
+            if (info.bufferOffset < size) {
+                upload.data.assign(start + info.bufferOffset, start + size);
+            }
+            buf->release();
+        }
+        upload.textureDesc = info.textureDesc;
+        upload.colorBufferIndex = info.colorBufferIndex;
+        uploads.push_back(upload);
+    }
+
+    const OP_CUDAArrayInfo *createCUDAArray(const TOP_CUDAOutputInfo &, void *) override { return nullptr; }
+
+    void *asOutput() { return static_cast<TOP_Output *>(this); }
+
+    int32_t numUploads() const { return (int32_t)uploads.size(); }
+    const uint8_t *uploadData(int32_t i) const { return uploads[i].data.data(); }
+    uint64_t uploadSize(int32_t i) const { return uploads[i].data.size(); }
+    uint32_t uploadWidth(int32_t i) const { return uploads[i].textureDesc.width; }
+    uint32_t uploadHeight(int32_t i) const { return uploads[i].textureDesc.height; }
+    OP_PixelFormat uploadPixelFormat(int32_t i) const { return uploads[i].textureDesc.pixelFormat; }
+    uint32_t uploadColorBufferIndex(int32_t i) const { return uploads[i].colorBufferIndex; }
+
+private:
+    void reserved0() override {}
+    void reserved1() override {}
+    void reserved2() override {}
+    void reserved3() override {}
+    void reserved4() override {}
+    void reserved5() override {}
+    void reserved6() override {}
+    void reserved7() override {}
+    void reserved8() override {}
+    void reserved9() override {}
+
+    std::vector<TestUpload> uploads;
+};
+
+class TestTopContext : public TOP_Context {
+public:
+    TestTopContext() {}
+    virtual ~TestTopContext() {}
+
+    OP_SmartRef<TOP_Buffer> createOutputBuffer(uint64_t size, TOP_BufferFlags flags, void *) override {
+        return OP_SmartRef<TOP_Buffer>(new TestTopBuffer(size, flags));
+    }
+
+    void returnBuffer(OP_SmartRef<TOP_Buffer> *buf) override {
+        if (buf) {
+            buf->release();
+        }
+    }
+
+    int getCUDADeviceIndex(void *) override { return -1; }
+
+    PyObject *createArgumentsTuple(int, void *) override { return nullptr; }
+    PyObject *callPythonCallback(const char *, PyObject *, PyObject *, void *) override { return nullptr; }
+    bool beginCUDAOperations(void *) override { return false; }
+    void endCUDAOperations(void *) override {}
+
+    void *asContext() { return static_cast<TOP_Context *>(this); }
+
+protected:
+    void reserved1() override {}
+    void reserved2() override {}
+    void reserved3() override {}
+    void reserved4() override {}
+    void reserved5() override {}
+    void reserved6() override {}
+    void reserved7() override {}
+    void reserved8() override {}
+    void reserved9() override {}
+
+    void *reservedFunc0() override { return nullptr; }
+    void *reservedFunc1() override { return nullptr; }
+    void *reservedFunc2() override { return nullptr; }
+    void *reservedFunc3() override { return nullptr; }
+    void *reservedFunc4() override { return nullptr; }
+    void *reservedFunc5() override { return nullptr; }
+    void *reservedFunc6() override { return nullptr; }
+    void *reservedFunc7() override { return nullptr; }
+    void *reservedFunc8() override { return nullptr; }
+    void *reservedFunc9() override { return nullptr; }
+    void *reservedFunc10() override { return nullptr; }
+    void *reservedFunc11() override { return nullptr; }
+    void *reservedFunc12() override { return nullptr; }
+    void *reservedFunc13() override { return nullptr; }
+    void *reservedFunc14() override { return nullptr; }
+};
+
+#endif //TD_RS_TESTHOST_H
@@ -0,0 +1,305 @@
+use crate::host::harness_common;
+use crate::TestHost;
+use autocxx::prelude::*;
+use std::ffi::c_char;
+use std::pin::Pin;
+use td_rs_base::cxx::OP_Inputs;
+use td_rs_chop::cxx::{
+    CHOP_GeneralInfo, CHOP_Output, CHOP_OutputInfo, RustChopPluginImpl, RustChopPlugin_methods,
+};
+use td_rs_chop::{Chop, OpNew};
+
+/// The channels produced by a CHOP cook.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CookedChop {
+    pub sample_rate: f32,
+    pub start_index: usize,
+    pub channels: Vec<(String, Vec<f32>)>,
+}
+
+impl CookedChop {
+    pub fn num_channels(&self) -> usize {
+        self.channels.len()
+    }
+
+    pub fn num_samples(&self) -> usize {
+        self.channels.first().map(|(_, s)| s.len()).unwrap_or(0)
+    }
+
+    /// Look up a channel by name.
+    pub fn channel(&self, name: &str) -> Option<&[f32]> {
+        self.channels
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, s)| s.as_slice())
+    }
+}
+
+/// Drives a [`Chop`] through the same glue TouchDesigner calls into.
+pub struct ChopHarness {
+    plugin: RustChopPluginImpl,
+    host: TestHost,
+}
+
+impl ChopHarness {
+    /// Create the operator and register its parameters.
+    pub fn new<T: Chop + OpNew + 'static>() -> Self {
+        let mut host = TestHost::new();
+        let mut plugin = RustChopPluginImpl::new(Box::new(T::new(host.node_info())));
+        plugin.setupParameters(host.parameter_manager());
+        Self { plugin, host }
+    }
+
+    /// Run a cook in the order TouchDesigner does: general info, output info,
+    /// channel names, execute.
+    ///
+    /// The output info starts out filled in from the first input, as
+    /// TouchDesigner does. If the operator doesn't provide output info, the
+    /// output matches the input selected by `input_match_index`, or is empty
+    /// without inputs.
+    pub fn cook(&mut self) -> CookedChop {
+        self.host.begin_cook();
+        let inputs = self.host.op_inputs();
+
+        let mut general: CHOP_GeneralInfo = unsafe { std::mem::zeroed() };
+        self.plugin.getGeneralInfo(Pin::new(&mut general), inputs);
+
+        let mut out_info = Self::default_output_info(inputs);
+        let (num_channels, num_samples, sample_rate, start_index, names) = if self
+            .plugin
+            .getOutputInfo(Pin::new(&mut out_info), inputs)
+        {
+            let names = (0..out_info.numChannels)
+                .map(|i| {
+                    let mut name = crate::cxx::TestString::new().within_unique_ptr();
+                    self.plugin
+                        .getChannelName(i, name.pin_mut().asString(), inputs);
+                    crate::host::from_c_str(name.get())
+                })
+                .collect::<Vec<_>>();
+            (
+                out_info.numChannels as usize,
+                out_info.numSamples as usize,
+                out_info.sampleRate,
+                out_info.startIndex,
+                names,
+            )
+        } else {
+            let matched = inputs.getInputCHOP(general.inputMatchIndex);
+            if matched.is_null() {
+                (0, 0, out_info.sampleRate, 0, vec![])
+            } else {
+                let matched = unsafe { &*matched };
+                let names = (0..matched.numChannels)
+                    .map(|i| crate::host::from_c_str(unsafe { *matched.nameData.add(i as usize) }))
+                    .collect();
+                (
+                    matched.numChannels as usize,
+                    matched.numSamples as usize,
+                    matched.sampleRate as f32,
+                    matched.startIndex as u32,
+                    names,
+                )
+            }
+        };
+
+        let mut data = vec![vec![0.0f32; num_samples]; num_channels];
+        let mut channel_ptrs = data.iter_mut().map(|c| c.as_mut_ptr()).collect::<Vec<_>>();
+        let c_names = names
+            .iter()
+            .map(|n| crate::host::c_string(n))
+            .collect::<Vec<_>>();
+        let mut name_ptrs = c_names
+            .iter()
+            .map(|n| n.as_ptr())
+            .collect::<Vec<*const c_char>>();
+        let mut output = CHOP_Output {
+            numChannels: num_channels as i32,
+            numSamples: num_samples as i32,
+            sampleRate: sample_rate,
+            startIndex: start_index,
+            names: name_ptrs.as_mut_ptr(),
+            channels: channel_ptrs.as_mut_ptr(),
+            reserved: [0; 20],
+        };
+        self.plugin.execute(Pin::new(&mut output), inputs);
+
+        CookedChop {
+            sample_rate,
+            start_index: start_index as usize,
+            channels: names.into_iter().zip(data).collect(),
+        }
+    }
+
+    /// The output info TouchDesigner passes to `getOutputInfo`: the shape of
+    /// the first input, or no channels at the timeline rate.
+    fn default_output_info(inputs: &OP_Inputs) -> CHOP_OutputInfo {
+        let mut info: CHOP_OutputInfo = unsafe { std::mem::zeroed() };
+        let time = inputs.getTimeInfo();
+        info.sampleRate = if time.is_null() {
+            60.0
+        } else {
+            unsafe { (*time).rate as f32 }
+        };
+        let first = inputs.getInputCHOP(0);
+        if !first.is_null() {
+            let first = unsafe { &*first };
+            info.numChannels = first.numChannels;
+            info.numSamples = first.numSamples;
+            info.startIndex = first.startIndex as u32;
+            info.sampleRate = first.sampleRate as f32;
+        }
+        info
+    }
+}
+
+harness_common!(ChopHarness);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FakeChop;
+    use td_rs_chop::{ChopInput, ChopOutput, ChopOutputInfo, NodeInfo, Op, OperatorInputs};
+
+    /// Two channels of four samples, whatever the inputs.
+    struct Ramp;
+
+    impl OpNew for Ramp {
+        fn new(_info: NodeInfo) -> Self {
+            Self
+        }
+    }
+
+    impl Op for Ramp {}
+
+    impl Chop for Ramp {
+        fn output_info(&self, _inputs: &OperatorInputs<ChopInput>) -> Option<ChopOutputInfo> {
+            Some(ChopOutputInfo {
+                num_channels: 2,
+                num_samples: 4,
+                sample_rate: 30.0,
+                start_index: 8,
+            })
+        }
+
+        fn channel_name(&self, index: usize, _inputs: &OperatorInputs<ChopInput>) -> String {
+            format!("ramp{}", index + 1)
+        }
+
+        fn execute(&mut self, output: &mut ChopOutput, _inputs: &OperatorInputs<ChopInput>) {
+            for c in 0..output.num_channels() {
+                for (i, sample) in output.channel_mut(c).iter_mut().enumerate() {
+                    *sample = (c * 10 + i) as f32;
+                }
+            }
+        }
+    }
+
+    /// Copies its first input, leaving the output to match it.
+    struct Passthrough;
+
+    impl OpNew for Passthrough {
+        fn new(_info: NodeInfo) -> Self {
+            Self
+        }
+    }
+
+    impl Op for Passthrough {}
+
+    impl Chop for Passthrough {
+        fn execute(&mut self, output: &mut ChopOutput, inputs: &OperatorInputs<ChopInput>) {
+            if let Some(input) = inputs.input(0) {
+                for c in 0..output.num_channels() {
+                    output.channel_mut(c).copy_from_slice(input.channel(c));
+                }
+            }
+        }
+    }
+
+    /// Outputs the timing of each cook.
+    struct Clock;
+
+    impl OpNew for Clock {
+        fn new(_info: NodeInfo) -> Self {
+            Self
+        }
+    }
+
+    impl Op for Clock {}
+
+    impl Chop for Clock {
+        fn output_info(&self, _inputs: &OperatorInputs<ChopInput>) -> Option<ChopOutputInfo> {
+            Some(ChopOutputInfo {
+                num_channels: 4,
+                num_samples: 1,
+                ..Default::default()
+            })
+        }
+
+        fn channel_name(&self, index: usize, _inputs: &OperatorInputs<ChopInput>) -> String {
+            ["absframe", "frame", "rate", "deltaframes"][index].to_string()
+        }
+
+        fn execute(&mut self, output: &mut ChopOutput, inputs: &OperatorInputs<ChopInput>) {
+            let time = inputs.time_info();
+            output[0][0] = time.abs_frame as f32;
+            output[1][0] = time.frame as f32;
+            output[2][0] = time.rate as f32;
+            output[3][0] = time.delta_frames as f32;
+        }
+    }
+
+    fn times(out: &CookedChop) -> Vec<f32> {
+        out.channels.iter().map(|(_, samples)| samples[0]).collect()
+    }
+
+    #[test]
+    fn test_time_advances_per_cook() {
+        let mut harness = ChopHarness::new::<Clock>();
+        assert_eq!(times(&harness.cook()), [0.0, 1.0, 60.0, 0.0]);
+        assert_eq!(times(&harness.cook()), [1.0, 2.0, 60.0, 1.0]);
+
+        // Two frames dropped on a 30 fps timeline
+        harness.set_time(100, 41.0, 30.0, 3.0);
+        assert_eq!(times(&harness.cook()), [100.0, 41.0, 30.0, 3.0]);
+        assert_eq!(times(&harness.cook()), [101.0, 42.0, 30.0, 1.0]);
+    }
+
+    #[test]
+    fn test_output_info_sizes_output() {
+        let mut harness = ChopHarness::new::<Ramp>();
+        let out = harness.cook();
+        assert_eq!(out.num_channels(), 2);
+        assert_eq!(out.num_samples(), 4);
+        assert_eq!(out.sample_rate, 30.0);
+        assert_eq!(out.start_index, 8);
+        assert_eq!(out.channel("ramp1"), Some(&[0.0, 1.0, 2.0, 3.0][..]));
+        assert_eq!(out.channel("ramp2"), Some(&[10.0, 11.0, 12.0, 13.0][..]));
+    }
+
+    #[test]
+    fn test_output_matches_input() {
+        let mut harness = ChopHarness::new::<Passthrough>();
+        let out = harness.cook();
+        assert_eq!(out.num_channels(), 0);
+
+        harness.set_input_chop(
+            0,
+            FakeChop::new("/project1/in")
+                .sample_rate(120.0)
+                .start_index(30.0)
+                .channel("tx", [1.0, 2.0, 3.0])
+                .channel("ty", [4.0, 5.0, 6.0]),
+        );
+        let out = harness.cook();
+        assert_eq!(out.sample_rate, 120.0);
+        assert_eq!(out.start_index, 30);
+        assert_eq!(
+            out.channels,
+            [
+                ("tx".to_string(), vec![1.0, 2.0, 3.0]),
+                ("ty".to_string(), vec![4.0, 5.0, 6.0]),
+            ]
+        );
+    }
+}
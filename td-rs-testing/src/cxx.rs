@@ -0,0 +1,28 @@
+#![allow(non_snake_case)]
+#![allow(ambiguous_glob_reexports)]
+
+use autocxx::prelude::*;
+
+include_cpp! {
+    #include "TestHost.h"
+    safety!(unsafe)
+    extern_cpp_type!("TD::OP_Inputs", td_rs_base::cxx::OP_Inputs)
+    extern_cpp_type!("TD::OP_ParameterManager", td_rs_base::cxx::OP_ParameterManager)
+    extern_cpp_type!("TD::OP_String", td_rs_base::cxx::OP_String)
+    extern_cpp_type!("TD::OP_BuildDynamicMenuInfo", td_rs_base::cxx::OP_BuildDynamicMenuInfo)
+    extern_cpp_type!("TD::OP_PixelFormat", td_rs_base::cxx::OP_PixelFormat)
+    pod!("TD::OP_PixelFormat")
+    generate!("TestString")
+    generate!("TestParameterManager")
+    generate!("TestInputs")
+    generate!("TestDynamicMenuInfo")
+    generate!("TestDatOutput")
+    generate!("TestSopOutput")
+    generate!("TestSopVboOutput")
+    generate!("TestTopOutput")
+    generate!("TestTopContext")
+    generate_pod!("TestPrimitiveKind")
+}
+
+pub use autocxx::c_void;
+pub use ffi::*;
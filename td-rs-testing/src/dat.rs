@@ -0,0 +1,77 @@
+use crate::host::{from_c_str, harness_common};
+use crate::TestHost;
+use autocxx::prelude::*;
+use std::pin::Pin;
+use td_rs_dat::cxx::{DAT_GeneralInfo, DAT_Output, RustDatPluginImpl, RustDatPlugin_methods};
+use td_rs_dat::{Dat, OpNew};
+
+/// The contents produced by a DAT cook.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CookedDat {
+    Table(Vec<Vec<String>>),
+    Text(String),
+}
+
+impl CookedDat {
+    /// Get a cell of a table DAT.
+    pub fn cell(&self, row: usize, col: usize) -> Option<&str> {
+        match self {
+            CookedDat::Table(rows) => rows.get(row)?.get(col).map(String::as_str),
+            CookedDat::Text(_) => None,
+        }
+    }
+
+    /// Get the contents of a text DAT.
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            CookedDat::Table(_) => None,
+            CookedDat::Text(text) => Some(text),
+        }
+    }
+}
+
+/// Drives a [`Dat`] through the same glue TouchDesigner calls into.
+pub struct DatHarness {
+    plugin: RustDatPluginImpl,
+    host: TestHost,
+}
+
+impl DatHarness {
+    /// Create the operator and register its parameters.
+    pub fn new<T: Dat + OpNew + 'static>() -> Self {
+        let mut host = TestHost::new();
+        let mut plugin = RustDatPluginImpl::new(Box::new(T::new(host.node_info())));
+        plugin.setupParameters(host.parameter_manager());
+        Self { plugin, host }
+    }
+
+    /// Run a cook in the order TouchDesigner does: general info, execute.
+    pub fn cook(&mut self) -> CookedDat {
+        self.host.begin_cook();
+        let inputs = self.host.op_inputs();
+
+        let mut general: DAT_GeneralInfo = unsafe { std::mem::zeroed() };
+        self.plugin.getGeneralInfo(Pin::new(&mut general), inputs);
+
+        let mut output = crate::cxx::TestDatOutput::new().within_unique_ptr();
+        let out =
+            unsafe { Pin::new_unchecked(&mut *(output.pin_mut().asOutput() as *mut DAT_Output)) };
+        self.plugin.execute(out, inputs);
+
+        if output.isTable() {
+            CookedDat::Table(
+                (0..output.numRows())
+                    .map(|r| {
+                        (0..output.numCols())
+                            .map(|c| from_c_str(output.cell(r, c)))
+                            .collect()
+                    })
+                    .collect(),
+            )
+        } else {
+            CookedDat::Text(from_c_str(output.getText()))
+        }
+    }
+}
+
+harness_common!(DatHarness);
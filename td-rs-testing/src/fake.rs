@@ -0,0 +1,233 @@
+use td_rs_base::object::Matrix4;
+use td_rs_base::top::PixelFormat;
+
+/// A CHOP to connect to the operator under test.
+#[derive(Debug, Clone)]
+pub struct FakeChop {
+    pub(crate) path: String,
+    pub(crate) sample_rate: f64,
+    pub(crate) start_index: f64,
+    pub(crate) channels: Vec<(String, Vec<f32>)>,
+}
+
+impl FakeChop {
+    /// An empty CHOP at 60 samples per second.
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            sample_rate: 60.0,
+            start_index: 0.0,
+            channels: vec![],
+        }
+    }
+
+    pub fn sample_rate(mut self, sample_rate: f64) -> Self {
+        self.sample_rate = sample_rate;
+        self
+    }
+
+    pub fn start_index(mut self, start_index: f64) -> Self {
+        self.start_index = start_index;
+        self
+    }
+
+    /// Add a channel. All channels must have the same number of samples.
+    pub fn channel(mut self, name: &str, samples: impl Into<Vec<f32>>) -> Self {
+        let samples = samples.into();
+        if let Some((_, first)) = self.channels.first() {
+            assert_eq!(
+                first.len(),
+                samples.len(),
+                "all channels of a CHOP must have the same length"
+            );
+        }
+        self.channels.push((name.to_string(), samples));
+        self
+    }
+
+    pub(crate) fn num_samples(&self) -> usize {
+        self.channels.first().map(|(_, s)| s.len()).unwrap_or(0)
+    }
+}
+
+/// A DAT to connect to the operator under test.
+#[derive(Debug, Clone)]
+pub struct FakeDat {
+    pub(crate) path: String,
+    pub(crate) is_table: bool,
+    pub(crate) rows: Vec<Vec<String>>,
+}
+
+impl FakeDat {
+    /// A table DAT. Short rows are padded with empty cells.
+    pub fn table<R, C>(path: &str, rows: R) -> Self
+    where
+        R: IntoIterator<Item = C>,
+        C: IntoIterator,
+        C::Item: ToString,
+    {
+        let mut rows = rows
+            .into_iter()
+            .map(|row| row.into_iter().map(|c| c.to_string()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let cols = rows.iter().map(Vec::len).max().unwrap_or(0);
+        for row in &mut rows {
+            row.resize(cols, String::new());
+        }
+        Self {
+            path: path.to_string(),
+            is_table: true,
+            rows,
+        }
+    }
+
+    /// A text DAT.
+    pub fn text(path: &str, text: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            is_table: false,
+            rows: vec![vec![text.to_string()]],
+        }
+    }
+
+    pub(crate) fn num_rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub(crate) fn num_cols(&self) -> usize {
+        self.rows.first().map(Vec::len).unwrap_or(0)
+    }
+}
+
+/// A SOP to connect to the operator under test.
+#[derive(Debug, Clone, Default)]
+pub struct FakeSop {
+    pub(crate) path: String,
+    pub(crate) points: Vec<[f32; 3]>,
+    pub(crate) normals: Vec<[f32; 3]>,
+    pub(crate) colors: Vec<[f32; 4]>,
+    pub(crate) polygons: Vec<(Vec<u32>, bool)>,
+}
+
+impl FakeSop {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn point(mut self, position: [f32; 3]) -> Self {
+        self.points.push(position);
+        self
+    }
+
+    /// Per-point normals, in point order.
+    pub fn normals(mut self, normals: impl Into<Vec<[f32; 3]>>) -> Self {
+        self.normals = normals.into();
+        self
+    }
+
+    /// Per-point colors, in point order.
+    pub fn colors(mut self, colors: impl Into<Vec<[f32; 4]>>) -> Self {
+        self.colors = colors.into();
+        self
+    }
+
+    /// Add a closed polygon over the given point indices.
+    pub fn polygon(mut self, indices: impl Into<Vec<u32>>) -> Self {
+        self.polygons.push((indices.into(), true));
+        self
+    }
+
+    /// Add an open polygon (a polyline) over the given point indices.
+    pub fn polyline(mut self, indices: impl Into<Vec<u32>>) -> Self {
+        self.polygons.push((indices.into(), false));
+        self
+    }
+}
+
+/// A TOP to connect to the operator under test.
+///
+/// Downloads return `data` as-is; the pixel format and vertical flip requested
+/// in the download options are not applied.
+#[derive(Debug, Clone)]
+pub struct FakeTop {
+    pub(crate) path: String,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) pixel_format: PixelFormat,
+    pub(crate) data: Vec<u8>,
+}
+
+impl FakeTop {
+    pub fn new(
+        path: &str,
+        width: u32,
+        height: u32,
+        pixel_format: PixelFormat,
+        data: impl Into<Vec<u8>>,
+    ) -> Self {
+        Self {
+            path: path.to_string(),
+            width,
+            height,
+            pixel_format,
+            data: data.into(),
+        }
+    }
+}
+
+const IDENTITY: Matrix4 = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+/// An object COMP, e.g. a Camera or Geometry COMP, to reference from the
+/// operator under test.
+#[derive(Debug, Clone)]
+pub struct FakeObject {
+    pub(crate) path: String,
+    pub(crate) world: Matrix4,
+    pub(crate) local: Matrix4,
+    pub(crate) total_cooks: i64,
+}
+
+impl FakeObject {
+    /// An object at the origin, with identity transforms.
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            world: IDENTITY,
+            local: IDENTITY,
+            total_cooks: 0,
+        }
+    }
+
+    pub fn world(mut self, world: Matrix4) -> Self {
+        self.world = world;
+        self
+    }
+
+    pub fn local(mut self, local: Matrix4) -> Self {
+        self.local = local;
+        self
+    }
+
+    /// Move the object to `[x, y, z]` in both world and local space.
+    pub fn translate(mut self, [x, y, z]: [f64; 3]) -> Self {
+        for m in [&mut self.world, &mut self.local] {
+            m[0][3] = x;
+            m[1][3] = y;
+            m[2][3] = z;
+        }
+        self
+    }
+
+    pub fn total_cooks(mut self, total_cooks: i64) -> Self {
+        self.total_cooks = total_cooks;
+        self
+    }
+}
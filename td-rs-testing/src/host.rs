@@ -0,0 +1,592 @@
+use crate::cxx;
+use crate::fake::{FakeChop, FakeDat, FakeObject, FakeSop, FakeTop};
+use autocxx::prelude::*;
+use std::ffi::{c_char, CStr, CString};
+use std::pin::Pin;
+use td_rs_base::cxx::{OP_Inputs, OP_NodeInfo, OP_ParameterManager, OP_PixelFormat};
+use td_rs_base::NodeInfo;
+
+pub(crate) fn c_string(s: &str) -> CString {
+    CString::new(s).expect("string contains a nul byte")
+}
+
+pub(crate) fn from_c_str(s: *const c_char) -> String {
+    if s.is_null() {
+        String::new()
+    } else {
+        unsafe { CStr::from_ptr(s) }.to_string_lossy().into_owned()
+    }
+}
+
+/// A parameter as it was registered by the operator during `setupParameters`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegisteredParam {
+    /// The `OP_ParameterManager::append*` call used, e.g. `"Float"`, `"XYZ"` or `"Menu"`.
+    pub kind: String,
+    pub name: String,
+    pub label: String,
+    pub page: String,
+    /// The number of components of a numeric parameter.
+    pub size: usize,
+    pub default: [f64; 4],
+    pub min: [f64; 4],
+    pub max: [f64; 4],
+    pub default_string: String,
+    pub menu_names: Vec<String>,
+    pub menu_labels: Vec<String>,
+}
+
+/// The host side of a headless cook: parameter values, connected inputs and
+/// the parameters the operator registered.
+///
+/// Parameters start out at the defaults they were registered with, the same
+/// as a freshly placed operator in TouchDesigner.
+pub struct TestHost {
+    inputs: UniquePtr<cxx::TestInputs>,
+}
+
+impl Default for TestHost {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestHost {
+    pub fn new() -> Self {
+        Self {
+            inputs: cxx::TestInputs::new().within_unique_ptr(),
+        }
+    }
+
+    pub(crate) fn op_inputs(&self) -> &OP_Inputs {
+        self.inputs.asInputs()
+    }
+
+    pub(crate) fn parameter_manager(&mut self) -> Pin<&mut OP_ParameterManager> {
+        self.inputs.pin_mut().parameterManager()
+    }
+
+    /// Node info for a new operator instance. The underlying struct is leaked,
+    /// as operators may hold on to it for their whole lifetime.
+    pub(crate) fn node_info(&self) -> NodeInfo {
+        let mut info: OP_NodeInfo = unsafe { std::mem::zeroed() };
+        info.opPath = c"/project1/test".as_ptr();
+        info.opId = 1;
+        info.pluginPath = c"".as_ptr();
+        NodeInfo::new(Box::leak(Box::new(info)))
+    }
+
+    /// All parameters registered by the operator, in registration order.
+    pub fn params(&self) -> Vec<RegisteredParam> {
+        let manager = self.inputs.parameters();
+        (0..manager.numParameters())
+            .map(|i| RegisteredParam {
+                kind: from_c_str(manager.parameterType(i)),
+                name: from_c_str(manager.parameterName(i)),
+                label: from_c_str(manager.parameterLabel(i)),
+                page: from_c_str(manager.parameterPage(i)),
+                size: manager.parameterSize(i) as usize,
+                default: std::array::from_fn(|c| manager.parameterDefault(i, c as i32)),
+                min: std::array::from_fn(|c| manager.parameterMin(i, c as i32)),
+                max: std::array::from_fn(|c| manager.parameterMax(i, c as i32)),
+                default_string: from_c_str(manager.parameterDefaultString(i)),
+                menu_names: (0..manager.parameterNumMenuItems(i))
+                    .map(|item| from_c_str(manager.parameterMenuName(i, item)))
+                    .collect(),
+                menu_labels: (0..manager.parameterNumMenuItems(i))
+                    .map(|item| from_c_str(manager.parameterMenuLabel(i, item)))
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Look up a registered parameter by name.
+    pub fn param(&self, name: &str) -> Option<RegisteredParam> {
+        self.params().into_iter().find(|p| p.name == name)
+    }
+
+    pub fn set_float(&mut self, name: &str, value: f64) {
+        self.set_floats(name, &[value]);
+    }
+
+    /// Set the components of a multi-value parameter, e.g. an XYZ or RGBA.
+    pub fn set_floats(&mut self, name: &str, values: &[f64]) {
+        assert!(values.len() <= 4, "parameters have at most 4 components");
+        let name = c_string(name);
+        for (i, v) in values.iter().enumerate() {
+            unsafe {
+                self.inputs
+                    .pin_mut()
+                    .setParDouble(name.as_ptr(), i as i32, *v);
+            }
+        }
+    }
+
+    pub fn set_int(&mut self, name: &str, value: i32) {
+        self.set_ints(name, &[value]);
+    }
+
+    pub fn set_ints(&mut self, name: &str, values: &[i32]) {
+        let values = values.iter().map(|v| *v as f64).collect::<Vec<_>>();
+        self.set_floats(name, &values);
+    }
+
+    pub fn set_toggle(&mut self, name: &str, value: bool) {
+        self.set_int(name, value as i32);
+    }
+
+    pub fn set_string(&mut self, name: &str, value: &str) {
+        let name = c_string(name);
+        let value = c_string(value);
+        unsafe {
+            self.inputs
+                .pin_mut()
+                .setParString(name.as_ptr(), value.as_ptr());
+        }
+    }
+
+    /// Select a menu item by name. The menu must have been registered by the
+    /// operator so the item's index can be resolved.
+    pub fn set_menu(&mut self, name: &str, item: &str) {
+        let param = self
+            .param(name)
+            .unwrap_or_else(|| panic!("no parameter named {name}"));
+        let index = param
+            .menu_names
+            .iter()
+            .position(|n| n == item)
+            .unwrap_or_else(|| panic!("{name} has no menu item {item}"));
+        self.set_int(name, index as i32);
+        self.set_string(name, item);
+    }
+
+    /// Whether the operator has left the parameter enabled via `enable_param`.
+    pub fn is_enabled(&self, name: &str) -> bool {
+        let name = c_string(name);
+        unsafe { self.inputs.isParEnabled(name.as_ptr()) }
+    }
+
+    /// Set the timing of the next cook, as reported by
+    /// `OperatorInputs::time_info`. Otherwise the first cook is at frame 1 of a
+    /// 60 fps timeline with no frames elapsed, and every later cook is one
+    /// frame after the previous one.
+    pub fn set_time(&mut self, abs_frame: i64, frame: f64, rate: f64, delta_frames: f64) {
+        self.inputs
+            .pin_mut()
+            .setTimeInfo(abs_frame, frame, rate, delta_frames);
+    }
+
+    /// Advance the time of the host for a cook.
+    pub(crate) fn begin_cook(&mut self) {
+        self.inputs.pin_mut().beginCook();
+    }
+
+    fn add_chop_fake(&mut self, chop: &FakeChop) -> i32 {
+        let path = c_string(&chop.path);
+        let id = unsafe {
+            self.inputs.pin_mut().addCHOP(
+                path.as_ptr(),
+                chop.num_samples() as i32,
+                chop.sample_rate,
+                chop.start_index,
+            )
+        };
+        for (name, samples) in &chop.channels {
+            let name = c_string(name);
+            unsafe {
+                self.inputs
+                    .pin_mut()
+                    .addCHOPChannel(id, name.as_ptr(), samples.as_ptr());
+            }
+        }
+        id
+    }
+
+    /// Make a CHOP available by path only, e.g. for `getCHOP` lookups.
+    pub fn add_chop(&mut self, chop: FakeChop) {
+        self.add_chop_fake(&chop);
+    }
+
+    /// Connect a CHOP to the given input of the operator.
+    pub fn set_input_chop(&mut self, index: usize, chop: FakeChop) {
+        let id = self.add_chop_fake(&chop);
+        self.inputs.pin_mut().setInputCHOP(index as i32, id);
+    }
+
+    /// Point a CHOP parameter at the given CHOP.
+    pub fn set_chop_param(&mut self, name: &str, chop: FakeChop) {
+        let id = self.add_chop_fake(&chop);
+        let name = c_string(name);
+        unsafe { self.inputs.pin_mut().setParCHOP(name.as_ptr(), id) };
+    }
+
+    fn add_dat_fake(&mut self, dat: &FakeDat) -> i32 {
+        let path = c_string(&dat.path);
+        let id = unsafe {
+            self.inputs.pin_mut().addDAT(
+                path.as_ptr(),
+                dat.num_rows() as i32,
+                dat.num_cols() as i32,
+                dat.is_table,
+            )
+        };
+        for (r, row) in dat.rows.iter().enumerate() {
+            for (c, cell) in row.iter().enumerate() {
+                let cell = c_string(cell);
+                unsafe {
+                    self.inputs
+                        .pin_mut()
+                        .setDATCell(id, r as i32, c as i32, cell.as_ptr());
+                }
+            }
+        }
+        id
+    }
+
+    /// Make a DAT available by path only, e.g. for `getDAT` lookups.
+    pub fn add_dat(&mut self, dat: FakeDat) {
+        self.add_dat_fake(&dat);
+    }
+
+    /// Connect a DAT to the given input of the operator.
+    pub fn set_input_dat(&mut self, index: usize, dat: FakeDat) {
+        let id = self.add_dat_fake(&dat);
+        self.inputs.pin_mut().setInputDAT(index as i32, id);
+    }
+
+    /// Point a DAT parameter at the given DAT.
+    pub fn set_dat_param(&mut self, name: &str, dat: FakeDat) {
+        let id = self.add_dat_fake(&dat);
+        let name = c_string(name);
+        unsafe { self.inputs.pin_mut().setParDAT(name.as_ptr(), id) };
+    }
+
+    fn add_sop_fake(&mut self, sop: &FakeSop) -> i32 {
+        let path = c_string(&sop.path);
+        let id = unsafe { self.inputs.pin_mut().addSOP(path.as_ptr()) };
+        for [x, y, z] in &sop.points {
+            self.inputs.pin_mut().addSOPPoint(id, *x, *y, *z);
+        }
+        for (i, [x, y, z]) in sop.normals.iter().enumerate() {
+            self.inputs.pin_mut().setSOPNormal(id, i as i32, *x, *y, *z);
+        }
+        for (i, [r, g, b, a]) in sop.colors.iter().enumerate() {
+            self.inputs
+                .pin_mut()
+                .setSOPColor(id, i as i32, *r, *g, *b, *a);
+        }
+        for (indices, closed) in &sop.polygons {
+            let indices = indices.iter().map(|i| *i as i32).collect::<Vec<_>>();
+            unsafe {
+                self.inputs.pin_mut().addSOPPrimitive(
+                    id,
+                    indices.as_ptr(),
+                    indices.len() as i32,
+                    *closed,
+                );
+            }
+        }
+        id
+    }
+
+    /// Make a SOP available by path only, e.g. for `getSOP` lookups.
+    pub fn add_sop(&mut self, sop: FakeSop) {
+        self.add_sop_fake(&sop);
+    }
+
+    /// Connect a SOP to the given input of the operator.
+    pub fn set_input_sop(&mut self, index: usize, sop: FakeSop) {
+        let id = self.add_sop_fake(&sop);
+        self.inputs.pin_mut().setInputSOP(index as i32, id);
+    }
+
+    /// Point a SOP parameter at the given SOP.
+    pub fn set_sop_param(&mut self, name: &str, sop: FakeSop) {
+        let id = self.add_sop_fake(&sop);
+        let name = c_string(name);
+        unsafe { self.inputs.pin_mut().setParSOP(name.as_ptr(), id) };
+    }
+
+    fn add_top_fake(&mut self, top: &FakeTop) -> i32 {
+        let path = c_string(&top.path);
+        unsafe {
+            self.inputs.pin_mut().addTOP(
+                path.as_ptr(),
+                top.width,
+                top.height,
+                OP_PixelFormat::from(&top.pixel_format),
+                top.data.as_ptr(),
+                top.data.len() as u64,
+            )
+        }
+    }
+
+    /// Make a TOP available by path only, e.g. for `getTOP` lookups.
+    pub fn add_top(&mut self, top: FakeTop) {
+        self.add_top_fake(&top);
+    }
+
+    /// Connect a TOP to the given input of the operator.
+    pub fn set_input_top(&mut self, index: usize, top: FakeTop) {
+        let id = self.add_top_fake(&top);
+        self.inputs.pin_mut().setInputTOP(index as i32, id);
+    }
+
+    /// Point a TOP parameter at the given TOP.
+    pub fn set_top_param(&mut self, name: &str, top: FakeTop) {
+        let id = self.add_top_fake(&top);
+        let name = c_string(name);
+        unsafe { self.inputs.pin_mut().setParTOP(name.as_ptr(), id) };
+    }
+
+    fn add_object_fake(&mut self, object: &FakeObject) -> i32 {
+        let path = c_string(&object.path);
+        unsafe {
+            self.inputs.pin_mut().addObject(
+                path.as_ptr(),
+                object.world.as_ptr() as *const f64,
+                object.local.as_ptr() as *const f64,
+                object.total_cooks,
+            )
+        }
+    }
+
+    /// Make an object COMP available by path only, e.g. for `getObject` lookups.
+    pub fn add_object(&mut self, object: FakeObject) {
+        self.add_object_fake(&object);
+    }
+
+    /// Point an object parameter at the given COMP.
+    pub fn set_object_param(&mut self, name: &str, object: FakeObject) {
+        let id = self.add_object_fake(&object);
+        let name = c_string(name);
+        unsafe { self.inputs.pin_mut().setParObject(name.as_ptr(), id) };
+    }
+}
+
+/// Methods shared by all operator harnesses: the info CHOP/DAT, status strings,
+/// pulses and dynamic menus. Expects the family's generated `Rust*Plugin_methods`
+/// trait to be in scope.
+macro_rules! harness_common {
+    ($harness:ident) => {
+        impl std::ops::Deref for $harness {
+            type Target = $crate::TestHost;
+
+            fn deref(&self) -> &Self::Target {
+                &self.host
+            }
+        }
+
+        impl std::ops::DerefMut for $harness {
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.host
+            }
+        }
+
+        impl $harness {
+            /// Press a pulse parameter.
+            pub fn pulse(&mut self, name: &str) {
+                let name = $crate::host::c_string(name);
+                unsafe { self.plugin.pulsePressed(name.as_ptr()) };
+            }
+
+            /// The entries the operator adds to the dynamic menu `param`.
+            pub fn build_dynamic_menu(&mut self, param: &str) -> Vec<(String, String)> {
+                use autocxx::prelude::*;
+                let param = $crate::host::c_string(param);
+                let mut info = unsafe { $crate::cxx::TestDynamicMenuInfo::new(param.as_ptr()) }
+                    .within_unique_ptr();
+                self.plugin
+                    .buildDynamicMenu(self.host.op_inputs(), info.pin_mut().asMenuInfo());
+                (0..info.numEntries())
+                    .map(|i| {
+                        (
+                            $crate::host::from_c_str(info.entryName(i)),
+                            $crate::host::from_c_str(info.entryLabel(i)),
+                        )
+                    })
+                    .collect()
+            }
+
+            /// The info CHOP channels of the operator.
+            pub fn info_chop(&mut self) -> Vec<(String, f32)> {
+                use autocxx::prelude::*;
+                (0..self.plugin.getNumInfoCHOPChans())
+                    .map(|i| {
+                        let mut name = $crate::cxx::TestString::new().within_unique_ptr();
+                        let mut value = 0.0f32;
+                        self.plugin.getInfoCHOPChan(
+                            i,
+                            name.pin_mut().asString(),
+                            std::pin::Pin::new(&mut value),
+                        );
+                        ($crate::host::from_c_str(name.get()), value)
+                    })
+                    .collect()
+            }
+
+            /// The info DAT of the operator as rows of cells.
+            pub fn info_dat(&mut self) -> Vec<Vec<String>> {
+                use autocxx::prelude::*;
+                let mut size: td_rs_base::cxx::OP_InfoDATSize = unsafe { std::mem::zeroed() };
+                if !self.plugin.getInfoDATSize(std::pin::Pin::new(&mut size)) {
+                    return vec![];
+                }
+                (0..size.rows)
+                    .map(|row| {
+                        (0..size.cols)
+                            .map(|col| {
+                                let mut entry = $crate::cxx::TestString::new().within_unique_ptr();
+                                self.plugin
+                                    .getInfoDATEntry(row, col, entry.pin_mut().asString());
+                                $crate::host::from_c_str(entry.get())
+                            })
+                            .collect()
+                    })
+                    .collect()
+            }
+
+            /// The string shown in the operator's info popup.
+            pub fn info(&mut self) -> String {
+                use autocxx::prelude::*;
+                let mut s = $crate::cxx::TestString::new().within_unique_ptr();
+                self.plugin.getInfoPopupString(s.pin_mut().asString());
+                $crate::host::from_c_str(s.get())
+            }
+
+            /// The operator's current warning.
+            pub fn warning(&mut self) -> String {
+                use autocxx::prelude::*;
+                let mut s = $crate::cxx::TestString::new().within_unique_ptr();
+                self.plugin.getWarningString(s.pin_mut().asString());
+                $crate::host::from_c_str(s.get())
+            }
+
+            /// The operator's current error.
+            pub fn error(&mut self) -> String {
+                use autocxx::prelude::*;
+                let mut s = $crate::cxx::TestString::new().within_unique_ptr();
+                self.plugin.getErrorString(s.pin_mut().asString());
+                $crate::host::from_c_str(s.get())
+            }
+        }
+    };
+}
+
+pub(crate) use harness_common;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use td_rs_base::chop::ChopInput;
+    use td_rs_base::top::PixelFormat;
+    use td_rs_base::OperatorInputs;
+
+    #[test]
+    fn test_chop_layout() {
+        let mut host = TestHost::new();
+        host.set_input_chop(
+            1,
+            FakeChop::new("/project1/in")
+                .sample_rate(120.0)
+                .start_index(30.0)
+                .channel("tx", [1.0, 2.0, 3.0])
+                .channel("ty", [4.0, 5.0, 6.0]),
+        );
+
+        let inputs = OperatorInputs::<ChopInput>::new(host.op_inputs());
+        assert_eq!(inputs.num_inputs(), 2);
+        assert!(inputs.input(0).is_none());
+        let chop = inputs.input(1).unwrap();
+        assert_eq!(chop.num_channels(), 2);
+        assert_eq!(chop.num_samples(), 3);
+        assert_eq!(chop.channel_name(0), "tx");
+        assert_eq!(chop.channel(0), [1.0, 2.0, 3.0]);
+        assert_eq!(chop.channel_name(1), "ty");
+        assert_eq!(chop.channel(1), [4.0, 5.0, 6.0]);
+        assert_eq!(chop.sample_rate(), 120.0);
+        assert_eq!(chop.start_index(), 30.0);
+        assert_eq!(chop.path(), "/project1/in");
+    }
+
+    #[test]
+    fn test_chop_by_path() {
+        let mut host = TestHost::new();
+        host.add_chop(FakeChop::new("/project1/lfo").channel("chan1", [0.5, 1.0]));
+        host.set_chop_param(
+            "Chop",
+            FakeChop::new("/project1/noise").channel("chan1", [0.25]),
+        );
+
+        let inputs = host.op_inputs();
+        let lfo = unsafe { inputs.getCHOP(c"/project1/lfo".as_ptr()) };
+        assert!(!lfo.is_null());
+        let lfo = unsafe { &*lfo };
+        assert_eq!(lfo.numChannels, 1);
+        assert_eq!(lfo.numSamples, 2);
+
+        // Parameters point at their CHOP by path, so it can be looked up too
+        let noise = unsafe { inputs.getCHOP(c"/project1/noise".as_ptr()) };
+        assert!(!noise.is_null());
+        assert_eq!(unsafe { (*noise).numSamples }, 1);
+        let param = unsafe { inputs.getParString(c"Chop".as_ptr()) };
+        assert_eq!(from_c_str(param), "/project1/noise");
+
+        assert!(unsafe { inputs.getCHOP(c"/project1/missing".as_ptr()) }.is_null());
+    }
+
+    #[test]
+    fn test_get_operators_by_path() {
+        let mut host = TestHost::new();
+        host.add_chop(FakeChop::new("/project1/lfo").channel("chan1", [0.5]));
+        host.add_dat(FakeDat::text("/project1/notes", "hello"));
+        host.add_sop(FakeSop::new("/project1/grid").point([0.0, 1.0, 2.0]));
+        host.add_top(FakeTop::new(
+            "/project1/image",
+            2,
+            1,
+            PixelFormat::RGBA8Fixed,
+            [0; 8],
+        ));
+        host.add_object(FakeObject::new("/project1/cam").translate([1.0, 2.0, 3.0]));
+        let inputs = OperatorInputs::<ChopInput>::new(host.op_inputs());
+
+        assert_eq!(
+            inputs.get_chop("/project1/lfo").map(|chop| chop.channel(0)),
+            Some(&[0.5][..])
+        );
+        assert_eq!(
+            inputs.get_dat("/project1/notes").map(|dat| dat.text()),
+            Some("hello")
+        );
+        assert_eq!(
+            inputs.get_sop("/project1/grid").map(|sop| sop.num_points()),
+            Some(1)
+        );
+        assert_eq!(
+            inputs
+                .get_top("/project1/image")
+                .map(|top| top.texture_desc().width),
+            Some(2)
+        );
+        assert_eq!(
+            inputs
+                .get_object("/project1/cam")
+                .map(|object| object.world_position()),
+            Some([1.0, 2.0, 3.0])
+        );
+
+        assert!(inputs.get_chop("/project1/missing").is_none());
+        assert!(inputs.get_dat("/project1/missing").is_none());
+        assert!(inputs.get_sop("/project1/missing").is_none());
+        assert!(inputs.get_top("/project1/missing").is_none());
+        assert!(inputs.get_object("/project1/missing").is_none());
+
+        // A path only resolves to an operator of its own family
+        assert!(inputs.get_dat("/project1/lfo").is_none());
+        assert!(inputs.get_chop("/project1/notes").is_none());
+        assert!(inputs.get_top("/project1/grid").is_none());
+        assert!(inputs.get_sop("/project1/image").is_none());
+        assert!(inputs.get_chop("/project1/cam").is_none());
+    }
+}
@@ -0,0 +1,46 @@
+//! A headless stand-in for TouchDesigner, for unit testing operators.
+//!
+//! A harness creates the operator through [`td_rs_base::OpNew`] (or `TopNew`),
+//! registers its parameters against a recording parameter manager and runs
+//! cooks through the same `Rust*PluginImpl` glue TouchDesigner calls into, so
+//! the results can be asserted on directly:
+//!
+//! ```ignore
+//! let mut harness = ChopHarness::new::<FilterChop>();
+//! harness.set_float("Scale", 2.0);
+//! harness.set_input_chop(0, FakeChop::new("/project1/in").channel("chan1", [1.0, 2.0]));
+//! let out = harness.cook();
+//! assert_eq!(out.channels[0].1, vec![2.0, 4.0]);
+//! ```
+//!
+//! Each operator family is behind a feature of the same name.
+
+#![cfg_attr(
+    not(any(feature = "chop", feature = "dat", feature = "sop", feature = "top")),
+    allow(dead_code, unused_imports, unused_macros)
+)]
+
+pub mod cxx;
+mod fake;
+mod host;
+
+#[cfg(feature = "chop")]
+mod chop;
+#[cfg(feature = "dat")]
+mod dat;
+#[cfg(feature = "sop")]
+mod sop;
+#[cfg(feature = "top")]
+mod top;
+
+pub use fake::*;
+pub use host::{RegisteredParam, TestHost};
+
+#[cfg(feature = "chop")]
+pub use chop::*;
+#[cfg(feature = "dat")]
+pub use dat::*;
+#[cfg(feature = "sop")]
+pub use sop::*;
+#[cfg(feature = "top")]
+pub use top::*;
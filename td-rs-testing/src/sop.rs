@@ -0,0 +1,122 @@
+use crate::cxx::{TestPrimitiveKind, TestSopOutput, TestSopVboOutput};
+use crate::host::harness_common;
+use crate::TestHost;
+use autocxx::prelude::*;
+use std::pin::Pin;
+use td_rs_sop::cxx::{
+    RustSopPluginImpl, RustSopPlugin_methods, SOP_GeneralInfo, SOP_Output, SOP_VBOOutput,
+};
+use td_rs_sop::{OpNew, Sop};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PrimitiveKind {
+    Triangle,
+    Line,
+    ParticleSystem,
+}
+
+/// A primitive and the point indices it was built from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CookedPrimitive {
+    pub kind: PrimitiveKind,
+    pub indices: Vec<u32>,
+}
+
+/// The geometry produced by a SOP cook, from either `execute` or `execute_vbo`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CookedSop {
+    pub points: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub colors: Vec<[f32; 4]>,
+    /// Texture coordinates, `tex_coord_layers` entries per point.
+    pub tex_coords: Vec<[f32; 3]>,
+    pub tex_coord_layers: usize,
+    pub primitives: Vec<CookedPrimitive>,
+}
+
+fn chunks<const N: usize>(data: *const f32, len: usize) -> Vec<[f32; N]> {
+    if len == 0 {
+        return vec![];
+    }
+    let data = unsafe { std::slice::from_raw_parts(data, len * N) };
+    data.chunks_exact(N)
+        .map(|c| std::array::from_fn(|i| c[i]))
+        .collect()
+}
+
+// Both SOP outputs expose the same read-back interface.
+macro_rules! read_geometry {
+    ($output:expr) => {{
+        let output = $output;
+        let indices = output.indexData();
+        CookedSop {
+            points: chunks::<3>(output.pointData(), output.numPoints() as usize),
+            normals: chunks::<3>(output.normalData(), output.numNormals() as usize),
+            colors: chunks::<4>(output.colorData(), output.numColors() as usize),
+            tex_coords: chunks::<3>(output.texCoordData(), output.numTexCoords() as usize),
+            tex_coord_layers: output.numTexCoordLayers() as usize,
+            primitives: (0..output.numPrims())
+                .map(|i| {
+                    let offset = output.primitiveOffset(i) as usize;
+                    let count = output.primitiveCount(i) as usize;
+                    CookedPrimitive {
+                        kind: match output.primitiveKind(i) {
+                            TestPrimitiveKind::Triangle => PrimitiveKind::Triangle,
+                            TestPrimitiveKind::Line => PrimitiveKind::Line,
+                            TestPrimitiveKind::ParticleSystem => PrimitiveKind::ParticleSystem,
+                        },
+                        indices: unsafe { std::slice::from_raw_parts(indices.add(offset), count) }
+                            .iter()
+                            .map(|i| *i as u32)
+                            .collect(),
+                    }
+                })
+                .collect(),
+        }
+    }};
+}
+
+/// Drives a [`Sop`] through the same glue TouchDesigner calls into.
+pub struct SopHarness {
+    plugin: RustSopPluginImpl,
+    host: TestHost,
+}
+
+impl SopHarness {
+    /// Create the operator and register its parameters.
+    pub fn new<T: Sop + OpNew + 'static>() -> Self {
+        let mut host = TestHost::new();
+        let mut plugin = RustSopPluginImpl::new(Box::new(T::new(host.node_info())));
+        plugin.setupParameters(host.parameter_manager());
+        Self { plugin, host }
+    }
+
+    /// Run a cook in the order TouchDesigner does: general info, then
+    /// `execute_vbo` if the operator asked to go direct to GPU, or `execute`
+    /// otherwise.
+    pub fn cook(&mut self) -> CookedSop {
+        self.host.begin_cook();
+        let inputs = self.host.op_inputs();
+
+        let mut general: SOP_GeneralInfo = unsafe { std::mem::zeroed() };
+        self.plugin.getGeneralInfo(Pin::new(&mut general), inputs);
+
+        if general.directToGPU {
+            let mut output = TestSopVboOutput::new().within_unique_ptr();
+            let out = unsafe {
+                Pin::new_unchecked(&mut *(output.pin_mut().asOutput() as *mut SOP_VBOOutput))
+            };
+            self.plugin.executeVBO(out, inputs);
+            read_geometry!(&*output)
+        } else {
+            let mut output = TestSopOutput::new().within_unique_ptr();
+            let out = unsafe {
+                Pin::new_unchecked(&mut *(output.pin_mut().asOutput() as *mut SOP_Output))
+            };
+            self.plugin.execute(out, inputs);
+            read_geometry!(&*output)
+        }
+    }
+}
+
+harness_common!(SopHarness);
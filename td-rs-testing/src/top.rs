@@ -0,0 +1,99 @@
+use crate::cxx::{TestTopContext, TestTopOutput};
+use crate::host::harness_common;
+use crate::TestHost;
+use autocxx::prelude::*;
+use std::pin::Pin;
+use td_rs_base::top::PixelFormat;
+use td_rs_top::cxx::{
+    RustTopPluginImpl, RustTopPlugin_methods, TOP_Context, TOP_GeneralInfo, TOP_Output,
+};
+use td_rs_top::{Top, TopContext, TopNew};
+
+/// A buffer uploaded by the operator during a cook.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Upload {
+    pub width: u32,
+    pub height: u32,
+    pub pixel_format: PixelFormat,
+    pub color_buffer_index: u32,
+    /// The buffer contents from the upload's offset onwards.
+    pub data: Vec<u8>,
+}
+
+/// The uploads produced by a TOP cook.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CookedTop {
+    pub uploads: Vec<Upload>,
+}
+
+impl CookedTop {
+    /// The last upload to the given color buffer, i.e. what the TOP will show.
+    pub fn color_buffer(&self, index: u32) -> Option<&Upload> {
+        self.uploads
+            .iter()
+            .rev()
+            .find(|u| u.color_buffer_index == index)
+    }
+}
+
+/// Drives a [`Top`] through the same glue TouchDesigner calls into. Only the
+/// CPU memory execute mode is supported.
+pub struct TopHarness {
+    plugin: RustTopPluginImpl,
+    host: TestHost,
+    // Declared after `plugin` so it outlives the `TopContext` the operator holds.
+    _context: UniquePtr<TestTopContext>,
+}
+
+impl TopHarness {
+    /// Create the operator and register its parameters.
+    pub fn new<T: Top + TopNew + 'static>() -> Self {
+        let mut host = TestHost::new();
+        let mut context = TestTopContext::new().within_unique_ptr();
+        // SAFETY: the context is owned by the harness and dropped after the plugin.
+        let top_context = unsafe {
+            TopContext::new(Pin::new_unchecked(
+                &mut *(context.pin_mut().asContext() as *mut TOP_Context),
+            ))
+        };
+        let mut plugin = RustTopPluginImpl::new(Box::new(T::new(host.node_info(), top_context)));
+        plugin.setupParameters(host.parameter_manager());
+        Self {
+            plugin,
+            host,
+            _context: context,
+        }
+    }
+
+    /// Run a cook in the order TouchDesigner does: general info, execute.
+    pub fn cook(&mut self) -> CookedTop {
+        self.host.begin_cook();
+        let inputs = self.host.op_inputs();
+
+        let mut general: TOP_GeneralInfo = unsafe { std::mem::zeroed() };
+        self.plugin.getGeneralInfo(Pin::new(&mut general), inputs);
+
+        let mut output = TestTopOutput::new().within_unique_ptr();
+        let out =
+            unsafe { Pin::new_unchecked(&mut *(output.pin_mut().asOutput() as *mut TOP_Output)) };
+        self.plugin.execute(out, inputs);
+
+        CookedTop {
+            uploads: (0..output.numUploads())
+                .map(|i| Upload {
+                    width: output.uploadWidth(i),
+                    height: output.uploadHeight(i),
+                    pixel_format: PixelFormat::from(&output.uploadPixelFormat(i)),
+                    color_buffer_index: output.uploadColorBufferIndex(i),
+                    data: match output.uploadSize(i) as usize {
+                        0 => vec![],
+                        size => unsafe { std::slice::from_raw_parts(output.uploadData(i), size) }
+                            .to_vec(),
+                    },
+                })
+                .collect(),
+        }
+    }
+}
+
+harness_common!(TopHarness);
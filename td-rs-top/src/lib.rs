@@ -207,8 +207,20 @@ pub trait Top: Op {
         TopGeneralInfo::default()
     }
 
+    /// Fallible version of [`Top::general_info`].
+    fn try_general_info(&self, input: &OperatorInputs<TopInput>) -> CookResult<TopGeneralInfo> {
+        Ok(self.general_info(input))
+    }
+
     fn execute(&mut self, _output: TopOutput, _input: &OperatorInputs<TopInput>) {}
 
+    /// Fallible version of [`Top::execute`]. On error nothing is uploaded and
+    /// the error is shown on the node.
+    fn try_execute(&mut self, output: TopOutput, input: &OperatorInputs<TopInput>) -> CookResult {
+        self.execute(output, input);
+        Ok(())
+    }
+
     fn build_dynamic_menu(
         &mut self,
         inputs: &OperatorInputs<TopInput>,
@@ -217,6 +229,12 @@ pub trait Top: Op {
     }
 }
 
+impl Top for FailedOp {
+    fn try_execute(&mut self, _output: TopOutput, _input: &OperatorInputs<TopInput>) -> CookResult {
+        self.fail()
+    }
+}
+
 #[macro_export]
 macro_rules! top_plugin {
     ($plugin_ty:ty) => {
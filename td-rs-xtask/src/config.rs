@@ -13,12 +13,22 @@ pub struct MacOsConfig {
     pub(crate) plugin_folder: String,
 }
 
+#[cfg(target_os = "linux")]
+#[derive(serde::Deserialize, Debug)]
+pub struct LinuxConfig {
+    pub(crate) plugin_folder: String,
+    /// Discovered with `python3-config` if not set.
+    pub(crate) python_include_dir: Option<String>,
+}
+
 #[derive(serde::Deserialize, Debug)]
 pub struct Config {
     #[cfg(target_os = "windows")]
     pub(crate) windows: WindowsConfig,
     #[cfg(target_os = "macos")]
     pub(crate) macos: MacOsConfig,
+    #[cfg(target_os = "linux")]
+    pub(crate) linux: LinuxConfig,
 }
 
 pub(crate) fn read_config() -> Config {
@@ -56,6 +66,19 @@ fn process_config(mut config: Config) -> Config {
         );
         config.macos.plugin_folder = plugin_folder;
     }
+    #[cfg(target_os = "linux")]
+    if config.linux.plugin_folder.contains("$HOME") {
+        let home_dir = homedir::get_my_home()
+            .expect("Could not get home directory")
+            .expect("Could not get home directory");
+        let plugin_folder = config.linux.plugin_folder.replace(
+            "$HOME",
+            home_dir
+                .to_str()
+                .expect("Could not convert home directory to string"),
+        );
+        config.linux.plugin_folder = plugin_folder;
+    }
 
     config
 }
@@ -1,4 +1,6 @@
 mod config;
+#[cfg(target_os = "linux")]
+mod linux;
 #[cfg(target_os = "macos")]
 mod macos;
 mod metadata;
@@ -6,6 +8,8 @@ mod util;
 #[cfg(target_os = "windows")]
 mod windows;
 
+#[cfg(target_os = "linux")]
+use crate::linux::{build_plugin, install_plugin};
 #[cfg(target_os = "macos")]
 use crate::macos::{build_plugin, install_plugin};
 #[cfg(target_os = "windows")]
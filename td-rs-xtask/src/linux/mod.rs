@@ -0,0 +1,131 @@
+use crate::config::Config;
+use crate::metadata::PluginType;
+use crate::util::ToTitleCase;
+use crate::{build, PLUGIN_HOME};
+use anyhow::Context;
+use fs_extra::dir::CopyOptions;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub(crate) fn install_plugin(
+    config: &Config,
+    plugin: &str,
+    _plugin_type: PluginType,
+) -> anyhow::Result<()> {
+    let plugin = &plugin.replace('-', "_");
+    let plugin_target_path = plugin_target_path(plugin);
+    let td_plugin_folder = &config.linux.plugin_folder;
+    std::fs::create_dir_all(td_plugin_folder).context("Could not create plugin directory")?;
+    println!(
+        "Installing plugin {:?} to {}",
+        plugin_target_path, td_plugin_folder
+    );
+    fs_extra::copy_items(
+        &[&plugin_target_path],
+        td_plugin_folder,
+        &CopyOptions::new().overwrite(true),
+    )?;
+    Ok(())
+}
+
+pub(crate) fn build_plugin(
+    config: &Config,
+    plugin: &str,
+    plugin_type: PluginType,
+) -> anyhow::Result<()> {
+    if crate::metadata::is_cuda_enabled(plugin, &plugin_type) {
+        anyhow::bail!("CUDA plugins are not supported on Linux");
+    }
+
+    let target = if cfg!(target_arch = "x86_64") {
+        "x86_64-unknown-linux-gnu"
+    } else {
+        "aarch64-unknown-linux-gnu"
+    };
+    build(
+        &[plugin, plugin_type.to_plugin_name()],
+        &["--release", &format!("--target={target}")],
+    )?;
+
+    let is_python_enabled = crate::metadata::is_python_enabled(plugin, &plugin_type);
+    let plugin = &plugin.replace('-', "_");
+    println!("Linking shared object");
+    link_plugin(config, target, plugin, &plugin_type, is_python_enabled)?;
+    Ok(())
+}
+
+fn plugin_target_path(plugin: &str) -> PathBuf {
+    Path::new(PLUGIN_HOME)
+        .join(plugin)
+        .join(format!("lib{plugin}.so"))
+}
+
+/// Compile the plugin shim for the operator family and link it against the
+/// plugin staticlib, which already bundles the operator and base crates, into a
+/// shared object.
+fn link_plugin(
+    config: &Config,
+    target: &str,
+    plugin: &str,
+    plugin_type: &PluginType,
+    is_python_enabled: bool,
+) -> anyhow::Result<()> {
+    let short_title = plugin_type.to_short_name().to_title_case();
+    let op_path = plugin_type.to_plugin_name();
+    let lib_dir = format!("target/{target}/release");
+
+    let plugin_target_path = plugin_target_path(plugin);
+    std::fs::create_dir_all(plugin_target_path.parent().unwrap())
+        .context("Could not create plugin directory")?;
+
+    let cxx = std::env::var("CXX").unwrap_or_else(|_| "c++".to_string());
+    let mut cmd = Command::new(&cxx);
+    cmd.arg("-std=c++17")
+        .arg("-O2")
+        .arg("-fPIC")
+        .arg("-shared")
+        .arg("-Itd-rs-base/src")
+        .arg(format!("-I{op_path}/src"));
+
+    if is_python_enabled {
+        // Python symbols are left undefined, they are provided by the host process
+        cmd.arg("-DPYTHON_ENABLED");
+        for dir in python_include_dirs(config)? {
+            cmd.arg(format!("-I{dir}"));
+        }
+    }
+
+    cmd.arg(format!("{op_path}/src/Rust{short_title}Plugin.cpp"))
+        .arg(format!("{lib_dir}/lib{plugin}.a"))
+        .args(["-lpthread", "-ldl", "-lm"])
+        .arg("-o")
+        .arg(&plugin_target_path);
+
+    let status = cmd
+        .spawn()
+        .with_context(|| format!("Could not call {cxx}"))?
+        .wait()?;
+    if !status.success() {
+        anyhow::bail!("Could not link {:?}", plugin_target_path);
+    }
+    Ok(())
+}
+
+fn python_include_dirs(config: &Config) -> anyhow::Result<Vec<String>> {
+    if let Some(dir) = &config.linux.python_include_dir {
+        return Ok(vec![dir.clone()]);
+    }
+
+    let output = Command::new("python3-config")
+        .arg("--includes")
+        .output()
+        .context("Could not call python3-config, set python_include_dir in td-rs.toml")?;
+    if !output.status.success() {
+        anyhow::bail!("python3-config --includes failed");
+    }
+    Ok(String::from_utf8(output.stdout)?
+        .split_whitespace()
+        .filter_map(|flag| flag.strip_prefix("-I"))
+        .map(str::to_string)
+        .collect())
+}